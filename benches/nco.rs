@@ -0,0 +1,95 @@
+//! 振荡器基准测试：相位累加器NCO与原先基于asin的相位重建对比，以及整幅图像调制
+//!
+//! 运行：`cargo bench --bench nco`
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use image::{DynamicImage, Rgb, RgbImage};
+use sstv_rust::{Nco, SineMode, SstvMode, SstvModulator};
+use std::f64::consts::PI;
+
+const SAMPLE_RATE: u32 = 48000;
+// 像素音调：Martin M1约0.4576ms/像素，48kHz下约22个样本
+const TONE_SAMPLES: usize = 22;
+const TONES: usize = 2048;
+
+fn tone_frequencies() -> Vec<f64> {
+    (0..TONES).map(|i| 1500.0 + ((i * 37) % 256) as f64 * 3.1372549).collect()
+}
+
+/// 原实现：每个音调由上一个样本的正弦值和余弦符号用asin恢复相位
+fn legacy_tones(frequencies: &[f64], out: &mut Vec<f32>) {
+    let mut older_data = 0.0f64;
+    let mut older_cos = 1.0f64;
+    for &frequency in frequencies {
+        let sign = if older_cos >= 0.0 { 1.0 } else { -1.0 };
+        let phi = sign * older_data.asin() + (sign - 1.0f64).abs() / 2.0 * PI;
+        for i in 0..TONE_SAMPLES {
+            let phase = 2.0 * PI * frequency * i as f64 / SAMPLE_RATE as f64 + phi;
+            out.push(phase.sin() as f32);
+        }
+        let phase = 2.0 * PI * frequency * TONE_SAMPLES as f64 / SAMPLE_RATE as f64 + phi;
+        older_data = phase.sin();
+        older_cos = phase.cos();
+    }
+}
+
+fn nco_tones(nco: &mut Nco, frequencies: &[f64], out: &mut Vec<f32>) {
+    let mut block = [0.0f32; TONE_SAMPLES];
+    for &frequency in frequencies {
+        let word = nco.frequency_word(frequency);
+        nco.fill(word, &mut block);
+        out.extend_from_slice(&block);
+    }
+}
+
+fn bench_oscillators(c: &mut Criterion) {
+    let frequencies = tone_frequencies();
+    let mut group = c.benchmark_group("pixel_tones");
+    group.throughput(Throughput::Elements((TONES * TONE_SAMPLES) as u64));
+
+    group.bench_function("legacy_asin", |b| {
+        let mut out = Vec::with_capacity(TONES * TONE_SAMPLES);
+        b.iter(|| {
+            out.clear();
+            legacy_tones(black_box(&frequencies), &mut out);
+            black_box(out.len())
+        })
+    });
+    for mode in [SineMode::Exact, SineMode::LookupTable] {
+        group.bench_with_input(BenchmarkId::new("nco", format!("{:?}", mode)), &mode, |b, &mode| {
+            let mut nco = Nco::new(SAMPLE_RATE).with_mode(mode);
+            let mut out = Vec::with_capacity(TONES * TONE_SAMPLES);
+            b.iter(|| {
+                out.clear();
+                nco_tones(&mut nco, black_box(&frequencies), &mut out);
+                black_box(out.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_modulate(c: &mut Criterion) {
+    let image = DynamicImage::ImageRgb8(RgbImage::from_fn(320, 256, |x, y| {
+        Rgb([x as u8, y as u8, (x ^ y) as u8])
+    }));
+    let mut group = c.benchmark_group("modulate_martin_m1");
+    group.sample_size(10);
+    for mode in [SineMode::Exact, SineMode::LookupTable] {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{:?}", mode)), &mode, |b, &mode| {
+            let mut modulator = SstvModulator::new(SstvMode::MartinM1)
+                .with_sample_rate(SAMPLE_RATE).unwrap()
+                .with_sine_mode(mode);
+            let mut samples: Vec<f32> = Vec::new();
+            b.iter(|| {
+                samples.clear();
+                modulator.modulate_image_to(black_box(&image), &mut samples).unwrap();
+                black_box(samples.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_oscillators, bench_modulate);
+criterion_main!(benches);
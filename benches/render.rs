@@ -0,0 +1,38 @@
+//! 扫描块并行生成基准测试：串行与rayon并行对比
+//!
+//! 运行：`cargo bench --bench render`，用 `RAYON_NUM_THREADS` 控制线程数观察扩展性。
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use image::{DynamicImage, Rgb, RgbImage};
+use sstv_rust::{SstvMode, SstvModulator};
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn bench_render(c: &mut Criterion) {
+    // 大尺寸源图像，缩放后按各模式分辨率生成
+    let image = DynamicImage::ImageRgb8(RgbImage::from_fn(800, 616, |x, y| {
+        Rgb([x as u8, y as u8, (x ^ y) as u8])
+    }));
+    let mut group = c.benchmark_group("render");
+    group.sample_size(10);
+    for mode in [SstvMode::Pd120, SstvMode::ScottieDx] {
+        for parallel in [false, true] {
+            let name = if parallel { "parallel" } else { "serial" };
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", mode), name), &parallel, |b, &parallel| {
+                let mut modulator = SstvModulator::new(mode)
+                    .with_sample_rate(SAMPLE_RATE).unwrap()
+                    .with_parallel_rendering(parallel);
+                let mut samples: Vec<f32> = Vec::new();
+                b.iter(|| {
+                    samples.clear();
+                    modulator.modulate_image_to(black_box(&image), &mut samples).unwrap();
+                    black_box(samples.len())
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
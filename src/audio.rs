@@ -1,9 +1,11 @@
 //! 音频处理和WAV文件生成模块
 //! 
-//! 本模块提供音频信号生成和WAV文件输出功能。
+//! 本模块提供音频信号生成、样本输出端（sink）和WAV文件输出功能。
 
 use crate::error::{Result, SstvError};
 use hound::{WavSpec, WavWriter as HoundWavWriter};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
 use std::path::Path;
 
 /// 音频生成器
@@ -15,11 +17,11 @@ pub struct AudioGenerator {
 impl AudioGenerator {
     /// 创建新的音频生成器
     pub fn new(sample_rate: u32, bit_depth: u16) -> Result<Self> {
-        if sample_rate < 8000 || sample_rate > 192000 {
-            return Err(SstvError::invalid_sample_rate(sample_rate, 8000, 192000));
+        if !(crate::MIN_SAMPLE_RATE..=crate::MAX_SAMPLE_RATE).contains(&sample_rate) {
+            return Err(SstvError::invalid_sample_rate(sample_rate, crate::MIN_SAMPLE_RATE, crate::MAX_SAMPLE_RATE));
         }
 
-        if bit_depth != 16 && bit_depth != 24 && bit_depth != 32 {
+        if PcmFormat::from_bit_depth(bit_depth).is_none() {
             return Err(SstvError::InvalidAudioParameter {
                 parameter: "bit_depth".to_string(),
                 value: bit_depth.to_string(),
@@ -81,11 +83,18 @@ impl AudioGenerator {
     pub fn bit_depth(&self) -> u16 {
         self.bit_depth
     }
+
+    /// 获取位深度对应的整数PCM格式
+    pub fn pcm_format(&self) -> PcmFormat {
+        PcmFormat::from_bit_depth(self.bit_depth).unwrap_or_default()
+    }
 }
 
 /// 音频处理器 - 用于收集和处理音频样本
+///
+/// 样本以归一化浮点数（-1.0 ~ 1.0）存储，导出时再按目标位深度量化。
 pub struct AudioProcessor {
-    samples: Vec<i16>,
+    samples: Vec<f32>,
     sample_rate: u32,
 }
 
@@ -98,16 +107,28 @@ impl AudioProcessor {
         }
     }
 
-    /// 添加音频样本
-    pub fn add_sample(&mut self, sample: i16) {
+    /// 添加音频样本（-1.0 ~ 1.0；旧版本为 `i16`，除以32767.0即可换算）
+    pub fn add_sample(&mut self, sample: f32) {
         self.samples.push(sample);
     }
 
     /// 获取所有样本
-    pub fn get_samples(&self) -> &[i16] {
+    ///
+    /// 返回归一化浮点样本；旧版本返回 `&[i16]`，需要16位整数时使用 [`AudioProcessor::to_i16`]。
+    pub fn get_samples(&self) -> &[f32] {
         &self.samples
     }
 
+    /// 获取可修改的样本（用于原地滤波等后处理）
+    pub fn get_samples_mut(&mut self) -> &mut [f32] {
+        &mut self.samples
+    }
+
+    /// 获取量化为16位整数的样本（不加抖动）
+    pub fn to_i16(&self) -> Vec<i16> {
+        self.samples.iter().map(|&sample| quantize_i16(sample)).collect()
+    }
+
     /// 清空样本
     pub fn clear(&mut self) {
         self.samples.clear();
@@ -119,27 +140,690 @@ impl AudioProcessor {
     }
 }
 
+/// PCM样本格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PcmFormat {
+    /// 16位整数（默认）
+    #[default]
+    Int16,
+    /// 24位整数
+    Int24,
+    /// 32位整数
+    Int32,
+    /// 32位IEEE浮点
+    Float32,
+}
+
+impl PcmFormat {
+    /// 根据位深度创建整数格式
+    pub fn from_bit_depth(bit_depth: u16) -> Option<Self> {
+        match bit_depth {
+            16 => Some(PcmFormat::Int16),
+            24 => Some(PcmFormat::Int24),
+            32 => Some(PcmFormat::Int32),
+            _ => None,
+        }
+    }
+
+    /// 每个样本的位数
+    pub fn bits_per_sample(&self) -> u16 {
+        match self {
+            PcmFormat::Int16 => 16,
+            PcmFormat::Int24 => 24,
+            PcmFormat::Int32 | PcmFormat::Float32 => 32,
+        }
+    }
+
+    /// 每个样本的字节数
+    pub fn bytes_per_sample(&self) -> usize {
+        self.bits_per_sample() as usize / 8
+    }
+
+    /// 是否为浮点格式
+    pub fn is_float(&self) -> bool {
+        matches!(self, PcmFormat::Float32)
+    }
+
+    /// 整数格式的满幅值
+    fn full_scale(&self) -> f64 {
+        match self {
+            PcmFormat::Int16 => i16::MAX as f64,
+            PcmFormat::Int24 => 8_388_607.0,
+            PcmFormat::Int32 => i32::MAX as f64,
+            PcmFormat::Float32 => 1.0,
+        }
+    }
+
+    /// 对应的WAV规格
+    pub fn wav_spec(&self, sample_rate: u32, channels: u16) -> WavSpec {
+        WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: self.bits_per_sample(),
+            sample_format: if self.is_float() {
+                hound::SampleFormat::Float
+            } else {
+                hound::SampleFormat::Int
+            },
+        }
+    }
+}
+
+/// 音频文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    /// WAV（RIFF）文件
+    #[default]
+    Wav,
+    /// FLAC无损压缩（仅16/24位整数样本）
+    Flac,
+    /// 无文件头的原始小端PCM（如s16le、f32le），样本格式由 [`PcmFormat`] 决定
+    Raw,
+}
+
+impl AudioFormat {
+    /// 格式名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Raw => "raw",
+        }
+    }
+
+    /// 默认文件扩展名
+    pub fn extension(&self) -> &'static str {
+        self.name()
+    }
+
+    /// 根据文件扩展名识别格式（不区分大小写）
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "wav" | "wave" => Some(AudioFormat::Wav),
+            "flac" => Some(AudioFormat::Flac),
+            "raw" | "pcm" | "s16" | "s24" | "s32" | "f32" => Some(AudioFormat::Raw),
+            _ => None,
+        }
+    }
+
+    /// 根据文件路径的扩展名识别格式
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// 当前构建是否支持导出该格式（FLAC需要启用 `flac` 特性）
+    pub fn is_available(&self) -> bool {
+        *self != AudioFormat::Flac || cfg!(feature = "flac")
+    }
+
+    /// 当前构建不支持该格式时返回 `InvalidFormat`
+    pub fn ensure_available(&self) -> Result<()> {
+        if self.is_available() {
+            Ok(())
+        } else {
+            Err(SstvError::InvalidFormat(format!("导出{}需要启用 `{}` 特性", self.name().to_uppercase(), self.name())))
+        }
+    }
+}
+
+/// 量化抖动方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dither {
+    /// 不加抖动，直接截断（与旧版本输出一致）
+    #[default]
+    None,
+    /// 三角概率密度（TPDF）抖动，幅度为±1 LSB
+    Tpdf,
+}
+
+/// 把归一化样本量化为16位整数（截断，不加抖动）
+pub fn quantize_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) as f64 * i16::MAX as f64) as i16
+}
+
+/// 样本量化器
+///
+/// 把归一化浮点样本转换为目标格式的整数值，抖动使用固定种子的伪随机数，
+/// 同样的输入总是得到同样的输出。
+#[derive(Debug, Clone)]
+pub struct Quantizer {
+    format: PcmFormat,
+    dither: Dither,
+    rng_state: u32,
+}
+
+impl Quantizer {
+    /// 创建量化器
+    pub fn new(format: PcmFormat, dither: Dither) -> Self {
+        Self {
+            format,
+            dither,
+            rng_state: 0x9E37_79B9,
+        }
+    }
+
+    /// 获取目标格式
+    pub fn format(&self) -> PcmFormat {
+        self.format
+    }
+
+    /// 生成 [0, 1) 区间的均匀随机数（xorshift32）
+    fn next_uniform(&mut self) -> f64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        x as f64 / (u32::MAX as f64 + 1.0)
+    }
+
+    /// 把样本量化为整数值（浮点格式返回0，请使用 `encode`）
+    pub fn quantize(&mut self, sample: f32) -> i32 {
+        let full_scale = self.format.full_scale();
+        let value = sample.clamp(-1.0, 1.0) as f64 * full_scale;
+        match self.dither {
+            Dither::None => value as i32,
+            Dither::Tpdf => {
+                let noise = self.next_uniform() - self.next_uniform();
+                (value + noise).round().clamp(-full_scale - 1.0, full_scale) as i32
+            }
+        }
+    }
+
+    /// 把样本按小端字节序编码追加到缓冲区
+    pub fn encode(&mut self, sample: f32, out: &mut Vec<u8>) {
+        match self.format {
+            PcmFormat::Int16 => out.extend_from_slice(&(self.quantize(sample) as i16).to_le_bytes()),
+            PcmFormat::Int24 => out.extend_from_slice(&self.quantize(sample).to_le_bytes()[..3]),
+            PcmFormat::Int32 => out.extend_from_slice(&self.quantize(sample).to_le_bytes()),
+            PcmFormat::Float32 => out.extend_from_slice(&sample.to_le_bytes()),
+        }
+    }
+}
+
+/// 非信号声道的内容
+///
+/// 序列化时以 `type` 字段区分，例如 `{"type": "pilot_tone", "frequency": 1000.0, "amplitude": 0.5}`。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuxChannel {
+    /// 复制SSTV信号
+    Mirror,
+    /// 静音
+    Silent,
+    /// 连续导频音，在整段输出期间保持（用于VOX/PTT键控）
+    PilotTone {
+        /// 导频频率（Hz）
+        frequency: f64,
+        /// 导频幅度（0.0 ~ 1.0）
+        amplitude: f32,
+    },
+}
+
+/// 声道路由配置
+///
+/// 序列化为 `{"channels", "signal_channel", "aux"}`；反序列化时按 [`ChannelRouting::new`] 校验。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ChannelRoutingRepr")]
+pub struct ChannelRouting {
+    channels: u16,
+    signal_channel: u16,
+    aux: AuxChannel,
+}
+
+/// `ChannelRouting` 未经校验的序列化形式
+#[derive(Deserialize)]
+struct ChannelRoutingRepr {
+    channels: u16,
+    signal_channel: u16,
+    aux: AuxChannel,
+}
+
+impl TryFrom<ChannelRoutingRepr> for ChannelRouting {
+    type Error = SstvError;
+
+    fn try_from(repr: ChannelRoutingRepr) -> Result<Self> {
+        Self::new(repr.channels, repr.signal_channel, repr.aux)
+    }
+}
+
+impl Default for ChannelRouting {
+    fn default() -> Self {
+        Self::mono()
+    }
+}
+
+impl ChannelRouting {
+    /// 最大支持的声道数
+    pub const MAX_CHANNELS: u16 = 8;
+
+    /// 创建声道路由
+    ///
+    /// * `channels` - 总声道数（1-8）
+    /// * `signal_channel` - SSTV信号所在声道（从0开始，0为左声道）
+    /// * `aux` - 其他声道的内容
+    pub fn new(channels: u16, signal_channel: u16, aux: AuxChannel) -> Result<Self> {
+        if channels == 0 || channels > Self::MAX_CHANNELS {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "channels".to_string(),
+                value: channels.to_string(),
+            });
+        }
+        if signal_channel >= channels {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "signal_channel".to_string(),
+                value: signal_channel.to_string(),
+            });
+        }
+        if let AuxChannel::PilotTone { frequency, amplitude } = aux
+            && (!frequency.is_finite() || frequency <= 0.0 || !(0.0..=1.0).contains(&amplitude))
+        {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "pilot_tone".to_string(),
+                value: format!("{}Hz@{}", frequency, amplitude),
+            });
+        }
+
+        Ok(Self {
+            channels,
+            signal_channel,
+            aux,
+        })
+    }
+
+    /// 单声道
+    pub fn mono() -> Self {
+        Self {
+            channels: 1,
+            signal_channel: 0,
+            aux: AuxChannel::Silent,
+        }
+    }
+
+    /// 立体声，SSTV信号在左声道，右声道为指定内容
+    pub fn stereo_left(aux: AuxChannel) -> Self {
+        Self {
+            channels: 2,
+            signal_channel: 0,
+            aux,
+        }
+    }
+
+    /// 立体声，SSTV信号在右声道，左声道为指定内容
+    pub fn stereo_right(aux: AuxChannel) -> Self {
+        Self {
+            channels: 2,
+            signal_channel: 1,
+            aux,
+        }
+    }
+
+    /// 总声道数
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// SSTV信号所在声道
+    pub fn signal_channel(&self) -> u16 {
+        self.signal_channel
+    }
+
+    /// 其他声道的内容
+    pub fn aux(&self) -> AuxChannel {
+        self.aux
+    }
+}
+
+/// 声道混合器：把单声道SSTV样本展开为交错的多声道帧
+#[derive(Debug, Clone)]
+pub struct ChannelMixer {
+    routing: ChannelRouting,
+    sample_rate: u32,
+    pilot_phase: f64,
+}
+
+impl ChannelMixer {
+    /// 创建声道混合器
+    pub fn new(routing: ChannelRouting, sample_rate: u32) -> Self {
+        Self {
+            routing,
+            sample_rate,
+            pilot_phase: 0.0,
+        }
+    }
+
+    /// 获取声道路由
+    pub fn routing(&self) -> &ChannelRouting {
+        &self.routing
+    }
+
+    /// 依次为一帧中的每个声道调用 `write`
+    pub fn write_frame<F>(&mut self, sample: f32, mut write: F) -> Result<()>
+    where
+        F: FnMut(f32) -> Result<()>,
+    {
+        let aux = match self.routing.aux {
+            AuxChannel::Mirror => sample,
+            AuxChannel::Silent => 0.0,
+            AuxChannel::PilotTone { frequency, amplitude } => {
+                let value = amplitude * self.pilot_phase.sin() as f32;
+                self.pilot_phase += 2.0 * std::f64::consts::PI * frequency / self.sample_rate as f64;
+                if self.pilot_phase >= 2.0 * std::f64::consts::PI {
+                    self.pilot_phase -= 2.0 * std::f64::consts::PI;
+                }
+                value
+            }
+        };
+
+        for channel in 0..self.routing.channels {
+            write(if channel == self.routing.signal_channel { sample } else { aux })?;
+        }
+        Ok(())
+    }
+}
+
+/// 音频样本输出端
+///
+/// 调制器在生成音调的同时把归一化浮点样本（-1.0 ~ 1.0）推送到输出端，
+/// 无需先在内存中缓存整段传输即可写入文件、管道或内存。
+pub trait SampleSink {
+    /// 写入单个样本
+    fn write_sample(&mut self, sample: f32) -> Result<()>;
+
+    /// 写入多个样本
+    fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        for &sample in samples {
+            self.write_sample(sample)?;
+        }
+        Ok(())
+    }
+
+    /// 完成写入（刷新缓冲区、补全文件头等）
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: SampleSink + ?Sized> SampleSink for &mut S {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        (**self).write_sample(sample)
+    }
+
+    fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        (**self).write_samples(samples)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        (**self).finish()
+    }
+}
+
+/// 内存输出端（浮点）
+impl SampleSink for Vec<f32> {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        self.push(sample);
+        Ok(())
+    }
+
+    fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        self.extend_from_slice(samples);
+        Ok(())
+    }
+}
+
+/// 内存输出端（16位整数，不加抖动）
+impl SampleSink for Vec<i16> {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        self.push(quantize_i16(sample));
+        Ok(())
+    }
+}
+
+/// 音频处理器作为内存输出端
+impl SampleSink for AudioProcessor {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        self.samples.push(sample);
+        Ok(())
+    }
+
+    fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        self.samples.extend_from_slice(samples);
+        Ok(())
+    }
+}
+
+/// 原始PCM输出端（无文件头，小端字节序）
+///
+/// 可写入任意 `io::Write`，例如标准输出：
+/// `sstv-encode ... | aplay -f S16_LE -r 6000 -c 1`
+pub struct RawPcmSink<W: Write> {
+    writer: std::io::BufWriter<W>,
+    quantizer: Quantizer,
+    mixer: ChannelMixer,
+    scratch: Vec<u8>,
+}
+
+impl<W: Write> RawPcmSink<W> {
+    /// 创建16位原始PCM输出端
+    pub fn new(writer: W) -> Self {
+        Self::with_format(writer, PcmFormat::Int16, Dither::None)
+    }
+
+    /// 创建指定格式的原始PCM输出端
+    pub fn with_format(writer: W, format: PcmFormat, dither: Dither) -> Self {
+        Self {
+            writer: std::io::BufWriter::new(writer),
+            quantizer: Quantizer::new(format, dither),
+            // 单声道不使用导频，采样率无关紧要
+            mixer: ChannelMixer::new(ChannelRouting::mono(), 1),
+            scratch: Vec::with_capacity(4),
+        }
+    }
+
+    /// 创建多声道（交错）原始PCM输出端
+    pub fn with_routing(
+        writer: W,
+        sample_rate: u32,
+        format: PcmFormat,
+        dither: Dither,
+        routing: ChannelRouting,
+    ) -> Self {
+        Self {
+            writer: std::io::BufWriter::new(writer),
+            quantizer: Quantizer::new(format, dither),
+            mixer: ChannelMixer::new(routing, sample_rate),
+            scratch: Vec::with_capacity(4 * routing.channels() as usize),
+        }
+    }
+
+    /// 取回内部的写入器
+    pub fn into_inner(self) -> Result<W> {
+        self.writer.into_inner().map_err(|e| SstvError::IoError(e.into_error()))
+    }
+}
+
+impl<W: Write> SampleSink for RawPcmSink<W> {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        self.scratch.clear();
+        let (quantizer, scratch) = (&mut self.quantizer, &mut self.scratch);
+        self.mixer.write_frame(sample, |value| {
+            quantizer.encode(value, scratch);
+            Ok(())
+        })?;
+        self.writer.write_all(&self.scratch)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// 流式WAV输出端
+///
+/// 用于不可回写的输出（管道、标准输出）：文件头中的长度字段按“未知长度”填写为最大值，
+/// aplay、sox、ffmpeg等工具均能正确读取。超过两个声道或超过16位时与文件导出一样使用
+/// WAVE_FORMAT_EXTENSIBLE格式块并写入声道掩码。
+pub struct WavStreamSink<W: Write> {
+    writer: std::io::BufWriter<W>,
+    sample_rate: u32,
+    quantizer: Quantizer,
+    mixer: ChannelMixer,
+    scratch: Vec<u8>,
+    header_written: bool,
+}
+
+impl<W: Write> WavStreamSink<W> {
+    /// 创建16位流式WAV输出端
+    pub fn new(writer: W, sample_rate: u32) -> Self {
+        Self::with_format(writer, sample_rate, PcmFormat::Int16, Dither::None)
+    }
+
+    /// 创建指定格式的流式WAV输出端
+    pub fn with_format(writer: W, sample_rate: u32, format: PcmFormat, dither: Dither) -> Self {
+        Self::with_routing(writer, sample_rate, format, dither, ChannelRouting::mono())
+    }
+
+    /// 创建多声道流式WAV输出端
+    pub fn with_routing(
+        writer: W,
+        sample_rate: u32,
+        format: PcmFormat,
+        dither: Dither,
+        routing: ChannelRouting,
+    ) -> Self {
+        Self {
+            writer: std::io::BufWriter::new(writer),
+            sample_rate,
+            quantizer: Quantizer::new(format, dither),
+            mixer: ChannelMixer::new(routing, sample_rate),
+            scratch: Vec::with_capacity(4 * routing.channels() as usize),
+            header_written: false,
+        }
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        let format = self.quantizer.format();
+        let channels = self.mixer.routing().channels();
+        let bits_per_sample = format.bits_per_sample();
+        let block_align = channels * bits_per_sample / 8;
+        let byte_rate = self.sample_rate * block_align as u32;
+        // 1 = PCM整数, 3 = IEEE浮点
+        let format_tag: u16 = if format.is_float() { 3 } else { 1 };
+        // 与hound写入的文件一致：超过两个声道或16位时必须使用WAVE_FORMAT_EXTENSIBLE
+        let extensible = channels > 2 || bits_per_sample > 16;
+
+        let mut header = Vec::with_capacity(68);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&(if extensible { 40u32 } else { 16 }).to_le_bytes());
+        header.extend_from_slice(&(if extensible { 0xFFFE } else { format_tag }).to_le_bytes());
+        header.extend_from_slice(&channels.to_le_bytes());
+        header.extend_from_slice(&self.sample_rate.to_le_bytes());
+        header.extend_from_slice(&byte_rate.to_le_bytes());
+        header.extend_from_slice(&block_align.to_le_bytes());
+        header.extend_from_slice(&bits_per_sample.to_le_bytes());
+        if extensible {
+            header.extend_from_slice(&22u16.to_le_bytes());
+            header.extend_from_slice(&bits_per_sample.to_le_bytes());
+            // 前若干个扬声器位置依次占用，超过18个声道的部分不对应扬声器
+            let channel_mask = (0..channels.min(18)).fold(0u32, |mask, c| mask | (1 << c));
+            header.extend_from_slice(&channel_mask.to_le_bytes());
+            // 子格式GUID：格式代码 + 00000000-0010-8000-00AA00389B71
+            header.extend_from_slice(&format_tag.to_le_bytes());
+            header.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71]);
+        }
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        self.writer.write_all(&header)?;
+        self.header_written = true;
+        Ok(())
+    }
+}
+
+impl<W: Write> SampleSink for WavStreamSink<W> {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        if !self.header_written {
+            self.write_header()?;
+        }
+        self.scratch.clear();
+        let (quantizer, scratch) = (&mut self.quantizer, &mut self.scratch);
+        self.mixer.write_frame(sample, |value| {
+            quantizer.encode(value, scratch);
+            Ok(())
+        })?;
+        self.writer.write_all(&self.scratch)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if !self.header_written {
+            self.write_header()?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
 /// WAV文件写入器
 pub struct WavWriter {
     spec: WavSpec,
     writer: Option<HoundWavWriter<std::io::BufWriter<std::fs::File>>>,
+    quantizer: Quantizer,
+    mixer: ChannelMixer,
 }
 
 impl WavWriter {
-    /// 创建新的WAV写入器
+    /// 创建新的WAV写入器（单声道，16位）
     pub fn new<P: AsRef<Path>>(filename: P, sample_rate: u32) -> Result<Self> {
-        let spec = WavSpec {
-            channels: 1,
-            sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
+        Self::with_format(filename, sample_rate, PcmFormat::Int16, Dither::None)
+    }
 
+    /// 创建指定样本格式的WAV写入器（单声道）
+    pub fn with_format<P: AsRef<Path>>(
+        filename: P,
+        sample_rate: u32,
+        format: PcmFormat,
+        dither: Dither,
+    ) -> Result<Self> {
+        Self::with_routing(filename, sample_rate, format, dither, ChannelRouting::mono())
+    }
+
+    /// 创建多声道WAV写入器，SSTV信号按路由配置写入指定声道
+    ///
+    /// ```rust,no_run
+    /// use sstv_rust::{WavWriter, PcmFormat, Dither};
+    /// use sstv_rust::audio::{AuxChannel, ChannelRouting};
+    ///
+    /// // 左声道送电台，右声道为1kHz VOX导频
+    /// let routing = ChannelRouting::stereo_left(AuxChannel::PilotTone { frequency: 1000.0, amplitude: 0.5 });
+    /// let writer = WavWriter::with_routing("out.wav", 48000, PcmFormat::Int16, Dither::None, routing).unwrap();
+    /// ```
+    pub fn with_routing<P: AsRef<Path>>(
+        filename: P,
+        sample_rate: u32,
+        format: PcmFormat,
+        dither: Dither,
+        routing: ChannelRouting,
+    ) -> Result<Self> {
+        let spec = format.wav_spec(sample_rate, routing.channels());
         let writer = HoundWavWriter::create(filename, spec)?;
 
         Ok(Self {
             spec,
             writer: Some(writer),
+            quantizer: Quantizer::new(format, dither),
+            mixer: ChannelMixer::new(routing, sample_rate),
         })
     }
 
@@ -149,7 +833,17 @@ impl WavWriter {
     }
 
     /// 写入i16样本
+    ///
+    /// 16位单声道格式原样写入；其他位深度或声道路由按 `i16::MAX` 归一化后经 `write_normalized`
+    /// 量化和展开声道。
     pub fn write_samples(&mut self, samples: &[i16]) -> Result<()> {
+        let raw = self.spec.channels == 1
+            && self.spec.bits_per_sample == 16
+            && self.spec.sample_format == hound::SampleFormat::Int;
+        if !raw {
+            let normalized: Vec<f32> = samples.iter().map(|&sample| sample as f32 / i16::MAX as f32).collect();
+            return self.write_normalized(&normalized);
+        }
         if let Some(ref mut writer) = self.writer {
             for &sample in samples {
                 writer.write_sample(sample)?;
@@ -158,6 +852,24 @@ impl WavWriter {
         Ok(())
     }
 
+    /// 写入归一化浮点样本，按写入器的格式量化并按声道路由展开
+    pub fn write_normalized(&mut self, samples: &[f32]) -> Result<()> {
+        if let Some(ref mut writer) = self.writer {
+            let quantizer = &mut self.quantizer;
+            for &sample in samples {
+                self.mixer.write_frame(sample, |value| {
+                    if quantizer.format().is_float() {
+                        writer.write_sample(value)?;
+                    } else {
+                        writer.write_sample(quantizer.quantize(value))?;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
     /// 将浮点音频样本写入WAV文件
     pub fn write_samples_f32<P: AsRef<Path>>(path: P, samples: &[f32], sample_rate: u32) -> Result<()> {
         let spec = WavSpec {
@@ -167,59 +879,530 @@ impl WavWriter {
             sample_format: hound::SampleFormat::Int,
         };
 
-        let mut writer = HoundWavWriter::create(path, spec)?;
+        let mut writer = HoundWavWriter::create(path, spec)?;
+
+        // 将浮点样本转换为16位整数
+        for &sample in samples {
+            let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            writer.write_sample(sample_i16)?;
+        }
+
+        writer.finalize()?;
+        Ok(())
+    }
+
+    /// 完成写入并关闭文件
+    pub fn finalize(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.finalize()?;
+        }
+        Ok(())
+    }
+
+    /// 获取WAV规格
+    pub fn spec(&self) -> &WavSpec {
+        &self.spec
+    }
+}
+
+/// WAV文件输出端
+impl SampleSink for WavWriter {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        self.write_normalized(&[sample])
+    }
+
+    fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        self.write_normalized(samples)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.finalize()
+    }
+}
+
+/// 在已写完的WAV文件末尾追加RIFF块（如 `cue `、`LIST`），并更新RIFF总长度
+///
+/// 数据块长度为奇数时先补齐一个字节；每个块的数据长度为奇数时同样补齐。
+pub(crate) fn append_wav_chunks(path: &Path, chunks: &[([u8; 4], Vec<u8>)]) -> Result<()> {
+    use std::io::{Seek, SeekFrom};
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(SstvError::InvalidFormat(format!("{} 不是WAV文件", path.display())));
+    }
+
+    let mut length = file.seek(SeekFrom::End(0))?;
+    let mut out = Vec::new();
+    if length % 2 == 1 {
+        out.push(0);
+    }
+    for (id, data) in chunks {
+        out.extend(id);
+        out.extend((data.len() as u32).to_le_bytes());
+        out.extend(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+    length += out.len() as u64;
+    let riff_size = u32::try_from(length - 8)
+        .map_err(|_| SstvError::InvalidFormat("WAV文件超过4GB，无法追加块".to_string()))?;
+    file.write_all(&out)?;
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&riff_size.to_le_bytes())?;
+    Ok(())
+}
+
+/// 读取WAV文件中除音频数据以外的全部RIFF块，返回（块ID, 数据）
+///
+/// 数据块只跳过不读取；长度字段为0或最大值的流式数据块延伸到文件末尾，其后不会再有块。
+pub(crate) fn read_wav_chunks(path: &Path) -> Result<Vec<([u8; 4], Vec<u8>)>> {
+    use std::io::{Seek, SeekFrom};
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(SstvError::InvalidFormat(format!("{} 不是WAV文件", path.display())));
+    }
+
+    let mut chunks = Vec::new();
+    loop {
+        let mut chunk = [0u8; 8];
+        match reader.read_exact(&mut chunk) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let id = [chunk[0], chunk[1], chunk[2], chunk[3]];
+        let length = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        if &id == b"data" {
+            if length == 0 || length == u32::MAX as u64 {
+                break;
+            }
+            reader.seek(SeekFrom::Current((length + (length & 1)) as i64))?;
+            continue;
+        }
+        let mut data = vec![0u8; length as usize];
+        reader.read_exact(&mut data)?;
+        if length & 1 == 1 {
+            // 文件末尾可能缺少补齐字节
+            let _ = reader.read_exact(&mut [0u8; 1]);
+        }
+        chunks.push((id, data));
+    }
+    Ok(chunks)
+}
+
+/// 音频输入端：流式读取WAV或原始PCM，输出指定声道的归一化样本
+///
+/// 只要求 `io::Read`，可以读取管道和标准输入，例如
+/// `arecord -f S16_LE -r 48000 | sstv-decode - -r 48000`。
+/// 长度字段为“未知长度”的流式WAV（见 [`WavStreamSink`]）读到输入结束为止。
+pub struct AudioReader<R: Read> {
+    reader: std::io::BufReader<R>,
+    sample_rate: u32,
+    channels: u16,
+    format: PcmFormat,
+    // 剩余的数据字节数，`None` 表示读到输入结束
+    remaining: Option<u64>,
+    scratch: Vec<u8>,
+}
+
+impl<R: Read> AudioReader<R> {
+    /// 读取WAV文件头，创建WAV输入端
+    ///
+    /// 支持16/24/32位整数和32位浮点PCM（含WAVE_FORMAT_EXTENSIBLE），跳过数据块之前的其他块。
+    pub fn wav(reader: R) -> Result<Self> {
+        let mut reader = std::io::BufReader::new(reader);
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+            return Err(SstvError::InvalidFormat("不是RIFF/WAVE文件".to_string()));
+        }
+
+        let mut spec: Option<(u32, u16, PcmFormat)> = None;
+        loop {
+            let mut chunk = [0u8; 8];
+            reader.read_exact(&mut chunk)?;
+            let length = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            match &chunk[0..4] {
+                b"fmt " => {
+                    let mut fmt = vec![0u8; length as usize + (length as usize & 1)];
+                    reader.read_exact(&mut fmt)?;
+                    if fmt.len() < 16 {
+                        return Err(SstvError::InvalidFormat("fmt块过短".to_string()));
+                    }
+                    let mut tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                    // WAVE_FORMAT_EXTENSIBLE：子格式GUID的前两个字节是实际格式
+                    if tag == 0xFFFE && fmt.len() >= 26 {
+                        tag = u16::from_le_bytes([fmt[24], fmt[25]]);
+                    }
+                    let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                    let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                    let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+                    let format = match (tag, bits) {
+                        (1, bits) => PcmFormat::from_bit_depth(bits),
+                        (3, 32) => Some(PcmFormat::Float32),
+                        _ => None,
+                    }
+                    .ok_or_else(|| SstvError::InvalidFormat(format!("不支持的WAV样本格式: 格式码{}，{}位", tag, bits)))?;
+                    spec = Some((sample_rate, channels, format));
+                }
+                b"data" => {
+                    let (sample_rate, channels, format) =
+                        spec.ok_or_else(|| SstvError::InvalidFormat("数据块之前缺少fmt块".to_string()))?;
+                    let mut audio = Self::buffered(reader, sample_rate, format, channels)?;
+                    // 流式WAV的长度字段为0或最大值
+                    audio.remaining = (length != 0 && length != u32::MAX).then_some(length as u64);
+                    return Ok(audio);
+                }
+                _ => {
+                    let skip = length as u64 + (length as u64 & 1);
+                    std::io::copy(&mut (&mut reader).take(skip), &mut std::io::sink())?;
+                }
+            }
+        }
+    }
+
+    /// 创建原始PCM（小端、交错）输入端
+    pub fn raw(reader: R, sample_rate: u32, format: PcmFormat, channels: u16) -> Result<Self> {
+        Self::buffered(std::io::BufReader::new(reader), sample_rate, format, channels)
+    }
+
+    fn buffered(reader: std::io::BufReader<R>, sample_rate: u32, format: PcmFormat, channels: u16) -> Result<Self> {
+        if !(crate::MIN_SAMPLE_RATE..=crate::MAX_SAMPLE_RATE).contains(&sample_rate) {
+            return Err(SstvError::invalid_sample_rate(sample_rate, crate::MIN_SAMPLE_RATE, crate::MAX_SAMPLE_RATE));
+        }
+        if channels == 0 {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "channels".to_string(),
+                value: channels.to_string(),
+            });
+        }
+        Ok(Self {
+            reader,
+            sample_rate,
+            channels,
+            format,
+            remaining: None,
+            scratch: Vec::new(),
+        })
+    }
+
+    /// 获取采样率
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// 获取声道数
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// 获取样本格式
+    pub fn format(&self) -> PcmFormat {
+        self.format
+    }
+
+    /// 最多读取 `max_frames` 帧，把第 `channel` 声道的样本追加到 `out`
+    ///
+    /// 返回追加的样本数，0表示输入已结束；末尾不完整的帧被丢弃。
+    pub fn read_channel(&mut self, channel: u16, max_frames: usize, out: &mut Vec<f32>) -> Result<usize> {
+        if channel >= self.channels {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "channel".to_string(),
+                value: format!("{}（共{}个声道）", channel, self.channels),
+            });
+        }
+        let width = self.format.bytes_per_sample();
+        let frame_size = self.channels as usize * width;
+        let mut wanted = (max_frames * frame_size) as u64;
+        if let Some(remaining) = self.remaining {
+            wanted = wanted.min(remaining);
+        }
+        self.scratch.resize(wanted as usize, 0);
+        let mut filled = 0;
+        while filled < self.scratch.len() {
+            match self.reader.read(&mut self.scratch[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= filled as u64;
+        }
+
+        let scale = 1.0 / self.format.full_scale() as f32;
+        let offset = channel as usize * width;
+        let before = out.len();
+        for frame in self.scratch[..filled].chunks_exact(frame_size) {
+            let bytes = &frame[offset..offset + width];
+            let sample = match self.format {
+                PcmFormat::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 * scale,
+                PcmFormat::Int24 => (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 * scale,
+                PcmFormat::Int32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 * scale,
+                PcmFormat::Float32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            };
+            out.push(sample);
+        }
+        Ok(out.len() - before)
+    }
+}
+/// 音频处理工具函数
+pub mod utils {
+    use crate::error::{Result, SstvError};
+
+    /// 将分贝转换为线性幅度
+    pub fn db_to_linear(db: f32) -> f32 {
+        10.0_f32.powf(db / 20.0)
+    }
+
+    /// 将线性幅度转换为分贝
+    pub fn linear_to_db(linear: f32) -> f32 {
+        20.0 * linear.log10()
+    }
+
+    /// 计算RMS值
+    pub fn calculate_rms(samples: &[f32]) -> f32 {
+        let sum_squares: f32 = samples.iter().map(|&x| x * x).sum();
+        (sum_squares / samples.len() as f32).sqrt()
+    }
+
+    /// 归一化音频样本
+    pub fn normalize(samples: &mut [f32], target_peak: f32) {
+        let max_amplitude = samples.iter().map(|&x| x.abs()).fold(0.0, f32::max);
+        if max_amplitude > 0.0 {
+            let scale_factor = target_peak / max_amplitude;
+            for sample in samples.iter_mut() {
+                *sample *= scale_factor;
+            }
+        }
+    }
+
+    /// 重采样质量
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum ResampleQuality {
+        /// 8个过零点，阻带衰减约50dB
+        Fast,
+        /// 16个过零点，阻带衰减约80dB
+        #[default]
+        Balanced,
+        /// 32个过零点，阻带衰减约100dB
+        High,
+    }
+
+    impl ResampleQuality {
+        /// (单侧过零点数, Kaiser窗β, 截止频率相对目标奈奎斯特频率的比例)
+        fn parameters(&self) -> (usize, f64, f64) {
+            match self {
+                ResampleQuality::Fast => (8, 5.0, 0.90),
+                ResampleQuality::Balanced => (16, 8.0, 0.94),
+                ResampleQuality::High => (32, 10.0, 0.97),
+            }
+        }
+    }
+
+    /// 带限重采样器（多相加窗sinc）
+    ///
+    /// 把采样率比化简为 L/M，对每个输出样本选择对应相位的Kaiser加窗sinc滤波器。
+    /// 降采样时截止频率随比例降低，避免高于目标奈奎斯特频率的成分混叠。
+    /// 滤波器零相位，输出与输入时间对齐，长度为 `ceil(输入长度 × L / M)`。
+    ///
+    /// ```rust
+    /// use sstv_rust::audio::utils::Resampler;
+    ///
+    /// // 48kHz母版降采样到11.025kHz
+    /// let resampler = Resampler::new(48000, 11025).unwrap();
+    /// let output = resampler.process(&vec![0.0f32; 48000]);
+    /// assert_eq!(output.len(), 11025);
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct Resampler {
+        input_rate: u32,
+        output_rate: u32,
+        up: u64,
+        down: u64,
+        half_taps: usize,
+        cutoff: f64,
+        beta: f64,
+        // 相位数不太大时预先计算的多相系数表，按 [相位][抽头] 平铺
+        table: Option<Vec<f32>>,
+    }
+
+    impl Resampler {
+        // 超过此相位数时不预先计算系数表，改为逐样本计算
+        const MAX_TABLE_PHASES: u64 = 1024;
+
+        /// 创建默认质量（Balanced）的重采样器
+        pub fn new(input_rate: u32, output_rate: u32) -> Result<Self> {
+            Self::with_quality(input_rate, output_rate, ResampleQuality::default())
+        }
+
+        /// 创建指定质量的重采样器
+        pub fn with_quality(input_rate: u32, output_rate: u32, quality: ResampleQuality) -> Result<Self> {
+            for (parameter, rate) in [("input_rate", input_rate), ("output_rate", output_rate)] {
+                if rate == 0 {
+                    return Err(SstvError::InvalidAudioParameter {
+                        parameter: parameter.to_string(),
+                        value: rate.to_string(),
+                    });
+                }
+            }
+
+            let divisor = gcd(input_rate as u64, output_rate as u64);
+            let up = output_rate as u64 / divisor;
+            let down = input_rate as u64 / divisor;
+
+            let (zero_crossings, beta, rolloff) = quality.parameters();
+            // 截止频率以输入奈奎斯特频率为1归一化
+            let cutoff = if up < down { rolloff * up as f64 / down as f64 } else { rolloff };
+            let half_taps = (zero_crossings as f64 / cutoff).ceil() as usize;
+
+            let mut resampler = Self {
+                input_rate,
+                output_rate,
+                up,
+                down,
+                half_taps,
+                cutoff,
+                beta,
+                table: None,
+            };
+
+            if up <= Self::MAX_TABLE_PHASES {
+                let mut table = Vec::with_capacity(up as usize * 2 * half_taps);
+                for phase in 0..up {
+                    let fraction = phase as f64 / up as f64;
+                    for tap in 0..2 * half_taps {
+                        table.push(resampler.coefficient(tap, fraction));
+                    }
+                }
+                resampler.table = Some(table);
+            }
+
+            Ok(resampler)
+        }
+
+        /// 输入采样率
+        pub fn input_rate(&self) -> u32 {
+            self.input_rate
+        }
+
+        /// 输出采样率
+        pub fn output_rate(&self) -> u32 {
+            self.output_rate
+        }
+
+        /// 给定输入长度时的输出长度
+        pub fn output_len(&self, input_len: usize) -> usize {
+            (input_len as u64 * self.up).div_ceil(self.down) as usize
+        }
 
-        // 将浮点样本转换为16位整数
-        for &sample in samples {
-            let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-            writer.write_sample(sample_i16)?;
+        /// 对整段样本重采样
+        pub fn process(&self, input: &[f32]) -> Vec<f32> {
+            if self.up == self.down {
+                return input.to_vec();
+            }
+
+            let output_len = self.output_len(input.len());
+            let mut output = Vec::with_capacity(output_len);
+            self.process_range(input, 0..output_len, &mut output);
+            output
         }
 
-        writer.finalize()?;
-        Ok(())
-    }
+        /// 只计算输出中 `range` 范围内的样本并追加到 `output`
+        ///
+        /// 输出很长时（例如上采样到MHz级的I/Q采样率）可分段计算，避免一次性占用大量内存。
+        pub fn process_range(&self, input: &[f32], range: std::ops::Range<usize>, output: &mut Vec<f32>) {
+            let end = range.end.min(self.output_len(input.len()));
+            let range = range.start.min(end)..end;
+            if self.up == self.down {
+                output.extend_from_slice(&input[range]);
+                return;
+            }
 
-    /// 完成写入并关闭文件
-    pub fn finalize(&mut self) -> Result<()> {
-        if let Some(writer) = self.writer.take() {
-            writer.finalize()?;
+            let taps = 2 * self.half_taps;
+            let mut coefficients = vec![0.0f32; taps];
+
+            for n in range.start as u64..range.end as u64 {
+                let position = n * self.down;
+                let center = (position / self.up) as i64;
+                let phase = position % self.up;
+
+                let phase_coefficients = match &self.table {
+                    Some(table) => &table[phase as usize * taps..(phase as usize + 1) * taps],
+                    None => {
+                        let fraction = phase as f64 / self.up as f64;
+                        for (tap, coefficient) in coefficients.iter_mut().enumerate() {
+                            *coefficient = self.coefficient(tap, fraction);
+                        }
+                        &coefficients[..]
+                    }
+                };
+
+                // 抽头 tap 对应输入样本 center + tap - half_taps + 1
+                let first = center - self.half_taps as i64 + 1;
+                let mut acc = 0.0f64;
+                for (tap, &coefficient) in phase_coefficients.iter().enumerate() {
+                    let index = first + tap as i64;
+                    if index >= 0 && (index as usize) < input.len() {
+                        acc += input[index as usize] as f64 * coefficient as f64;
+                    }
+                }
+                output.push(acc as f32);
+            }
         }
-        Ok(())
-    }
 
-    /// 获取WAV规格
-    pub fn spec(&self) -> &WavSpec {
-        &self.spec
+        /// 距离输出时刻 `tap - half_taps + 1 - fraction` 个输入样本处的滤波器系数
+        fn coefficient(&self, tap: usize, fraction: f64) -> f32 {
+            let x = tap as f64 - self.half_taps as f64 + 1.0 - fraction;
+            let ratio = x / self.half_taps as f64;
+            if ratio.abs() >= 1.0 {
+                return 0.0;
+            }
+            let window = bessel_i0(self.beta * (1.0 - ratio * ratio).sqrt()) / bessel_i0(self.beta);
+            (self.cutoff * sinc(self.cutoff * x) * window) as f32
+        }
     }
-}
 
-/// 音频处理工具函数
-pub mod utils {
-    /// 将分贝转换为线性幅度
-    pub fn db_to_linear(db: f32) -> f32 {
-        10.0_f32.powf(db / 20.0)
+    /// 把样本从一个采样率转换到另一个采样率（默认质量）
+    pub fn resample(samples: &[f32], input_rate: u32, output_rate: u32) -> Result<Vec<f32>> {
+        Ok(Resampler::new(input_rate, output_rate)?.process(samples))
     }
 
-    /// 将线性幅度转换为分贝
-    pub fn linear_to_db(linear: f32) -> f32 {
-        20.0 * linear.log10()
+    fn sinc(x: f64) -> f64 {
+        if x.abs() < 1e-12 {
+            1.0
+        } else {
+            let px = std::f64::consts::PI * x;
+            px.sin() / px
+        }
     }
 
-    /// 计算RMS值
-    pub fn calculate_rms(samples: &[f32]) -> f32 {
-        let sum_squares: f32 = samples.iter().map(|&x| x * x).sum();
-        (sum_squares / samples.len() as f32).sqrt()
+    // 第一类零阶修正贝塞尔函数（级数展开）
+    pub(crate) fn bessel_i0(x: f64) -> f64 {
+        let mut sum = 1.0;
+        let mut term = 1.0;
+        let half = x / 2.0;
+        for k in 1..64 {
+            term *= half / k as f64;
+            let contribution = term * term;
+            sum += contribution;
+            if contribution < sum * 1e-16 {
+                break;
+            }
+        }
+        sum
     }
 
-    /// 归一化音频样本
-    pub fn normalize(samples: &mut [f32], target_peak: f32) {
-        let max_amplitude = samples.iter().map(|&x| x.abs()).fold(0.0, f32::max);
-        if max_amplitude > 0.0 {
-            let scale_factor = target_peak / max_amplitude;
-            for sample in samples.iter_mut() {
-                *sample *= scale_factor;
-            }
+    fn gcd(mut a: u64, mut b: u64) -> u64 {
+        while b != 0 {
+            (a, b) = (b, a % b);
         }
+        a
     }
 }
 
@@ -253,6 +1436,8 @@ pub mod effects {
     }
 
     /// 应用低通滤波器（简单的一阶滤波器）
+    ///
+    /// `cutoff_ratio` 是无量纲的平滑系数；需要按Hz设计的滤波器请使用 [`super::filters`]。
     pub fn apply_lowpass_filter(samples: &mut [f32], cutoff_ratio: f32) {
         if samples.is_empty() || cutoff_ratio >= 1.0 {
             return;
@@ -267,7 +1452,7 @@ pub mod effects {
         }
     }
 
-    /// 应用高通滤波器
+    /// 应用高通滤波器（简单的一阶滤波器）
     pub fn apply_highpass_filter(samples: &mut [f32], cutoff_ratio: f32) {
         if samples.is_empty() || cutoff_ratio <= 0.0 {
             return;
@@ -285,7 +1470,7 @@ pub mod effects {
         }
     }
 
-    /// 应用带通滤波器
+    /// 应用带通滤波器（一阶高通和一阶低通串联）
     pub fn apply_bandpass_filter(samples: &mut [f32], low_cutoff: f32, high_cutoff: f32) {
         // 先应用高通滤波器
         apply_highpass_filter(samples, low_cutoff);
@@ -294,6 +1479,430 @@ pub mod effects {
     }
 }
 
+/// 按截止频率（Hz）设计的数字滤波器
+///
+/// 包含RBJ（Audio EQ Cookbook）双二阶IIR滤波器、Butterworth级联和Kaiser窗sinc线性相位FIR，
+/// 以及现成的SSTV发射带通滤波器。
+pub mod filters {
+    use super::utils::bessel_i0;
+    use crate::error::{Result, SstvError};
+    use rustfft::FftPlanner;
+    use rustfft::num_complex::Complex;
+    use std::f64::consts::PI;
+
+    /// SSTV发射带通的通带（Hz）
+    pub const SSTV_PASSBAND: (f64, f64) = (1000.0, 2500.0);
+
+    // 检查频率在 (0, 奈奎斯特频率) 内
+    fn check_frequency(parameter: &str, frequency: f64, sample_rate: u32) -> Result<()> {
+        if !frequency.is_finite() || frequency <= 0.0 || frequency >= sample_rate as f64 / 2.0 {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: parameter.to_string(),
+                value: format!("{}Hz（采样率{}Hz）", frequency, sample_rate),
+            });
+        }
+        Ok(())
+    }
+
+    /// 双二阶滤波器类型
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BiquadType {
+        /// 低通
+        LowPass,
+        /// 高通
+        HighPass,
+        /// 带通（中心频率处增益0dB）
+        BandPass,
+        /// 陷波
+        Notch,
+    }
+
+    /// RBJ双二阶IIR滤波器（转置直接II型）
+    #[derive(Debug, Clone)]
+    pub struct Biquad {
+        b0: f64,
+        b1: f64,
+        b2: f64,
+        a1: f64,
+        a2: f64,
+        z1: f64,
+        z2: f64,
+    }
+
+    impl Biquad {
+        /// 按类型、特征频率（Hz）和品质因数Q设计滤波器
+        pub fn new(kind: BiquadType, frequency: f64, q: f64, sample_rate: u32) -> Result<Self> {
+            check_frequency("frequency", frequency, sample_rate)?;
+            if !q.is_finite() || q <= 0.0 {
+                return Err(SstvError::InvalidAudioParameter {
+                    parameter: "q".to_string(),
+                    value: q.to_string(),
+                });
+            }
+
+            let omega = 2.0 * PI * frequency / sample_rate as f64;
+            let (sin, cos) = omega.sin_cos();
+            let alpha = sin / (2.0 * q);
+
+            let (b0, b1, b2) = match kind {
+                BiquadType::LowPass => ((1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0),
+                BiquadType::HighPass => ((1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0),
+                BiquadType::BandPass => (alpha, 0.0, -alpha),
+                BiquadType::Notch => (1.0, -2.0 * cos, 1.0),
+            };
+            let (a0, a1, a2) = (1.0 + alpha, -2.0 * cos, 1.0 - alpha);
+
+            Ok(Self {
+                b0: b0 / a0,
+                b1: b1 / a0,
+                b2: b2 / a0,
+                a1: a1 / a0,
+                a2: a2 / a0,
+                z1: 0.0,
+                z2: 0.0,
+            })
+        }
+
+        /// 低通
+        pub fn lowpass(frequency: f64, q: f64, sample_rate: u32) -> Result<Self> {
+            Self::new(BiquadType::LowPass, frequency, q, sample_rate)
+        }
+
+        /// 高通
+        pub fn highpass(frequency: f64, q: f64, sample_rate: u32) -> Result<Self> {
+            Self::new(BiquadType::HighPass, frequency, q, sample_rate)
+        }
+
+        /// 带通
+        pub fn bandpass(frequency: f64, q: f64, sample_rate: u32) -> Result<Self> {
+            Self::new(BiquadType::BandPass, frequency, q, sample_rate)
+        }
+
+        /// 陷波
+        pub fn notch(frequency: f64, q: f64, sample_rate: u32) -> Result<Self> {
+            Self::new(BiquadType::Notch, frequency, q, sample_rate)
+        }
+
+        /// 处理单个样本
+        pub fn process_sample(&mut self, input: f32) -> f32 {
+            let x = input as f64;
+            let y = self.b0 * x + self.z1;
+            self.z1 = self.b1 * x - self.a1 * y + self.z2;
+            self.z2 = self.b2 * x - self.a2 * y;
+            y as f32
+        }
+
+        /// 原地处理样本
+        pub fn process(&mut self, samples: &mut [f32]) {
+            for sample in samples.iter_mut() {
+                *sample = self.process_sample(*sample);
+            }
+        }
+
+        /// 清除滤波器状态
+        pub fn reset(&mut self) {
+            self.z1 = 0.0;
+            self.z2 = 0.0;
+        }
+
+        /// 频率 `frequency` 处的幅度响应（线性）
+        pub fn magnitude_response(&self, frequency: f64, sample_rate: u32) -> f64 {
+            let omega = 2.0 * PI * frequency / sample_rate as f64;
+            let (z1_re, z1_im) = (omega.cos(), -omega.sin());
+            let (z2_re, z2_im) = ((2.0 * omega).cos(), -(2.0 * omega).sin());
+            let num_re = self.b0 + self.b1 * z1_re + self.b2 * z2_re;
+            let num_im = self.b1 * z1_im + self.b2 * z2_im;
+            let den_re = 1.0 + self.a1 * z1_re + self.a2 * z2_re;
+            let den_im = self.a1 * z1_im + self.a2 * z2_im;
+            ((num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im)).sqrt()
+        }
+    }
+
+    /// 双二阶滤波器级联
+    #[derive(Debug, Clone, Default)]
+    pub struct BiquadCascade {
+        stages: Vec<Biquad>,
+    }
+
+    impl BiquadCascade {
+        /// 由若干双二阶节组成级联
+        pub fn new(stages: Vec<Biquad>) -> Self {
+            Self { stages }
+        }
+
+        /// `order` 阶（偶数）Butterworth低通
+        pub fn butterworth_lowpass(order: usize, frequency: f64, sample_rate: u32) -> Result<Self> {
+            Self::butterworth(BiquadType::LowPass, order, frequency, sample_rate)
+        }
+
+        /// `order` 阶（偶数）Butterworth高通
+        pub fn butterworth_highpass(order: usize, frequency: f64, sample_rate: u32) -> Result<Self> {
+            Self::butterworth(BiquadType::HighPass, order, frequency, sample_rate)
+        }
+
+        /// 由Butterworth高通和低通组成的带通
+        pub fn butterworth_bandpass(order: usize, low: f64, high: f64, sample_rate: u32) -> Result<Self> {
+            let mut cascade = Self::butterworth_highpass(order, low, sample_rate)?;
+            cascade.stages.extend(Self::butterworth_lowpass(order, high, sample_rate)?.stages);
+            Ok(cascade)
+        }
+
+        fn butterworth(kind: BiquadType, order: usize, frequency: f64, sample_rate: u32) -> Result<Self> {
+            if order == 0 || !order.is_multiple_of(2) || order > 16 {
+                return Err(SstvError::InvalidAudioParameter {
+                    parameter: "order".to_string(),
+                    value: order.to_string(),
+                });
+            }
+            // 每个二阶节的Q由Butterworth极点角度决定
+            let stages = (0..order / 2)
+                .map(|k| {
+                    let q = 1.0 / (2.0 * (PI * (2 * k + 1) as f64 / (2 * order) as f64).cos());
+                    Biquad::new(kind, frequency, q, sample_rate)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Self { stages })
+        }
+
+        /// 原地处理样本
+        pub fn process(&mut self, samples: &mut [f32]) {
+            for stage in self.stages.iter_mut() {
+                stage.process(samples);
+            }
+        }
+
+        /// 处理单个样本
+        pub fn process_sample(&mut self, input: f32) -> f32 {
+            self.stages.iter_mut().fold(input, |x, stage| stage.process_sample(x))
+        }
+
+        /// 清除滤波器状态
+        pub fn reset(&mut self) {
+            self.stages.iter_mut().for_each(Biquad::reset);
+        }
+
+        /// 频率 `frequency` 处的幅度响应（线性）
+        pub fn magnitude_response(&self, frequency: f64, sample_rate: u32) -> f64 {
+            self.stages
+                .iter()
+                .map(|stage| stage.magnitude_response(frequency, sample_rate))
+                .product()
+        }
+    }
+
+    /// Kaiser窗sinc线性相位FIR滤波器
+    #[derive(Debug, Clone)]
+    pub struct FirFilter {
+        taps: Vec<f32>,
+        // 环形历史缓冲区
+        history: Vec<f32>,
+        position: usize,
+    }
+
+    impl FirFilter {
+        /// 阻带衰减（dB），决定Kaiser窗β和抽头数
+        const ATTENUATION_DB: f64 = 60.0;
+
+        /// 由抽头系数创建滤波器
+        pub fn from_taps(taps: Vec<f32>) -> Self {
+            let history = vec![0.0; taps.len()];
+            Self {
+                taps,
+                history,
+                position: 0,
+            }
+        }
+
+        /// 低通，`transition` 为过渡带宽度（Hz）
+        pub fn lowpass(cutoff: f64, transition: f64, sample_rate: u32) -> Result<Self> {
+            check_frequency("cutoff", cutoff, sample_rate)?;
+            let length = Self::length_for(transition, sample_rate)?;
+            Ok(Self::from_taps(Self::design(length, |x| ideal_lowpass(x, cutoff, sample_rate))))
+        }
+
+        /// 高通，`transition` 为过渡带宽度（Hz）
+        pub fn highpass(cutoff: f64, transition: f64, sample_rate: u32) -> Result<Self> {
+            check_frequency("cutoff", cutoff, sample_rate)?;
+            let length = Self::length_for(transition, sample_rate)?;
+            Ok(Self::from_taps(Self::design(length, |x| {
+                ideal_allpass(x) - ideal_lowpass(x, cutoff, sample_rate)
+            })))
+        }
+
+        /// 带通，`low`/`high` 为-6dB截止频率，`transition` 为过渡带宽度（Hz）
+        pub fn bandpass(low: f64, high: f64, transition: f64, sample_rate: u32) -> Result<Self> {
+            check_frequency("low", low, sample_rate)?;
+            check_frequency("high", high, sample_rate)?;
+            if low >= high {
+                return Err(SstvError::InvalidAudioParameter {
+                    parameter: "passband".to_string(),
+                    value: format!("{}-{}Hz", low, high),
+                });
+            }
+            let length = Self::length_for(transition, sample_rate)?;
+            Ok(Self::from_taps(Self::design(length, |x| {
+                ideal_lowpass(x, high, sample_rate) - ideal_lowpass(x, low, sample_rate)
+            })))
+        }
+
+        /// 现成的SSTV发射带通：1000-2500Hz通带平坦，带外衰减约60dB
+        ///
+        /// 通带覆盖VIS码（1100Hz）到白电平（2300Hz）的全部音调，滤除邻道干扰。
+        /// 采样率需高于约5.8kHz，使上侧过渡带落在奈奎斯特频率以内。
+        pub fn sstv_bandpass(sample_rate: u32) -> Result<Self> {
+            let transition = 400.0;
+            let (low, high) = SSTV_PASSBAND;
+            Self::bandpass(low - transition / 2.0, high + transition / 2.0, transition, sample_rate)
+        }
+
+        // Kaiser公式估计奇数抽头数
+        fn length_for(transition: f64, sample_rate: u32) -> Result<usize> {
+            if !transition.is_finite() || transition <= 0.0 {
+                return Err(SstvError::InvalidAudioParameter {
+                    parameter: "transition".to_string(),
+                    value: transition.to_string(),
+                });
+            }
+            let delta = 2.0 * PI * transition / sample_rate as f64;
+            let length = ((Self::ATTENUATION_DB - 8.0) / (2.285 * delta)).ceil() as usize + 1;
+            Ok(length | 1)
+        }
+
+        // 按理想冲激响应（以中心为原点的样本偏移）加Kaiser窗
+        fn design<F: Fn(f64) -> f64>(length: usize, ideal: F) -> Vec<f32> {
+            let beta = 0.1102 * (Self::ATTENUATION_DB - 8.7);
+            let centre = (length - 1) as f64 / 2.0;
+            (0..length)
+                .map(|n| {
+                    let x = n as f64 - centre;
+                    let ratio = x / centre.max(1.0);
+                    let window = bessel_i0(beta * (1.0 - ratio * ratio).max(0.0).sqrt()) / bessel_i0(beta);
+                    (ideal(x) * window) as f32
+                })
+                .collect()
+        }
+
+        /// 抽头系数
+        pub fn taps(&self) -> &[f32] {
+            &self.taps
+        }
+
+        /// 群延迟（样本数）
+        pub fn group_delay(&self) -> usize {
+            self.taps.len() / 2
+        }
+
+        /// 处理单个样本（因果，输出延迟 `group_delay` 个样本）
+        pub fn process_sample(&mut self, input: f32) -> f32 {
+            let len = self.taps.len();
+            self.history[self.position] = input;
+            let mut acc = 0.0f64;
+            for (k, &tap) in self.taps.iter().enumerate() {
+                let index = (self.position + len - k) % len;
+                acc += tap as f64 * self.history[index] as f64;
+            }
+            self.position = (self.position + 1) % len;
+            acc as f32
+        }
+
+        /// 原地处理样本（因果，输出延迟 `group_delay` 个样本）
+        pub fn process(&mut self, samples: &mut [f32]) {
+            for sample in samples.iter_mut() {
+                *sample = self.process_sample(*sample);
+            }
+        }
+
+        /// 对整段样本做延迟补偿的滤波，输出与输入时间对齐
+        ///
+        /// 用于离线处理已渲染的传输，同步脉冲位置保持不变。以FFT分块重叠相加实现，
+        /// 计算量与抽头数基本无关；结果与逐样本卷积一致（误差在浮点舍入量级）。
+        pub fn apply_aligned(&self, samples: &mut [f32]) {
+            let taps = self.taps.len();
+            let delay = self.group_delay();
+            let len = samples.len();
+            let fft_len = (4 * taps).next_power_of_two();
+            let block = fft_len - taps + 1;
+
+            let mut planner = FftPlanner::<f64>::new();
+            let forward = planner.plan_fft_forward(fft_len);
+            let inverse = planner.plan_fft_inverse(fft_len);
+            let mut kernel: Vec<Complex<f64>> = self
+                .taps
+                .iter()
+                .map(|&tap| Complex::new(tap as f64, 0.0))
+                .collect();
+            kernel.resize(fft_len, Complex::new(0.0, 0.0));
+            forward.process(&mut kernel);
+            let scale = 1.0 / fft_len as f64;
+
+            // 完整卷积 y[m] 写回 samples[m - delay]；写入位置总在尚未读取的块之前，可以原地处理
+            let mut buffer = vec![Complex::new(0.0, 0.0); fft_len];
+            // 上一块卷积伸入当前块的尾部
+            let mut carry = vec![0.0f64; taps - 1];
+            for start in (0..len).step_by(block) {
+                let chunk_len = block.min(len - start);
+                for (slot, &sample) in buffer.iter_mut().zip(&samples[start..start + chunk_len]) {
+                    *slot = Complex::new(sample as f64, 0.0);
+                }
+                buffer[chunk_len..].fill(Complex::new(0.0, 0.0));
+                forward.process(&mut buffer);
+                for (value, coefficient) in buffer.iter_mut().zip(&kernel) {
+                    *value *= coefficient * scale;
+                }
+                inverse.process(&mut buffer);
+
+                let conv = |i: usize| buffer[i].re + carry.get(i).copied().unwrap_or(0.0);
+                let next_carry: Vec<f64> = (0..taps - 1).map(|j| conv(chunk_len + j)).collect();
+                for i in 0..chunk_len {
+                    let m = start + i;
+                    if m >= delay {
+                        samples[m - delay] = conv(i) as f32;
+                    }
+                }
+                carry = next_carry;
+            }
+            // 最后 delay 个输出来自末块之后的卷积尾部
+            for (j, &value) in carry.iter().enumerate() {
+                let m = len + j;
+                if m >= delay && m - delay < len {
+                    samples[m - delay] = value as f32;
+                }
+            }
+        }
+
+        /// 清除滤波器状态
+        pub fn reset(&mut self) {
+            self.history.iter_mut().for_each(|h| *h = 0.0);
+            self.position = 0;
+        }
+
+        /// 频率 `frequency` 处的幅度响应（线性）
+        pub fn magnitude_response(&self, frequency: f64, sample_rate: u32) -> f64 {
+            let omega = 2.0 * PI * frequency / sample_rate as f64;
+            let (re, im) = self.taps.iter().enumerate().fold((0.0, 0.0), |(re, im), (k, &tap)| {
+                let angle = omega * k as f64;
+                (re + tap as f64 * angle.cos(), im - tap as f64 * angle.sin())
+            });
+            (re * re + im * im).sqrt()
+        }
+    }
+
+    // 理想低通冲激响应
+    fn ideal_lowpass(x: f64, cutoff: f64, sample_rate: u32) -> f64 {
+        let fc = cutoff / sample_rate as f64;
+        if x == 0.0 {
+            2.0 * fc
+        } else {
+            (2.0 * PI * fc * x).sin() / (PI * x)
+        }
+    }
+
+    // 理想全通（单位冲激）
+    fn ideal_allpass(x: f64) -> f64 {
+        if x == 0.0 { 1.0 } else { 0.0 }
+    }
+}
+
 /// 从WAV文件加载音频数据
 pub fn load_wav_file<P: AsRef<Path>>(path: P) -> Result<(Vec<f32>, u32)> {
     let mut reader = hound::WavReader::open(path)?;
@@ -326,7 +1935,7 @@ pub fn load_wav_file<P: AsRef<Path>>(path: P) -> Result<(Vec<f32>, u32)> {
         },
     };
     
-    let samples = samples.map_err(|e| SstvError::AudioError(e))?;
+    let samples = samples.map_err(SstvError::AudioError)?;
     Ok((samples, spec.sample_rate))
 }
 
@@ -339,6 +1948,8 @@ mod tests {
         let generator = AudioGenerator::new(48000, 16).unwrap();
         assert_eq!(generator.sample_rate(), 48000);
         assert_eq!(generator.bit_depth(), 16);
+        assert!(AudioGenerator::new(crate::MIN_SAMPLE_RATE, 16).is_ok());
+        assert!(AudioGenerator::new(crate::MIN_SAMPLE_RATE - 1, 16).is_err());
     }
 
     #[test]
@@ -350,10 +1961,397 @@ mod tests {
 
     #[test]
     fn test_wav_writer_creation() {
-        let writer = WavWriter::for_sstv("test.wav", 48000).unwrap();
+        let path = std::env::temp_dir().join(format!("sstv_rust_writer_{}.wav", std::process::id()));
+        let writer = WavWriter::for_sstv(&path, 48000).unwrap();
         assert_eq!(writer.spec().sample_rate, 48000);
         assert_eq!(writer.spec().channels, 1);
         assert_eq!(writer.spec().bits_per_sample, 16);
+        drop(writer);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_raw_pcm_sink() {
+        let mut sink = RawPcmSink::new(Vec::new());
+        sink.write_samples(&[0.0, -1.0, 1.0]).unwrap();
+        sink.finish().unwrap();
+        let bytes = sink.into_inner().unwrap();
+        assert_eq!(bytes, vec![0x00, 0x00, 0x01, 0x80, 0xFF, 0x7F]);
+
+        let mut sink = RawPcmSink::with_format(Vec::new(), PcmFormat::Float32, Dither::None);
+        sink.write_sample(0.5).unwrap();
+        assert_eq!(sink.into_inner().unwrap(), 0.5f32.to_le_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_wav_stream_sink_header() {
+        let mut buffer = Vec::new();
+        {
+            let mut sink = WavStreamSink::new(&mut buffer, 8000);
+            sink.write_samples(&[0.25, -0.25]).unwrap();
+            sink.finish().unwrap();
+        }
+        assert_eq!(buffer.len(), 44 + 4);
+        assert_eq!(&buffer[0..4], b"RIFF");
+        assert_eq!(&buffer[8..12], b"WAVE");
+        assert_eq!(u32::from_le_bytes(buffer[24..28].try_into().unwrap()), 8000);
+        assert_eq!(&buffer[36..40], b"data");
+
+        // 24位整数和多声道使用WAVE_FORMAT_EXTENSIBLE，并能被hound读取
+        let samples = [0.25f32, -0.5, 0.75];
+        for (format, routing, signal, mask) in [
+            (PcmFormat::Int24, ChannelRouting::mono(), 0, 0x1),
+            (PcmFormat::Int32, ChannelRouting::stereo_left(AuxChannel::Silent), 0, 0x3),
+            (PcmFormat::Int16, ChannelRouting::new(4, 2, AuxChannel::Silent).unwrap(), 2, 0xF),
+            (PcmFormat::Float32, ChannelRouting::mono(), 0, 0x1),
+        ] {
+            let channels = routing.channels();
+            let mut buffer = Vec::new();
+            let mut sink = WavStreamSink::with_routing(&mut buffer, 8000, format, Dither::None, routing);
+            sink.write_samples(&samples).unwrap();
+            sink.finish().unwrap();
+            drop(sink);
+            assert_eq!(u16::from_le_bytes([buffer[20], buffer[21]]), 0xFFFE, "{:?}", format);
+            assert_eq!(u32::from_le_bytes(buffer[40..44].try_into().unwrap()), mask);
+            assert_eq!(&buffer[60..64], b"data");
+
+            // 流式文件头的长度字段未知，改成实际长度后交给hound解析
+            let data_len = (buffer.len() - 68) as u32;
+            buffer[64..68].copy_from_slice(&data_len.to_le_bytes());
+            let riff_len = buffer.len() as u32 - 8;
+            buffer[4..8].copy_from_slice(&riff_len.to_le_bytes());
+            let reader = hound::WavReader::new(buffer.as_slice()).unwrap();
+            let spec = reader.spec();
+            assert_eq!((spec.channels, spec.bits_per_sample), (channels, format.bits_per_sample()));
+            assert_eq!(reader.len(), samples.len() as u32 * channels as u32);
+
+            let mut reader = AudioReader::wav(buffer.as_slice()).unwrap();
+            let mut decoded = Vec::new();
+            while reader.read_channel(signal, 16, &mut decoded).unwrap() > 0 {}
+            assert!(decoded.iter().zip(&samples).all(|(a, b)| (a - b).abs() < 1e-3), "{:?}", decoded);
+        }
+    }
+
+    #[test]
+    fn test_audio_reader() {
+        let samples: Vec<f32> = (0..1000).map(|i| ((i as f32) * 0.01).sin() * 0.5).collect();
+
+        // 流式WAV（长度未知），立体声中取信号所在的右声道
+        let mut buffer = Vec::new();
+        let routing = ChannelRouting::stereo_right(AuxChannel::Silent);
+        let mut sink = WavStreamSink::with_routing(&mut buffer, 8000, PcmFormat::Int16, Dither::None, routing);
+        sink.write_samples(&samples).unwrap();
+        sink.finish().unwrap();
+        drop(sink);
+        let mut reader = AudioReader::wav(buffer.as_slice()).unwrap();
+        assert_eq!((reader.sample_rate(), reader.channels()), (8000, 2));
+        let mut decoded = Vec::new();
+        while reader.read_channel(1, 300, &mut decoded).unwrap() > 0 {}
+        assert_eq!(decoded.len(), samples.len());
+        assert!(decoded.iter().zip(&samples).all(|(a, b)| (a - b).abs() < 1e-4));
+        assert!(reader.read_channel(2, 1, &mut decoded).is_err());
+
+        // 原始24位PCM
+        let mut sink = RawPcmSink::with_format(Vec::new(), PcmFormat::Int24, Dither::None);
+        sink.write_samples(&samples).unwrap();
+        let bytes = sink.into_inner().unwrap();
+        let mut reader = AudioReader::raw(bytes.as_slice(), 11025, PcmFormat::Int24, 1).unwrap();
+        let mut decoded = Vec::new();
+        assert_eq!(reader.read_channel(0, 4096, &mut decoded).unwrap(), samples.len());
+        assert_eq!(reader.read_channel(0, 4096, &mut decoded).unwrap(), 0);
+        assert!(decoded.iter().zip(&samples).all(|(a, b)| (a - b).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_quantizer_formats() {
+        let mut quantizer = Quantizer::new(PcmFormat::Int24, Dither::None);
+        assert_eq!(quantizer.quantize(1.0), 8_388_607);
+        assert_eq!(quantizer.quantize(-0.5), -4_194_303);
+
+        let mut bytes = Vec::new();
+        quantizer.encode(1.0, &mut bytes);
+        assert_eq!(bytes, vec![0xFF, 0xFF, 0x7F]);
+
+        let mut quantizer = Quantizer::new(PcmFormat::Int32, Dither::None);
+        assert_eq!(quantizer.quantize(1.0), i32::MAX);
+    }
+
+    #[test]
+    fn test_tpdf_dither() {
+        // 抖动误差不超过±1 LSB（加上舍入），且输出可复现
+        let mut first = Quantizer::new(PcmFormat::Int16, Dither::Tpdf);
+        let mut second = Quantizer::new(PcmFormat::Int16, Dither::Tpdf);
+        let mut sum = 0i64;
+        for i in 0..10000 {
+            let sample = 0.3 + (i as f32) * 1e-6;
+            let a = first.quantize(sample);
+            assert_eq!(a, second.quantize(sample));
+            let exact = sample as f64 * i16::MAX as f64;
+            assert!((a as f64 - exact).abs() <= 1.5);
+            sum += a as i64;
+        }
+        // 抖动是无偏的
+        let mean = sum as f64 / 10000.0;
+        let exact_mean = (0.3 + 0.005) * i16::MAX as f64;
+        assert!((mean - exact_mean).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_wav_writer_formats() {
+        for (format, bits, sample_format) in [
+            (PcmFormat::Int24, 24, hound::SampleFormat::Int),
+            (PcmFormat::Int32, 32, hound::SampleFormat::Int),
+            (PcmFormat::Float32, 32, hound::SampleFormat::Float),
+        ] {
+            let path = std::env::temp_dir().join(format!("sstv_rust_test_{:?}.wav", format));
+            let mut writer = WavWriter::with_format(&path, 8000, format, Dither::Tpdf).unwrap();
+            writer.write_normalized(&[0.0, 0.5, -0.5]).unwrap();
+            writer.finalize().unwrap();
+
+            let reader = hound::WavReader::open(&path).unwrap();
+            assert_eq!(reader.spec().bits_per_sample, bits);
+            assert_eq!(reader.spec().sample_format, sample_format);
+            assert_eq!(reader.len(), 3);
+            std::fs::remove_file(&path).ok();
+        }
+    }
+
+    #[test]
+    fn test_wav_writer_i16_samples() {
+        // 16位单声道原样写入
+        let path = std::env::temp_dir().join(format!("sstv_rust_i16_mono_{}.wav", std::process::id()));
+        let mut writer = WavWriter::new(&path, 8000).unwrap();
+        WavWriter::write_samples(&mut writer, &[16384, -32768, 7]).unwrap();
+        writer.finalize().unwrap();
+        let samples: Vec<i16> = hound::WavReader::open(&path).unwrap().samples().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, vec![16384, -32768, 7]);
+
+        // 24位立体声按格式缩放，并按路由写入左声道
+        let routing = ChannelRouting::stereo_left(AuxChannel::Silent);
+        let mut writer = WavWriter::with_routing(&path, 8000, PcmFormat::Int24, Dither::None, routing).unwrap();
+        WavWriter::write_samples(&mut writer, &[16384, -16384]).unwrap();
+        writer.finalize().unwrap();
+        let samples: Vec<i32> = hound::WavReader::open(&path).unwrap().samples().map(|s| s.unwrap()).collect();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(samples.len(), 4);
+        assert!((samples[0] - (16384 << 8)).abs() < 256, "{:?}", samples);
+        assert!((samples[2] + (16384 << 8)).abs() < 256, "{:?}", samples);
+        assert_eq!((samples[1], samples[3]), (0, 0));
+    }
+
+    #[test]
+    fn test_channel_routing_validation() {
+        assert!(ChannelRouting::new(0, 0, AuxChannel::Silent).is_err());
+        assert!(ChannelRouting::new(2, 2, AuxChannel::Silent).is_err());
+        assert!(ChannelRouting::new(2, 1, AuxChannel::PilotTone { frequency: 0.0, amplitude: 0.5 }).is_err());
+        assert!(ChannelRouting::new(4, 3, AuxChannel::Mirror).is_ok());
+    }
+
+    #[test]
+    fn test_stereo_routing() {
+        let mut frames = Vec::new();
+        let mut mixer = ChannelMixer::new(ChannelRouting::stereo_right(AuxChannel::Silent), 8000);
+        mixer.write_frame(0.5, |v| { frames.push(v); Ok(()) }).unwrap();
+        assert_eq!(frames, vec![0.0, 0.5]);
+
+        frames.clear();
+        let mut mixer = ChannelMixer::new(ChannelRouting::stereo_left(AuxChannel::Mirror), 8000);
+        mixer.write_frame(0.25, |v| { frames.push(v); Ok(()) }).unwrap();
+        assert_eq!(frames, vec![0.25, 0.25]);
+    }
+
+    #[test]
+    fn test_pilot_tone_channel() {
+        let routing = ChannelRouting::stereo_left(AuxChannel::PilotTone { frequency: 1000.0, amplitude: 0.5 });
+        let mut sink = RawPcmSink::with_routing(Vec::new(), 8000, PcmFormat::Float32, Dither::None, routing);
+        for _ in 0..8000 {
+            sink.write_sample(0.0).unwrap();
+        }
+        let bytes = sink.into_inner().unwrap();
+        let values: Vec<f32> = bytes.chunks(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())).collect();
+        assert_eq!(values.len(), 16000);
+
+        // 左声道为信号（此处静音），右声道为持续导频
+        let left: Vec<f32> = values.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = values.iter().skip(1).step_by(2).copied().collect();
+        assert!(left.iter().all(|&v| v == 0.0));
+        assert!((utils::calculate_rms(&right) - 0.5 / 2f32.sqrt()).abs() < 0.01);
+    }
+
+    // 加Hann窗的Goertzel幅度估计，正弦波幅度A返回约A
+    fn goertzel_amplitude(samples: &[f32], sample_rate: u32, frequency: f64) -> f64 {
+        let n = samples.len();
+        let omega = 2.0 * std::f64::consts::PI * frequency / sample_rate as f64;
+        let coeff = 2.0 * omega.cos();
+        let (mut s1, mut s2) = (0.0f64, 0.0f64);
+        for (i, &x) in samples.iter().enumerate() {
+            let window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos();
+            let s0 = x as f64 * window + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+        // Hann窗相干增益为0.5
+        power.sqrt() * 4.0 / n as f64
+    }
+
+    fn tone(frequency: f64, sample_rate: u32, seconds: f64) -> Vec<f32> {
+        (0..(sample_rate as f64 * seconds) as usize)
+            .map(|i| (0.8 * (2.0 * std::f64::consts::PI * frequency * i as f64 / sample_rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    #[test]
+    fn test_resampler_tone_accuracy() {
+        for &(from, to) in &[(48000, 6000), (48000, 8000), (48000, 11025), (48000, 44100), (44100, 6000), (6000, 48000)] {
+            for &frequency in &[1200.0, 1500.0, 1900.0, 2300.0] {
+                let output = utils::resample(&tone(frequency, from, 1.0), from, to).unwrap();
+                assert_eq!(output.len(), to as usize);
+
+                // 跳过首尾滤波器过渡段
+                let trim = to as usize / 20;
+                let body = &output[trim..output.len() - trim];
+
+                let amplitude = goertzel_amplitude(body, to, frequency);
+                assert!((amplitude - 0.8).abs() < 0.01, "{}->{} {}Hz: 幅度 {}", from, to, frequency, amplitude);
+
+                // 能量峰值应落在原频率上（±3Hz以内）
+                let peak = (-30..=30)
+                    .map(|step| frequency + step as f64 * 0.5)
+                    .max_by(|a, b| {
+                        goertzel_amplitude(body, to, *a)
+                            .partial_cmp(&goertzel_amplitude(body, to, *b))
+                            .unwrap()
+                    })
+                    .unwrap();
+                assert!((peak - frequency).abs() <= 3.0, "{}->{} {}Hz: 峰值 {}Hz", from, to, frequency, peak);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resampler_rejects_aliases() {
+        // 5kHz高于8kHz输出的奈奎斯特频率，不应混叠到3kHz
+        let output = utils::resample(&tone(5000.0, 48000, 1.0), 48000, 8000).unwrap();
+        let body = &output[400..7600];
+        let alias = goertzel_amplitude(body, 8000, 3000.0);
+        assert!(utils::linear_to_db((alias / 0.8) as f32) < -60.0, "混叠分量 {}", alias);
+    }
+
+    #[test]
+    fn test_resampler_identity_and_validation() {
+        let input = tone(1000.0, 8000, 0.1);
+        assert_eq!(utils::resample(&input, 8000, 8000).unwrap(), input);
+        assert!(utils::Resampler::new(0, 8000).is_err());
+        assert!(utils::Resampler::new(8000, 0).is_err());
+
+        // 相位数较大时逐样本计算系数
+        let resampler = utils::Resampler::with_quality(44100, 44101, utils::ResampleQuality::Fast).unwrap();
+        assert_eq!(resampler.output_len(44100), 44101);
+    }
+
+    #[test]
+    fn test_audio_format_from_path() {
+        assert_eq!(AudioFormat::from_path("a/b/out.FLAC"), Some(AudioFormat::Flac));
+        assert_eq!(AudioFormat::from_path("out.wav"), Some(AudioFormat::Wav));
+        assert_eq!(AudioFormat::from_path("out.f32"), Some(AudioFormat::Raw));
+        assert_eq!(AudioFormat::from_path("out"), None);
+        assert_eq!(AudioFormat::Flac.extension(), "flac");
+    }
+
+    fn filtered_gain_db(filter: &mut impl FnMut(&mut [f32]), frequency: f64, sample_rate: u32) -> f32 {
+        let mut samples = tone(frequency, sample_rate, 0.5);
+        let input_rms = utils::calculate_rms(&samples[samples.len() / 2..]);
+        filter(&mut samples);
+        utils::linear_to_db(utils::calculate_rms(&samples[samples.len() / 2..]) / input_rms)
+    }
+
+    #[test]
+    fn test_biquad_response() {
+        let mut lowpass = filters::Biquad::lowpass(1000.0, std::f64::consts::FRAC_1_SQRT_2, 48000).unwrap();
+        assert!((utils::linear_to_db(lowpass.magnitude_response(1000.0, 48000) as f32) + 3.01).abs() < 0.05);
+        let gain = filtered_gain_db(&mut |s| lowpass.process(s), 1000.0, 48000);
+        assert!((gain + 3.01).abs() < 0.1, "{}", gain);
+
+        let mut highpass = filters::Biquad::highpass(1000.0, std::f64::consts::FRAC_1_SQRT_2, 48000).unwrap();
+        assert!(filtered_gain_db(&mut |s| highpass.process(s), 100.0, 48000) < -35.0);
+
+        let mut notch = filters::Biquad::notch(1500.0, 5.0, 8000).unwrap();
+        assert!(filtered_gain_db(&mut |s| notch.process(s), 1500.0, 8000) < -40.0);
+
+        // 4阶Butterworth在截止频率2倍处约衰减24dB
+        let butterworth = filters::BiquadCascade::butterworth_lowpass(4, 1000.0, 48000).unwrap();
+        let db = utils::linear_to_db(butterworth.magnitude_response(2000.0, 48000) as f32);
+        assert!((db + 24.1).abs() < 1.0, "{}", db);
+
+        assert!(filters::Biquad::lowpass(5000.0, 0.7, 8000).is_err());
+        assert!(filters::BiquadCascade::butterworth_lowpass(3, 1000.0, 8000).is_err());
+    }
+
+    #[test]
+    fn test_sstv_bandpass() {
+        for sample_rate in [6000, 11025, 48000] {
+            let filter = filters::FirFilter::sstv_bandpass(sample_rate).unwrap();
+            for frequency in [1100.0, 1200.0, 1500.0, 1900.0, 2300.0] {
+                let db = utils::linear_to_db(filter.magnitude_response(frequency, sample_rate) as f32);
+                assert!(db.abs() < 0.1, "{}Hz@{}: {}dB", frequency, sample_rate, db);
+            }
+            for frequency in [300.0, 500.0] {
+                let db = utils::linear_to_db(filter.magnitude_response(frequency, sample_rate) as f32);
+                assert!(db < -55.0, "{}Hz@{}: {}dB", frequency, sample_rate, db);
+            }
+        }
+
+        // 带外的邻道成分被滤除，通带音调保持不变且时间对齐
+        let filter = filters::FirFilter::sstv_bandpass(48000).unwrap();
+        let mut samples: Vec<f32> = tone(1900.0, 48000, 0.2)
+            .iter()
+            .zip(tone(3500.0, 48000, 0.2))
+            .map(|(a, b)| 0.5 * a + 0.5 * b)
+            .collect();
+        filter.apply_aligned(&mut samples);
+        let reference: Vec<f32> = tone(1900.0, 48000, 0.2).iter().map(|s| 0.5 * s).collect();
+        let middle = 2000..7600;
+        let error: Vec<f32> = samples[middle.clone()].iter().zip(&reference[middle]).map(|(a, b)| a - b).collect();
+        assert!(utils::calculate_rms(&error) < 1e-3);
+
+        // 因果处理的结果等于延迟 group_delay 的对齐结果
+        let mut causal = filter.clone();
+        let mut streamed = tone(1900.0, 48000, 0.05);
+        let mut aligned = streamed.clone();
+        causal.process(&mut streamed);
+        filter.apply_aligned(&mut aligned);
+        let delay = filter.group_delay();
+        for n in delay..streamed.len() {
+            assert!((streamed[n] - aligned[n - delay]).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_apply_aligned_matches_causal_filter() {
+        // 覆盖短于群延迟、单块和跨越多块的长度
+        let filter = filters::FirFilter::sstv_bandpass(48000).unwrap();
+        let delay = filter.group_delay();
+        for len in [0, 1, 17, delay - 1, delay + 3, 1200, 2048, 9001] {
+            let input: Vec<f32> = (0..len).map(|n| ((n * 7919) % 201) as f32 / 100.0 - 1.0).collect();
+            let mut aligned = input.clone();
+            filter.apply_aligned(&mut aligned);
+
+            let mut causal = filter.clone();
+            let mut expected = input.clone();
+            expected.resize(len + delay, 0.0);
+            causal.process(&mut expected);
+            for n in 0..len {
+                assert!(
+                    (aligned[n] - expected[n + delay]).abs() < 1e-5,
+                    "len {} index {}: {} vs {}",
+                    len,
+                    n,
+                    aligned[n],
+                    expected[n + delay]
+                );
+            }
+        }
     }
 
     #[test]
@@ -0,0 +1,485 @@
+//! 批量编码模块
+//!
+//! 把多张输入图像按“模式 × 采样率 × 文件格式”的组合矩阵批量编码为音频文件。
+//! 各输入图像在限定线程数的线程池中并行处理，每个任务边生成边写入文件，
+//! 内存占用只与并行数有关；单个任务失败只记录在报告中，不会中断整个批次。
+//!
+//! ```rust,no_run
+//! use sstv_rust::batch::BatchEncoder;
+//! use sstv_rust::{AudioFormat, SstvMode};
+//!
+//! let report = BatchEncoder::new("media")
+//!     .with_input_directory("photos").unwrap()
+//!     .with_modes(&[SstvMode::Robot36, SstvMode::MartinM1])
+//!     .with_sample_rates(&[8000, 44100])
+//!     .with_formats(&[AudioFormat::Wav, AudioFormat::Flac])
+//!     .run()
+//!     .unwrap();
+//!
+//! println!("成功 {} 个，失败 {} 个", report.outputs.len(), report.failures.len());
+//! ```
+
+use crate::audio::AudioFormat;
+use crate::error::{Result, SstvError};
+use crate::preprocess::load_image;
+use crate::sstv::{SstvMode, SstvModulator};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 扫描目录时识别的图像扩展名（不区分大小写）
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp", "gif", "tif", "tiff", "webp"];
+
+/// 调制器附加设置（预处理、样本格式、音调整形等），在套用批次的模式、采样率和格式之前调用
+type ModulatorSetup = dyn Fn(SstvModulator) -> SstvModulator + Send + Sync;
+
+/// 单个编码任务
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    /// 输入图像路径
+    pub input: PathBuf,
+    pub mode: SstvMode,
+    pub sample_rate: u32,
+    pub format: AudioFormat,
+    /// 输出文件路径
+    pub output: PathBuf,
+}
+
+/// 成功完成的任务
+#[derive(Debug, Clone)]
+pub struct BatchOutput {
+    pub job: BatchJob,
+    /// 单声道样本数
+    pub samples: usize,
+    /// 音频时长
+    pub audio_duration: Duration,
+    /// 编码耗时（含写入文件）
+    pub elapsed: Duration,
+    /// 输出文件大小（字节）
+    pub file_size: u64,
+}
+
+/// 失败的任务
+#[derive(Debug)]
+pub struct BatchFailure {
+    /// 输入图像路径
+    pub input: PathBuf,
+    /// 失败的任务；为 `None` 表示输入图像本身无法加载，该图像的全部任务均未执行
+    pub job: Option<BatchJob>,
+    pub error: SstvError,
+}
+
+/// 批量编码报告
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// 成功的任务，按输入顺序和组合矩阵顺序排列
+    pub outputs: Vec<BatchOutput>,
+    /// 失败的任务
+    pub failures: Vec<BatchFailure>,
+    /// 整个批次的耗时
+    pub elapsed: Duration,
+}
+
+impl BatchReport {
+    /// 是否全部成功
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// 全部输出文件的音频总时长
+    pub fn total_audio_duration(&self) -> Duration {
+        self.outputs.iter().map(|output| output.audio_duration).sum()
+    }
+
+    /// 全部输出文件的总大小（字节）
+    pub fn total_file_size(&self) -> u64 {
+        self.outputs.iter().map(|output| output.file_size).sum()
+    }
+
+    /// 转换为JSON，便于写入日志或交给其他工具
+    pub fn to_json(&self) -> serde_json::Value {
+        let job_json = |job: &BatchJob| {
+            serde_json::json!({
+                "input": job.input.display().to_string(),
+                "mode": job.mode.get_mode_name(),
+                "sample_rate": job.sample_rate,
+                "format": job.format.name(),
+                "output": job.output.display().to_string()
+            })
+        };
+        serde_json::json!({
+            "elapsed_seconds": self.elapsed.as_secs_f64(),
+            "total_audio_seconds": self.total_audio_duration().as_secs_f64(),
+            "total_file_size": self.total_file_size(),
+            "outputs": self.outputs.iter().map(|output| serde_json::json!({
+                "job": job_json(&output.job),
+                "samples": output.samples,
+                "audio_seconds": output.audio_duration.as_secs_f64(),
+                "elapsed_seconds": output.elapsed.as_secs_f64(),
+                "file_size": output.file_size
+            })).collect::<Vec<_>>(),
+            "failures": self.failures.iter().map(|failure| serde_json::json!({
+                "input": failure.input.display().to_string(),
+                "job": failure.job.as_ref().map(job_json),
+                "error": failure.error.to_string()
+            })).collect::<Vec<_>>()
+        })
+    }
+}
+
+/// 批量编码器
+pub struct BatchEncoder {
+    inputs: Vec<PathBuf>,
+    output_dir: PathBuf,
+    modes: Vec<SstvMode>,
+    sample_rates: Vec<u32>,
+    formats: Vec<AudioFormat>,
+    max_parallel_jobs: usize,
+    setup: Option<Box<ModulatorSetup>>,
+}
+
+impl BatchEncoder {
+    /// 创建批量编码器，输出文件写入 `output_dir`（不存在时自动创建）
+    ///
+    /// 默认组合为全部四种模式、各模式的默认采样率、WAV格式，并行数为CPU核心数。
+    pub fn new<P: AsRef<Path>>(output_dir: P) -> Self {
+        Self {
+            inputs: Vec::new(),
+            output_dir: output_dir.as_ref().to_path_buf(),
+            modes: vec![SstvMode::ScottieDx, SstvMode::Robot36, SstvMode::Pd120, SstvMode::MartinM1],
+            sample_rates: Vec::new(),
+            formats: vec![AudioFormat::Wav],
+            max_parallel_jobs: rayon::current_num_threads(),
+            setup: None,
+        }
+    }
+
+    /// 添加一张输入图像
+    pub fn add_input<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.inputs.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// 添加多张输入图像
+    pub fn with_inputs<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.inputs.extend(paths.into_iter().map(|path| path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// 添加目录中的全部图像文件（不递归，按文件名排序）
+    pub fn with_input_directory<P: AsRef<Path>>(mut self, dir: P) -> Result<Self> {
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_image = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IMAGE_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(ext)));
+            if is_image && path.is_file() {
+                found.push(path);
+            }
+        }
+        found.sort();
+        self.inputs.extend(found);
+        Ok(self)
+    }
+
+    /// 设置要生成的SSTV模式
+    pub fn with_modes(mut self, modes: &[SstvMode]) -> Self {
+        self.modes = modes.to_vec();
+        self
+    }
+
+    /// 设置要生成的采样率；为空时每种模式使用 `SstvMode::get_default_sample_rate()`
+    pub fn with_sample_rates(mut self, sample_rates: &[u32]) -> Self {
+        self.sample_rates = sample_rates.to_vec();
+        self
+    }
+
+    /// 设置要生成的文件格式
+    pub fn with_formats(mut self, formats: &[AudioFormat]) -> Self {
+        self.formats = formats.to_vec();
+        self
+    }
+
+    /// 设置同时处理的输入图像数上限（至少为1），决定线程数和峰值内存
+    pub fn with_max_parallel_jobs(mut self, jobs: usize) -> Self {
+        self.max_parallel_jobs = jobs.max(1);
+        self
+    }
+
+    /// 设置每个任务的调制器附加配置，如预处理、样本格式或音调整形
+    ///
+    /// ```rust,no_run
+    /// use sstv_rust::batch::BatchEncoder;
+    /// use sstv_rust::{FitMode, CropAnchor, PcmFormat};
+    ///
+    /// let encoder = BatchEncoder::new("out").with_modulator_setup(|modulator| {
+    ///     modulator.with_fit_mode(FitMode::CropToFill(CropAnchor::Center)).with_output_format(PcmFormat::Int24)
+    /// });
+    /// ```
+    pub fn with_modulator_setup<F>(mut self, setup: F) -> Self
+    where
+        F: Fn(SstvModulator) -> SstvModulator + Send + Sync + 'static,
+    {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// 展开全部任务（输入 × 模式 × 采样率 × 格式）
+    ///
+    /// 输出文件名为 `<文件名>_<模式>_<采样率>hz.<扩展名>`；不同目录下的同名输入追加序号区分。
+    pub fn jobs(&self) -> Vec<BatchJob> {
+        let stems: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|input| {
+                input
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "image".to_string())
+            })
+            .collect();
+        let mut stem_counts: HashMap<&str, usize> = HashMap::new();
+        for stem in &stems {
+            *stem_counts.entry(stem.as_str()).or_default() += 1;
+        }
+
+        let rates_per_mode = self.sample_rates.len().max(1);
+        let mut jobs = Vec::with_capacity(self.inputs.len() * self.modes.len() * rates_per_mode * self.formats.len());
+        for (index, (input, stem)) in self.inputs.iter().zip(&stems).enumerate() {
+            let name = if stem_counts[stem.as_str()] > 1 {
+                format!("{}_{}", stem, index + 1)
+            } else {
+                stem.clone()
+            };
+            for &mode in &self.modes {
+                let default_rate = [mode.get_default_sample_rate()];
+                let sample_rates = if self.sample_rates.is_empty() { &default_rate[..] } else { &self.sample_rates[..] };
+                for &sample_rate in sample_rates {
+                    for &format in &self.formats {
+                        let filename = format!("{}_{}_{}hz.{}", name, mode.get_mode_name(), sample_rate, format.extension());
+                        jobs.push(BatchJob {
+                            input: input.clone(),
+                            mode,
+                            sample_rate,
+                            format,
+                            output: self.output_dir.join(filename),
+                        });
+                    }
+                }
+            }
+        }
+        jobs
+    }
+
+    /// 执行批量编码
+    ///
+    /// 只有无法创建输出目录或线程池时返回错误；单个任务的错误记录在报告的 `failures` 中。
+    pub fn run(&self) -> Result<BatchReport> {
+        let start = Instant::now();
+        std::fs::create_dir_all(&self.output_dir)?;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.max_parallel_jobs)
+            .build()
+            .map_err(|e| SstvError::ModulationError { message: format!("无法创建线程池: {}", e) })?;
+
+        // 按输入图像分组，每张图像只解码一次
+        let mut groups: Vec<(PathBuf, Vec<BatchJob>)> = Vec::new();
+        for job in self.jobs() {
+            match groups.last_mut() {
+                Some((input, jobs)) if *input == job.input => jobs.push(job),
+                _ => groups.push((job.input.clone(), vec![job])),
+            }
+        }
+
+        let results: Vec<(Vec<BatchOutput>, Vec<BatchFailure>)> = pool.install(|| {
+            groups
+                .into_par_iter()
+                .map(|(input, jobs)| self.run_input(input, jobs))
+                .collect()
+        });
+
+        let mut report = BatchReport::default();
+        for (outputs, failures) in results {
+            report.outputs.extend(outputs);
+            report.failures.extend(failures);
+        }
+        report.elapsed = start.elapsed();
+        Ok(report)
+    }
+
+    /// 处理一张输入图像的全部任务
+    fn run_input(&self, input: PathBuf, jobs: Vec<BatchJob>) -> (Vec<BatchOutput>, Vec<BatchFailure>) {
+        let mut outputs = Vec::new();
+        let mut failures = Vec::new();
+
+        let image = match load_image(&input) {
+            Ok(image) => image,
+            Err(error) => {
+                failures.push(BatchFailure { input, job: None, error });
+                return (outputs, failures);
+            }
+        };
+
+        for job in jobs {
+            let start = Instant::now();
+            // 只删除本任务新建的文件，保留之前运行留下的输出
+            let created = !job.output.exists();
+            let mut modulator = SstvModulator::new(job.mode);
+            if let Some(setup) = &self.setup {
+                modulator = setup(modulator);
+            }
+            let result = modulator
+                .with_sample_rate(job.sample_rate)
+                .and_then(|modulator| modulator.with_audio_format(job.format).encode_to_file(&image, &job.output))
+                .and_then(|samples| Ok((samples, std::fs::metadata(&job.output)?.len())));
+            match result {
+                Ok((samples, file_size)) => outputs.push(BatchOutput {
+                    audio_duration: Duration::from_secs_f64(samples as f64 / job.sample_rate as f64),
+                    elapsed: start.elapsed(),
+                    samples,
+                    file_size,
+                    job,
+                }),
+                Err(error) => {
+                    // 删除写了一半的文件
+                    if created {
+                        let _ = std::fs::remove_file(&job.output);
+                    }
+                    failures.push(BatchFailure { input: input.clone(), job: Some(job), error });
+                }
+            }
+        }
+        (outputs, failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::PcmFormat;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sstv_batch_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_image(path: &Path) {
+        let image = image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([(x * 4) as u8, (y * 5) as u8, 128]));
+        image.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_jobs_matrix_and_names() {
+        let encoder = BatchEncoder::new("out")
+            .with_inputs(["a/photo.jpg", "b/photo.png", "c/other.bmp"])
+            .with_modes(&[SstvMode::Robot36, SstvMode::MartinM1])
+            .with_sample_rates(&[8000, 11025])
+            .with_formats(&[AudioFormat::Wav, AudioFormat::Flac]);
+        let jobs = encoder.jobs();
+        assert_eq!(jobs.len(), 3 * 2 * 2 * 2);
+        assert_eq!(jobs[0].output, Path::new("out").join("photo_1_Robot36_8000hz.wav"));
+        assert_eq!(jobs[1].output, Path::new("out").join("photo_1_Robot36_8000hz.flac"));
+        assert_eq!(jobs[8].output, Path::new("out").join("photo_2_Robot36_8000hz.wav"));
+        assert_eq!(jobs[23].output, Path::new("out").join("other_MartinM1_11025hz.flac"));
+
+        // 输出文件名互不相同
+        let mut names: Vec<_> = jobs.iter().map(|job| job.output.clone()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), jobs.len());
+    }
+
+    #[test]
+    fn test_batch_isolates_failures() {
+        let dir = temp_dir("run");
+        let inputs = dir.join("inputs");
+        std::fs::create_dir_all(&inputs).unwrap();
+        write_image(&inputs.join("good.png"));
+        std::fs::write(inputs.join("broken.jpg"), b"not an image").unwrap();
+        std::fs::write(inputs.join("notes.txt"), b"ignored").unwrap();
+
+        // FLAC不支持浮点样本，这些任务失败但不影响WAV任务
+        let report = BatchEncoder::new(dir.join("out"))
+            .with_input_directory(&inputs)
+            .unwrap()
+            .with_modes(&[SstvMode::Robot36])
+            .with_sample_rates(&[8000, 11025])
+            .with_formats(&[AudioFormat::Wav, AudioFormat::Flac])
+            .with_max_parallel_jobs(2)
+            .with_modulator_setup(|modulator| modulator.with_output_format(PcmFormat::Float32))
+            .run()
+            .unwrap();
+
+        assert!(!report.is_success());
+        assert_eq!(report.outputs.len(), 2);
+        for output in &report.outputs {
+            assert_eq!(output.job.format, AudioFormat::Wav);
+            assert!(output.job.output.exists());
+            assert_eq!(output.file_size, std::fs::metadata(&output.job.output).unwrap().len());
+            let seconds = output.audio_duration.as_secs_f64();
+            assert!((seconds - output.samples as f64 / output.job.sample_rate as f64).abs() < 1e-6);
+            assert!(seconds > 38.0 && seconds < 40.0, "{}", seconds);
+        }
+
+        // 损坏的图像记录为一条输入级失败，FLAC任务各记录一条
+        assert_eq!(report.failures.len(), 3);
+        let broken: Vec<_> = report.failures.iter().filter(|f| f.job.is_none()).collect();
+        assert_eq!(broken.len(), 1);
+        assert!(broken[0].input.ends_with("broken.jpg"));
+        for failure in report.failures.iter().filter_map(|f| f.job.as_ref()) {
+            assert_eq!(failure.format, AudioFormat::Flac);
+            assert!(!failure.output.exists());
+        }
+
+        let json = report.to_json();
+        assert_eq!(json["outputs"].as_array().unwrap().len(), 2);
+        assert_eq!(json["failures"].as_array().unwrap().len(), 3);
+        assert!(json["failures"][0]["error"].is_string());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_job_keeps_existing_output() {
+        let dir = temp_dir("existing");
+        let input = dir.join("good.png");
+        write_image(&input);
+        let out = dir.join("out");
+        std::fs::create_dir_all(&out).unwrap();
+        // 采样率过低和FLAC浮点样本都在写入前失败，之前运行留下的文件不能被删除或截断
+        let existing = [out.join("good_Robot36_4000hz.wav"), out.join("good_Robot36_8000hz.flac")];
+        for path in &existing {
+            std::fs::write(path, b"previous run").unwrap();
+        }
+
+        let report = BatchEncoder::new(&out)
+            .add_input(&input)
+            .with_modes(&[SstvMode::Robot36])
+            .with_sample_rates(&[4000])
+            .run()
+            .unwrap();
+        assert_eq!(report.failures.len(), 1);
+        let report = BatchEncoder::new(&out)
+            .add_input(&input)
+            .with_modes(&[SstvMode::Robot36])
+            .with_formats(&[AudioFormat::Flac])
+            .with_modulator_setup(|modulator| modulator.with_output_format(PcmFormat::Float32))
+            .run()
+            .unwrap();
+        assert_eq!(report.failures.len(), 1);
+
+        for path in &existing {
+            assert_eq!(std::fs::read(path).unwrap(), b"previous run");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
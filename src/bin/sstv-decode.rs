@@ -0,0 +1,283 @@
+//! sstv-decode：从录音中解码SSTV图像的命令行工具
+//!
+//! ```text
+//! sstv-decode recording.wav -o images/
+//! sstv-encode photo.jpg -o - | sstv-decode -
+//! arecord -f S16_LE -r 48000 -t raw | sstv-decode - -r 48000
+//! ```
+
+use clap::{Parser, ValueEnum};
+use sstv_rust::{AudioReader, DecodedImage, PcmFormat, SstvDecoder, SstvError};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// 每次读取的帧数
+const CHUNK_FRAMES: usize = 16384;
+
+/// 从WAV或原始PCM录音中解码SSTV图像，每幅图像保存为PNG并附带JSON接收参数
+#[derive(Parser, Debug)]
+#[command(name = "sstv-decode", version)]
+struct Cli {
+    /// 输入录音；`-` 表示标准输入
+    input: PathBuf,
+
+    /// 输出目录
+    #[arg(short, long, default_value = ".")]
+    output_dir: PathBuf,
+
+    /// 输出文件名前缀；默认为输入文件名，标准输入时为 `sstv`
+    #[arg(short, long)]
+    prefix: Option<String>,
+
+    /// 输入格式；默认根据RIFF文件头判断
+    #[arg(short, long, value_enum)]
+    format: Option<FormatArg>,
+
+    /// 原始PCM的采样率（Hz）
+    #[arg(short = 'r', long)]
+    sample_rate: Option<u32>,
+
+    /// 原始PCM的样本格式（位深度）
+    #[arg(short, long, value_enum, default_value_t = BitsArg::S16)]
+    bits: BitsArg,
+
+    /// 原始PCM的声道数
+    #[arg(long, default_value_t = 1)]
+    channels: u16,
+
+    /// 解码的声道（从0开始）
+    #[arg(long, default_value_t = 0)]
+    channel: u16,
+
+    /// 不按测得的时钟误差校正图像倾斜
+    #[arg(long)]
+    no_slant_correction: bool,
+
+    /// VIS检测允许的最大频率偏移（Hz）
+    #[arg(long, default_value_t = 250.0)]
+    max_offset: f64,
+
+    /// 不输出进度信息
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FormatArg {
+    Wav,
+    Raw,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum BitsArg {
+    #[value(name = "16")]
+    S16,
+    #[value(name = "24")]
+    S24,
+    #[value(name = "32")]
+    S32,
+    #[value(name = "float")]
+    Float,
+}
+
+impl From<BitsArg> for PcmFormat {
+    fn from(bits: BitsArg) -> Self {
+        match bits {
+            BitsArg::S16 => PcmFormat::Int16,
+            BitsArg::S24 => PcmFormat::Int24,
+            BitsArg::S32 => PcmFormat::Int32,
+            BitsArg::Float => PcmFormat::Float32,
+        }
+    }
+}
+
+impl Cli {
+    fn is_stdin(&self) -> bool {
+        self.input.as_os_str() == "-"
+    }
+
+    /// 输出文件名前缀
+    fn prefix(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => prefix.clone(),
+            None if self.is_stdin() => "sstv".to_string(),
+            None => self
+                .input
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "sstv".to_string()),
+        }
+    }
+
+    /// 打开输入；未指定格式时读取前4个字节判断是否为WAV
+    fn open(&self) -> Result<AudioReader<Box<dyn Read>>, SstvError> {
+        let mut input: Box<dyn Read> = if self.is_stdin() {
+            Box::new(std::io::stdin().lock())
+        } else {
+            let file = std::fs::File::open(&self.input)
+                .map_err(|e| SstvError::InvalidFormat(format!("{}: {}", self.input.display(), e)))?;
+            Box::new(file)
+        };
+        let format = match self.format {
+            Some(format) => format,
+            None => {
+                let mut magic = Vec::with_capacity(4);
+                (&mut input).take(4).read_to_end(&mut magic)?;
+                let format = if magic == b"RIFF" { FormatArg::Wav } else { FormatArg::Raw };
+                input = Box::new(std::io::Cursor::new(magic).chain(input));
+                format
+            }
+        };
+        match format {
+            FormatArg::Wav => AudioReader::wav(input),
+            FormatArg::Raw => {
+                let sample_rate = self.sample_rate.ok_or_else(|| {
+                    SstvError::InvalidFormat("输入不是WAV文件，按原始PCM读取时需要用 -r/--sample-rate 指定采样率".to_string())
+                })?;
+                AudioReader::raw(input, sample_rate, self.bits.into(), self.channels)
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("错误: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), SstvError> {
+    let mut reader = cli.open()?;
+    let mut decoder = SstvDecoder::new(reader.sample_rate())?
+        .with_slant_correction(!cli.no_slant_correction)
+        .with_max_frequency_offset(cli.max_offset);
+    std::fs::create_dir_all(&cli.output_dir)?;
+    if !cli.quiet {
+        eprintln!(
+            "解码 {}（{}Hz，声道{}/{}）",
+            if cli.is_stdin() { "标准输入".to_string() } else { cli.input.display().to_string() },
+            reader.sample_rate(),
+            cli.channel,
+            reader.channels()
+        );
+    }
+
+    let prefix = cli.prefix();
+    let mut count = 0;
+    let mut samples = Vec::with_capacity(CHUNK_FRAMES);
+    loop {
+        samples.clear();
+        if reader.read_channel(cli.channel, CHUNK_FRAMES, &mut samples)? == 0 {
+            break;
+        }
+        for image in decoder.push(&samples) {
+            count += 1;
+            save(cli, &prefix, count, &image)?;
+        }
+    }
+    for image in decoder.finish() {
+        count += 1;
+        save(cli, &prefix, count, &image)?;
+    }
+
+    if !cli.quiet {
+        if count == 0 {
+            eprintln!("未发现SSTV信号");
+        } else {
+            eprintln!("共解码{}幅图像", count);
+        }
+    }
+    Ok(())
+}
+
+/// 保存PNG图像和同名JSON接收参数
+fn save(cli: &Cli, prefix: &str, index: usize, decoded: &DecodedImage) -> Result<(), SstvError> {
+    let stem = format!("{}_{:03}_{}", prefix, index, decoded.mode.get_mode_name());
+    let image_path = cli.output_dir.join(format!("{}.png", stem));
+    let json_path = cli.output_dir.join(format!("{}.json", stem));
+    decoded.image.save(&image_path)?;
+
+    let mut json = decoded.to_json();
+    json["image"] = file_name(&image_path).into();
+    json["source"] = if cli.is_stdin() { "-".to_string() } else { cli.input.display().to_string() }.into();
+    let text = serde_json::to_string_pretty(&json).map_err(|e| SstvError::InvalidFormat(e.to_string()))?;
+    std::fs::write(&json_path, text)?;
+
+    if !cli.quiet {
+        eprintln!(
+            "[{}] {} {} SNR {:.1}dB 偏移 {:+.1}Hz 倾斜 {:+.2}°（{:+.0}ppm{}）{} -> {}",
+            index,
+            decoded.mode.get_mode_name(),
+            format_time(decoded.start_time),
+            decoded.snr_db,
+            decoded.frequency_offset,
+            decoded.slant_degrees,
+            decoded.clock_error_ppm,
+            if decoded.slant_corrected { "，已校正" } else { "" },
+            if decoded.complete { "" } else { "不完整" },
+            image_path.display()
+        );
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+fn format_time(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).floor();
+    format!("{:02}:{:06.3}", minutes as u64, seconds - minutes * 60.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let cli = Cli::try_parse_from(["sstv-decode", "captures/pass.wav", "-o", "out"]).unwrap();
+        assert_eq!(cli.prefix(), "pass");
+        assert_eq!(cli.output_dir, PathBuf::from("out"));
+        assert!(!cli.no_slant_correction);
+
+        let cli = Cli::try_parse_from([
+            "sstv-decode", "-", "-r", "48000", "-b", "24", "--channels", "2", "--channel", "1", "--no-slant-correction",
+        ])
+        .unwrap();
+        assert!(cli.is_stdin());
+        assert_eq!(cli.prefix(), "sstv");
+        assert_eq!(cli.sample_rate, Some(48000));
+        assert_eq!(PcmFormat::from(cli.bits), PcmFormat::Int24);
+        assert_eq!((cli.channels, cli.channel), (2, 1));
+        assert!(cli.no_slant_correction);
+
+        assert!(Cli::try_parse_from(["sstv-decode"]).is_err());
+        assert!(Cli::try_parse_from(["sstv-decode", "in.raw", "-b", "8"]).is_err());
+    }
+
+    #[test]
+    fn test_raw_input_requires_sample_rate() {
+        let path = std::env::temp_dir().join(format!("sstv_decode_raw_{}.raw", std::process::id()));
+        std::fs::write(&path, [0u8; 64]).unwrap();
+        let cli = Cli::try_parse_from(["sstv-decode", path.to_str().unwrap()]).unwrap();
+        assert!(cli.open().is_err());
+        let cli = Cli::try_parse_from(["sstv-decode", path.to_str().unwrap(), "-r", "8000"]).unwrap();
+        assert_eq!(cli.open().unwrap().sample_rate(), 8000);
+        let cli = Cli::try_parse_from(["sstv-decode", path.to_str().unwrap(), "-r", "4000"]).unwrap();
+        assert!(matches!(cli.open(), Err(SstvError::InvalidSampleRate { min_rate: 6000, .. })));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,756 @@
+//! sstv-encode：把图像编码为SSTV音频的命令行工具
+//!
+//! ```text
+//! sstv-encode photo.jpg -m martin-m1 -r 48000 -o photo.wav
+//! sstv-encode photo.jpg -m robot36 -r 11025 -o - | aplay
+//! sstv-encode --list-modes
+//! sstv-encode -m pd120 -r 44100 -b 24 -o out.flac --dry-run
+//! sstv-encode photo.jpg --callsign VK7KSM --station-id --overlay-text "CQ DX" -o photo.wav
+//! ```
+
+use clap::{Parser, ValueEnum};
+use image::DynamicImage;
+use sstv_rust::audio::{AuxChannel, ChannelRouting};
+use sstv_rust::{
+    get_supported_modes, load_image, AudioFormat, BarFill, ColorAdjustment, CropAnchor, Dither,
+    FitMode, FrequencyMapping, OverlayPosition, PcmFormat, RawPcmSink, ResizeFilter, SampleSink,
+    SineMode, SstvError, SstvMode, SstvModulator, TextOverlay, ToneShaping, WavStreamSink,
+};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+/// 把图像编码为SSTV音频（WAV/FLAC/原始PCM），写入文件或标准输出
+#[derive(Parser, Debug)]
+#[command(name = "sstv-encode", version)]
+struct Cli {
+    /// 输入图像（JPG、PNG、BMP等，按EXIF方向自动旋转）
+    #[arg(required_unless_present_any = ["list_modes", "dry_run"])]
+    input: Option<PathBuf>,
+
+    /// 输出文件；`-` 表示标准输出。默认为输入文件名加模式名
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// SSTV模式：ScottieDX、Robot36、PD120、MartinM1（不区分大小写，也可写作 pd-120、"Robot 36"、m1 等）
+    #[arg(short, long, default_value = "Robot36", value_parser = |s: &str| s.parse::<SstvMode>())]
+    mode: SstvMode,
+
+    /// 采样率（Hz）；默认为模式的默认采样率（Robot36为8000Hz，其他模式为6000Hz）
+    #[arg(short = 'r', long)]
+    sample_rate: Option<u32>,
+
+    /// 样本格式（位深度）
+    #[arg(short, long, value_enum, default_value_t = BitsArg::S16)]
+    bits: BitsArg,
+
+    /// 输出文件格式；默认按输出文件扩展名推断，标准输出时为WAV
+    #[arg(short, long, value_enum)]
+    format: Option<FormatArg>,
+
+    /// 量化抖动
+    #[arg(long, value_enum, default_value_t = DitherArg::None)]
+    dither: DitherArg,
+
+    /// 图像适配策略
+    #[arg(long, value_enum, default_value_t = FitArg::Letterbox)]
+    fit: FitArg,
+
+    /// 裁剪铺满（`--fit crop`）时的锚点
+    #[arg(long, value_enum, default_value_t = AnchorArg::Center)]
+    anchor: AnchorArg,
+
+    /// 缩放滤波器
+    #[arg(long, value_enum, default_value_t = FilterArg::Lanczos3)]
+    resize_filter: FilterArg,
+
+    /// 使用针对SSTV接收调校的色彩（补偿偏灰、发淡）
+    #[arg(long)]
+    sstv_colors: bool,
+
+    /// 声道：mono、left、right（立体声时SSTV信号所在声道）
+    #[arg(long, value_enum, default_value_t = ChannelsArg::Mono)]
+    channels: ChannelsArg,
+
+    /// 立体声另一声道的内容：mirror、silent，或导频频率（Hz，用于VOX/PTT）
+    #[arg(long, default_value = "silent", value_parser = parse_aux)]
+    aux: AuxChannel,
+
+    /// 全部音调频率偏移（Hz），补偿电台失谐
+    #[arg(long, allow_hyphen_values = true)]
+    offset: Option<f64>,
+
+    /// 以该频率（Hz）为中心镜像全部音调，补偿LSB造成的频谱反转
+    #[arg(long)]
+    mirror: Option<f64>,
+
+    /// 首尾包络渐变时长（毫秒）
+    #[arg(long, default_value_t = 0.0)]
+    ramp_ms: f64,
+
+    /// 像素间频率平滑时长（毫秒）
+    #[arg(long, default_value_t = 0.0)]
+    smoothing_ms: f64,
+
+    /// 使用查表正弦振荡器（更快，跨平台逐位一致）
+    #[arg(long)]
+    sine_table: bool,
+
+    /// 应用1000-2500Hz发射带通滤波（需要在内存中保留整段音频）
+    #[arg(long)]
+    tx_filter: bool,
+
+    /// 另存处理后（送入调制器）的图像
+    #[arg(long)]
+    save_image: Option<PathBuf>,
+
+    /// 在WAV文件中写入VIS码和每个扫描块的 cue 标记
+    #[arg(long)]
+    cue_markers: bool,
+
+    /// 把传输时间轴（每段音调的样本位置）保存为JSON
+    #[arg(long)]
+    timeline: Option<PathBuf>,
+
+    /// 在WAV文件中写入模式、VIS码、采样率、时间和源图像哈希
+    #[arg(long)]
+    wav_metadata: bool,
+
+    /// 写入WAV元数据的呼号（隐含 --wav-metadata）
+    #[arg(long)]
+    callsign: Option<String>,
+
+    /// 把 --callsign 的呼号作为台标叠加到图像上
+    #[arg(long, requires = "callsign")]
+    station_id: bool,
+
+    /// 台标的位置
+    #[arg(long, value_enum, default_value_t = PositionArg::TopLeft)]
+    station_id_position: PositionArg,
+
+    /// 叠加到图像上的文字（字母、数字和常用标点）
+    #[arg(long)]
+    overlay_text: Option<String>,
+
+    /// 叠加文字的位置
+    #[arg(long, value_enum, default_value_t = PositionArg::BottomLeft)]
+    overlay_position: PositionArg,
+
+    /// 台标和叠加文字的颜色：#RRGGBB 或 R,G,B
+    #[arg(long, default_value = "#FFFFFF", value_parser = parse_color)]
+    overlay_color: [u8; 3],
+
+    /// 台标和叠加文字的放大倍数（每个字体点的像素数）
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..=16))]
+    overlay_scale: u32,
+
+    /// 不在台标和叠加文字下方绘制黑色底色
+    #[arg(long)]
+    no_overlay_background: bool,
+
+    /// 列出支持的SSTV模式后退出
+    #[arg(long)]
+    list_modes: bool,
+
+    /// 只计算时长和文件大小，不生成音频
+    #[arg(long)]
+    dry_run: bool,
+
+    /// 不输出进度信息
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum BitsArg {
+    #[value(name = "16")]
+    S16,
+    #[value(name = "24")]
+    S24,
+    #[value(name = "32")]
+    S32,
+    #[value(name = "float")]
+    Float,
+}
+
+impl From<BitsArg> for PcmFormat {
+    fn from(bits: BitsArg) -> Self {
+        match bits {
+            BitsArg::S16 => PcmFormat::Int16,
+            BitsArg::S24 => PcmFormat::Int24,
+            BitsArg::S32 => PcmFormat::Int32,
+            BitsArg::Float => PcmFormat::Float32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FormatArg {
+    Wav,
+    Flac,
+    Raw,
+}
+
+impl From<FormatArg> for AudioFormat {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Wav => AudioFormat::Wav,
+            FormatArg::Flac => AudioFormat::Flac,
+            FormatArg::Raw => AudioFormat::Raw,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DitherArg {
+    None,
+    Tpdf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FitArg {
+    /// 黑边留白
+    Letterbox,
+    /// 模糊背景留白
+    Blur,
+    /// 裁剪铺满
+    Crop,
+    /// 拉伸
+    Stretch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum AnchorArg {
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl From<AnchorArg> for CropAnchor {
+    fn from(anchor: AnchorArg) -> Self {
+        match anchor {
+            AnchorArg::Center => CropAnchor::Center,
+            AnchorArg::Top => CropAnchor::Top,
+            AnchorArg::Bottom => CropAnchor::Bottom,
+            AnchorArg::Left => CropAnchor::Left,
+            AnchorArg::Right => CropAnchor::Right,
+            AnchorArg::TopLeft => CropAnchor::TopLeft,
+            AnchorArg::TopRight => CropAnchor::TopRight,
+            AnchorArg::BottomLeft => CropAnchor::BottomLeft,
+            AnchorArg::BottomRight => CropAnchor::BottomRight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PositionArg {
+    TopLeft,
+    Top,
+    TopRight,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl From<PositionArg> for OverlayPosition {
+    fn from(position: PositionArg) -> Self {
+        match position {
+            PositionArg::TopLeft => OverlayPosition::TopLeft,
+            PositionArg::Top => OverlayPosition::Top,
+            PositionArg::TopRight => OverlayPosition::TopRight,
+            PositionArg::BottomLeft => OverlayPosition::BottomLeft,
+            PositionArg::Bottom => OverlayPosition::Bottom,
+            PositionArg::BottomRight => OverlayPosition::BottomRight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FilterArg {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl From<FilterArg> for ResizeFilter {
+    fn from(filter: FilterArg) -> Self {
+        match filter {
+            FilterArg::Nearest => ResizeFilter::Nearest,
+            FilterArg::Triangle => ResizeFilter::Triangle,
+            FilterArg::CatmullRom => ResizeFilter::CatmullRom,
+            FilterArg::Gaussian => ResizeFilter::Gaussian,
+            FilterArg::Lanczos3 => ResizeFilter::Lanczos3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ChannelsArg {
+    Mono,
+    Left,
+    Right,
+}
+
+/// 解析 `--aux`：mirror、silent 或导频频率
+fn parse_aux(value: &str) -> Result<AuxChannel, String> {
+    match value {
+        "mirror" => Ok(AuxChannel::Mirror),
+        "silent" => Ok(AuxChannel::Silent),
+        _ => value
+            .trim_end_matches("Hz")
+            .trim_end_matches("hz")
+            .parse::<f64>()
+            .map(|frequency| AuxChannel::PilotTone { frequency, amplitude: 0.5 })
+            .map_err(|_| format!("应为 mirror、silent 或导频频率（Hz），实际为 '{}'", value)),
+    }
+}
+
+/// 解析颜色：#RRGGBB 或 R,G,B
+fn parse_color(value: &str) -> Result<[u8; 3], String> {
+    let error = || format!("应为 #RRGGBB 或 R,G,B，实际为 '{}'", value);
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(error());
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| error());
+        return Ok([channel(0)?, channel(2)?, channel(4)?]);
+    }
+    let channels: Vec<u8> = value
+        .split(',')
+        .map(|part| part.trim().parse::<u8>().map_err(|_| error()))
+        .collect::<Result<_, _>>()?;
+    channels.try_into().map_err(|_| error())
+}
+
+/// 输出目标
+enum Output {
+    Stdout,
+    File(PathBuf),
+}
+
+impl Cli {
+    fn sstv_mode(&self) -> SstvMode {
+        self.mode
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate.unwrap_or_else(|| self.mode.get_default_sample_rate())
+    }
+
+    fn output(&self) -> Output {
+        match &self.output {
+            Some(path) if path.as_os_str() == "-" => Output::Stdout,
+            Some(path) => Output::File(path.clone()),
+            None => {
+                let stem = self
+                    .input
+                    .as_deref()
+                    .and_then(Path::file_stem)
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "sstv".to_string());
+                let name = format!("{}_{}.{}", stem, self.sstv_mode().get_mode_name(), self.audio_format().extension());
+                let dir = self.input.as_deref().and_then(Path::parent).unwrap_or(Path::new(""));
+                Output::File(dir.join(name))
+            }
+        }
+    }
+
+    fn audio_format(&self) -> AudioFormat {
+        if let Some(format) = self.format {
+            return format.into();
+        }
+        match &self.output {
+            Some(path) if path.as_os_str() != "-" => AudioFormat::from_path(path).unwrap_or_default(),
+            _ => AudioFormat::Wav,
+        }
+    }
+
+    fn dither(&self) -> Dither {
+        match self.dither {
+            DitherArg::None => Dither::None,
+            DitherArg::Tpdf => Dither::Tpdf,
+        }
+    }
+
+    fn channel_routing(&self) -> ChannelRouting {
+        match self.channels {
+            ChannelsArg::Mono => ChannelRouting::mono(),
+            ChannelsArg::Left => ChannelRouting::stereo_left(self.aux),
+            ChannelsArg::Right => ChannelRouting::stereo_right(self.aux),
+        }
+    }
+
+    fn fit_mode(&self) -> FitMode {
+        match self.fit {
+            FitArg::Letterbox => FitMode::letterbox(),
+            FitArg::Blur => FitMode::Letterbox(BarFill::Blur { sigma: 12.0 }),
+            FitArg::Crop => FitMode::CropToFill(self.anchor.into()),
+            FitArg::Stretch => FitMode::Stretch,
+        }
+    }
+
+    /// 台标（`--station-id`）和 `--overlay-text` 的文字叠加
+    fn text_overlays(&self) -> Vec<TextOverlay> {
+        let station_id = self.callsign.as_ref().filter(|_| self.station_id).map(|callsign| (callsign, self.station_id_position));
+        let text = self.overlay_text.as_ref().map(|text| (text, self.overlay_position));
+        let background = (!self.no_overlay_background).then_some([0, 0, 0]);
+        station_id
+            .into_iter()
+            .chain(text)
+            .map(|(text, position)| {
+                TextOverlay::new(text.as_str())
+                    .with_position(position.into())
+                    .with_color(self.overlay_color)
+                    .with_background(background)
+                    .with_scale(self.overlay_scale)
+            })
+            .collect()
+    }
+
+    fn frequency_mapping(&self) -> FrequencyMapping {
+        let mapping = match self.mirror {
+            Some(centre) => FrequencyMapping::mirror(centre),
+            None => FrequencyMapping::identity(),
+        };
+        mapping.with_offset(self.offset.unwrap_or(0.0))
+    }
+
+    /// 按命令行参数配置调制器
+    fn modulator(&self) -> Result<SstvModulator, SstvError> {
+        let mut modulator = SstvModulator::new(self.sstv_mode())
+            .with_sample_rate(self.sample_rate())?
+            .with_audio_format(self.audio_format())
+            .with_output_format(self.bits.into())
+            .with_dither(self.dither())
+            .with_channel_routing(self.channel_routing())
+            .with_fit_mode(self.fit_mode())
+            .with_resize_filter(self.resize_filter.into())
+            .with_frequency_mapping(self.frequency_mapping())
+            .with_cue_markers(self.cue_markers)
+            .with_wav_metadata(self.wav_metadata || self.callsign.is_some())
+            .with_tone_shaping(
+                ToneShaping::new()
+                    .with_ramps(self.ramp_ms)
+                    .with_frequency_smoothing(self.smoothing_ms),
+            );
+        if self.sstv_colors {
+            modulator = modulator.with_color_adjustment(ColorAdjustment::sstv_tuned());
+        }
+        if self.sine_table {
+            modulator = modulator.with_sine_mode(SineMode::LookupTable);
+        }
+        if let Some(callsign) = &self.callsign {
+            modulator = modulator.with_callsign(callsign.clone());
+        }
+        for overlay in self.text_overlays() {
+            modulator = modulator.with_text_overlay(overlay);
+        }
+        Ok(modulator)
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("错误: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), SstvError> {
+    if cli.list_modes {
+        list_modes();
+        return Ok(());
+    }
+    cli.sstv_mode().validate_sample_rate(cli.sample_rate())?;
+
+    cli.audio_format().ensure_available()?;
+
+    let mut modulator = cli.modulator()?;
+    let output = cli.output();
+    if cli.dry_run {
+        return dry_run(cli, &modulator, &output);
+    }
+
+    let input = cli.input.as_deref().expect("clap保证未指定 --dry-run/--list-modes 时必须提供输入");
+    let start = Instant::now();
+    let image = load_image(input)
+        .map_err(|e| SstvError::ImageProcessing(format!("{}: {}", input.display(), e)))?;
+    let format = cli.audio_format();
+
+    // 编码失败时删除本次新建的不完整文件
+    let created = match &output {
+        Output::File(path) if !path.exists() => Some(path.clone()),
+        _ => None,
+    };
+    let samples = encode(cli, &mut modulator, &image, &output, format).inspect_err(|_| {
+        if let Some(path) = &created {
+            let _ = std::fs::remove_file(path);
+        }
+    })?;
+
+    if let Some(path) = &cli.save_image {
+        modulator.save_processed_image(path)?;
+    }
+    if let (Some(path), Some(timeline)) = (&cli.timeline, modulator.get_timeline()) {
+        timeline.save_json(path)?;
+    }
+
+    if !cli.quiet {
+        let seconds = samples as f64 / cli.sample_rate() as f64;
+        let target = match &output {
+            Output::Stdout => "标准输出".to_string(),
+            Output::File(path) => path.display().to_string(),
+        };
+        eprintln!(
+            "{} {}Hz {} -> {}（{}，耗时{:.2}秒）",
+            cli.sstv_mode().get_mode_name(),
+            cli.sample_rate(),
+            format.name(),
+            target,
+            format_duration(seconds),
+            start.elapsed().as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+/// 调制并写出音频，返回单声道样本数
+fn encode(
+    cli: &Cli,
+    modulator: &mut SstvModulator,
+    image: &DynamicImage,
+    output: &Output,
+    format: AudioFormat,
+) -> Result<usize, SstvError> {
+    if cli.tx_filter {
+        // 滤波需要完整的样本序列
+        let samples = modulator.modulate_image(image)?.len();
+        modulator.apply_transmit_filter()?;
+        match output {
+            Output::File(path) => modulator.export_audio(path, format)?,
+            Output::Stdout => {
+                write_stdout(cli, format, |sink| sink.write_samples(modulator.get_samples()).map(|_| samples))?;
+            }
+        }
+        return Ok(samples);
+    }
+
+    match output {
+        Output::File(path) => modulator.encode_to_file(image, path),
+        Output::Stdout => write_stdout(cli, format, |sink| modulator.modulate_image_to(image, sink)),
+    }
+}
+
+/// 按格式把样本写到标准输出，返回单声道样本数
+fn write_stdout<F>(cli: &Cli, format: AudioFormat, write: F) -> Result<usize, SstvError>
+where
+    F: FnOnce(&mut dyn SampleSink) -> Result<usize, SstvError>,
+{
+    let rate = cli.sample_rate();
+    let pcm: PcmFormat = cli.bits.into();
+    let dither = cli.dither();
+    let routing = cli.channel_routing();
+    let stdout = std::io::stdout().lock();
+    let count = match format {
+        AudioFormat::Wav => {
+            let mut sink = WavStreamSink::with_routing(stdout, rate, pcm, dither, routing);
+            let count = write(&mut sink)?;
+            sink.finish()?;
+            count
+        }
+        AudioFormat::Raw => {
+            let mut sink = RawPcmSink::with_routing(stdout, rate, pcm, dither, routing);
+            let count = write(&mut sink)?;
+            sink.finish()?;
+            count
+        }
+        #[cfg(feature = "flac")]
+        AudioFormat::Flac => {
+            use std::io::Write;
+
+            // FLAC结束时需要回写STREAMINFO，先在内存中编码
+            let mut sink = sstv_rust::FlacWriter::new(std::io::Cursor::new(Vec::new()), rate, pcm, dither, routing)?;
+            let count = write(&mut sink)?;
+            sink.finish()?;
+            let mut stdout = stdout;
+            stdout.write_all(&sink.into_inner()?.into_inner())?;
+            stdout.flush()?;
+            count
+        }
+        #[cfg(not(feature = "flac"))]
+        AudioFormat::Flac => return format.ensure_available().map(|_| 0),
+    };
+    Ok(count)
+}
+
+fn list_modes() {
+    println!("{:<12} {:>10} {:>10}  VIS", "模式", "分辨率", "图像时长");
+    for (mode, name, (width, height), duration) in get_supported_modes() {
+        println!(
+            "{:<12} {:>10} {:>9.1}s  {}",
+            name,
+            format!("{}x{}", width, height),
+            duration,
+            mode.get_vis_code()
+        );
+    }
+}
+
+/// 估算时长和文件大小
+fn dry_run(cli: &Cli, modulator: &SstvModulator, output: &Output) -> Result<(), SstvError> {
+    let mapping = cli.frequency_mapping();
+    mapping.validate(cli.sample_rate())?;
+    modulator.get_tone_shaping().validate()?;
+
+    let format = cli.audio_format();
+    let pcm: PcmFormat = cli.bits.into();
+    if format == AudioFormat::Flac && !matches!(pcm, PcmFormat::Int16 | PcmFormat::Int24) {
+        return Err(SstvError::InvalidFormat(format!("FLAC不支持{:?}样本格式，请使用16位或24位整数", pcm)));
+    }
+
+    let samples = modulator.transmission_samples()?;
+    let channels = cli.channel_routing().channels() as u64;
+    let data_bytes = samples as u64 * channels * (pcm.bits_per_sample() as u64 / 8);
+    let (width, height) = cli.sstv_mode().get_dimensions();
+
+    println!("模式:     {} ({}x{}, VIS {})", cli.sstv_mode().get_mode_name(), width, height, cli.sstv_mode().get_vis_code());
+    println!("采样率:   {}Hz, {}声道, {:?}", cli.sample_rate(), channels, pcm);
+    println!("样本数:   {}", samples);
+    println!("时长:     {}", format_duration(samples as f64 / cli.sample_rate() as f64));
+    let size = match format {
+        AudioFormat::Wav => format!("约 {}（WAV）", format_bytes(data_bytes + 44)),
+        AudioFormat::Raw => format!("{}（原始PCM）", format_bytes(data_bytes)),
+        AudioFormat::Flac => format!("不超过 {}（FLAC，实际大小取决于图像内容）", format_bytes(data_bytes)),
+    };
+    println!("文件大小: {}", size);
+    match output {
+        Output::Stdout => println!("输出:     标准输出"),
+        Output::File(path) => println!("输出:     {}", path.display()),
+    }
+    Ok(())
+}
+
+fn format_duration(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).floor();
+    format!("{}分{:06.3}秒", minutes as u64, seconds - minutes * 60.0)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1 << 20 {
+        format!("{:.2} MiB", bytes as f64 / (1 << 20) as f64)
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let cli = Cli::try_parse_from([
+            "sstv-encode", "in.jpg", "-m", "pd-120", "-r", "44100", "-b", "24", "-o", "out.flac",
+            "--offset", "-25", "--channels", "left", "--aux", "1000", "--cue-markers", "--timeline", "t.json",
+        ])
+        .unwrap();
+        assert!(cli.modulator().unwrap().get_cue_markers());
+        assert_eq!(cli.timeline, Some(PathBuf::from("t.json")));
+        assert_eq!(cli.mode, SstvMode::Pd120);
+        for (name, mode) in [("PD120", SstvMode::Pd120), ("Robot 36", SstvMode::Robot36), ("martin-m1", SstvMode::MartinM1)] {
+            let cli = Cli::try_parse_from(["sstv-encode", "in.jpg", "-m", name]).unwrap();
+            assert_eq!(cli.sstv_mode(), mode, "{}", name);
+        }
+        assert!(Cli::try_parse_from(["sstv-encode", "in.jpg", "-m", "PD90"]).is_err());
+        assert_eq!(cli.sample_rate(), 44100);
+        assert_eq!(cli.audio_format(), AudioFormat::Flac);
+        assert_eq!(cli.offset, Some(-25.0));
+        assert_eq!(cli.channel_routing().channels(), 2);
+        assert!(matches!(cli.aux, AuxChannel::PilotTone { frequency, .. } if frequency == 1000.0));
+        let cli = Cli::try_parse_from(["sstv-encode", "in.jpg", "--callsign", "VK7KSM"]).unwrap();
+        assert_eq!(cli.modulator().unwrap().get_callsign(), Some("VK7KSM"));
+
+        // 默认输出文件放在输入旁边
+        let cli = Cli::try_parse_from(["sstv-encode", "photos/cat.png", "-m", "m1"]).unwrap();
+        match cli.output() {
+            Output::File(path) => assert_eq!(path, Path::new("photos").join("cat_MartinM1.wav")),
+            Output::Stdout => panic!("应输出到文件"),
+        }
+        let cli = Cli::try_parse_from(["sstv-encode", "cat.png", "-o", "-", "-f", "raw"]).unwrap();
+        assert!(matches!(cli.output(), Output::Stdout));
+        assert_eq!(cli.audio_format(), AudioFormat::Raw);
+
+        // 未指定采样率时使用模式的默认采样率
+        let cli = Cli::try_parse_from(["sstv-encode", "in.jpg"]).unwrap();
+        assert_eq!(cli.sample_rate(), SstvMode::Robot36.get_default_sample_rate());
+        assert!(cli.modulator().is_ok());
+    }
+
+    #[test]
+    fn test_overlay_options() {
+        let cli = Cli::try_parse_from(["sstv-encode", "in.jpg"]).unwrap();
+        assert!(cli.modulator().unwrap().get_preprocess_options().overlays.is_empty());
+
+        let cli = Cli::try_parse_from([
+            "sstv-encode", "in.jpg", "--callsign", "VK7KSM", "--station-id", "--overlay-text", "CQ DX",
+            "--overlay-position", "bottom-right", "--overlay-color", "#FFFF00", "--overlay-scale", "3",
+        ])
+        .unwrap();
+        let modulator = cli.modulator().unwrap();
+        let overlays = &modulator.get_preprocess_options().overlays;
+        let style = TextOverlay::default().with_color([255, 255, 0]).with_scale(3);
+        assert_eq!(overlays, &vec![
+            TextOverlay { text: "VK7KSM".to_string(), ..style.clone() },
+            TextOverlay { text: "CQ DX".to_string(), position: OverlayPosition::BottomRight, ..style },
+        ]);
+
+        let cli = Cli::try_parse_from(["sstv-encode", "in.jpg", "--overlay-text", "CQ", "--overlay-color", "0, 128,255", "--no-overlay-background"]).unwrap();
+        let overlay = &cli.text_overlays()[0];
+        assert_eq!((overlay.color, overlay.background), ([0, 128, 255], None));
+
+        // 台标需要呼号；颜色和放大倍数须有效
+        assert!(Cli::try_parse_from(["sstv-encode", "in.jpg", "--station-id"]).is_err());
+        for color in ["#FFF", "red", "1,2", "1,2,300", "#GG0000"] {
+            assert!(Cli::try_parse_from(["sstv-encode", "in.jpg", "--overlay-color", color]).is_err(), "{}", color);
+        }
+        assert!(Cli::try_parse_from(["sstv-encode", "in.jpg", "--overlay-scale", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_input_requirement() {
+        assert!(Cli::try_parse_from(["sstv-encode"]).is_err());
+        assert!(Cli::try_parse_from(["sstv-encode", "--list-modes"]).is_ok());
+        assert!(Cli::try_parse_from(["sstv-encode", "--dry-run", "-m", "scottie-dx"]).is_ok());
+        assert!(Cli::try_parse_from(["sstv-encode", "in.jpg", "--aux", "loud"]).is_err());
+    }
+
+    #[test]
+    fn test_sample_rate_uses_mode_range() {
+        let cli = Cli::try_parse_from(["sstv-encode", "--dry-run", "-r", "4000"]).unwrap();
+        match run(&cli) {
+            Err(SstvError::InvalidSampleRate { sample_rate, min_rate, max_rate }) => {
+                let mode = cli.sstv_mode();
+                assert_eq!((sample_rate, min_rate, max_rate), (4000, mode.get_min_sample_rate(), sstv_rust::MAX_SAMPLE_RATE));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! 编码器配置模块
+//!
+//! [`EncoderConfig`] 汇总调制器的全部可调参数：模式、采样率、幅度、首尾静音、VIS码和结束音、
+//! 图像预处理、频率映射和音调整形，以及导出格式和声道路由。配置可序列化为JSON保存为电台预设，
+//! 用 [`EncoderConfig::build`] 校验后创建调制器。
+
+use crate::audio::{AudioFormat, ChannelRouting, Dither, PcmFormat};
+use crate::error::{Result, SstvError};
+use crate::nco::SineMode;
+use crate::preprocess::PreprocessOptions;
+use crate::sstv::{Framing, FrequencyMapping, SstvMode, SstvModulator, ToneShaping};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 编码器配置
+///
+/// 缺少的字段取默认值（与 `SstvModulator::new` 相同），因此预设文件只需写出需要修改的参数。
+///
+/// ```rust
+/// use sstv_rust::{EncoderConfig, SstvMode, PcmFormat};
+///
+/// let config = EncoderConfig::new(SstvMode::Pd120)
+///     .with_sample_rate(48000)
+///     .with_amplitude(0.8)
+///     .with_silence(500.0, 300.0)
+///     .with_output_format(PcmFormat::Int24)
+///     .with_callsign("VK7KSM");
+/// let json = serde_json::to_string_pretty(&config).unwrap();
+/// let modulator = serde_json::from_str::<EncoderConfig>(&json).unwrap().build().unwrap();
+/// assert_eq!(modulator.get_amplitude(), 0.8);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EncoderConfig {
+    /// SSTV模式
+    pub mode: SstvMode,
+    /// 采样率（Hz）
+    pub sample_rate: u32,
+    /// 输出幅度，(0, 1]
+    pub amplitude: f32,
+    /// 开头静音时长（毫秒）
+    pub leading_silence_ms: f64,
+    /// 结尾静音时长（毫秒）
+    pub trailing_silence_ms: f64,
+    /// 是否发送前导音和VIS码
+    pub vis_header: bool,
+    /// 是否发送结束音
+    pub end_tones: bool,
+    /// 图像预处理选项
+    pub preprocess: PreprocessOptions,
+    /// 音调频率映射
+    pub frequency_mapping: FrequencyMapping,
+    /// 包络渐变和频率平滑
+    pub tone_shaping: ToneShaping,
+    /// 振荡器正弦值计算方式
+    pub sine_mode: SineMode,
+    /// 是否并行生成扫描块
+    pub parallel_rendering: bool,
+    /// 导出文件格式
+    pub audio_format: AudioFormat,
+    /// 导出样本格式
+    pub output_format: PcmFormat,
+    /// 量化抖动
+    pub dither: Dither,
+    /// 导出声道路由（立体声声道选择和VOX/PTT导频）
+    pub channel_routing: ChannelRouting,
+    /// 导出WAV时是否写入标记
+    pub cue_markers: bool,
+    /// 导出WAV时是否内嵌来源信息
+    pub wav_metadata: bool,
+    /// 呼号
+    pub callsign: Option<String>,
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        Self::new(SstvMode::Robot36)
+    }
+}
+
+impl EncoderConfig {
+    /// 以默认参数创建指定模式的配置
+    pub fn new(mode: SstvMode) -> Self {
+        let framing = Framing::default();
+        Self {
+            mode,
+            sample_rate: mode.get_default_sample_rate(),
+            amplitude: framing.amplitude,
+            leading_silence_ms: framing.leading_silence_ms,
+            trailing_silence_ms: framing.trailing_silence_ms,
+            vis_header: framing.vis_header,
+            end_tones: framing.end_tones,
+            preprocess: PreprocessOptions::default(),
+            frequency_mapping: FrequencyMapping::default(),
+            tone_shaping: ToneShaping::default(),
+            sine_mode: SineMode::default(),
+            parallel_rendering: true,
+            audio_format: AudioFormat::default(),
+            output_format: PcmFormat::default(),
+            dither: Dither::default(),
+            channel_routing: ChannelRouting::default(),
+            cue_markers: false,
+            wav_metadata: false,
+            callsign: None,
+        }
+    }
+
+    /// 设置模式
+    pub fn with_mode(mut self, mode: SstvMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 设置采样率
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// 设置输出幅度
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// 设置开头和结尾的静音时长（毫秒）
+    pub fn with_silence(mut self, leading_ms: f64, trailing_ms: f64) -> Self {
+        self.leading_silence_ms = leading_ms;
+        self.trailing_silence_ms = trailing_ms;
+        self
+    }
+
+    /// 设置是否发送前导音和VIS码
+    pub fn with_vis_header(mut self, enabled: bool) -> Self {
+        self.vis_header = enabled;
+        self
+    }
+
+    /// 设置是否发送结束音
+    pub fn with_end_tones(mut self, enabled: bool) -> Self {
+        self.end_tones = enabled;
+        self
+    }
+
+    /// 设置图像预处理选项
+    pub fn with_preprocess_options(mut self, options: PreprocessOptions) -> Self {
+        self.preprocess = options;
+        self
+    }
+
+    /// 设置音调频率映射
+    pub fn with_frequency_mapping(mut self, mapping: FrequencyMapping) -> Self {
+        self.frequency_mapping = mapping;
+        self
+    }
+
+    /// 设置包络渐变和频率平滑
+    pub fn with_tone_shaping(mut self, shaping: ToneShaping) -> Self {
+        self.tone_shaping = shaping;
+        self
+    }
+
+    /// 设置振荡器正弦值计算方式
+    pub fn with_sine_mode(mut self, mode: SineMode) -> Self {
+        self.sine_mode = mode;
+        self
+    }
+
+    /// 设置是否并行生成扫描块
+    pub fn with_parallel_rendering(mut self, enabled: bool) -> Self {
+        self.parallel_rendering = enabled;
+        self
+    }
+
+    /// 设置导出文件格式
+    pub fn with_audio_format(mut self, format: AudioFormat) -> Self {
+        self.audio_format = format;
+        self
+    }
+
+    /// 设置导出样本格式
+    pub fn with_output_format(mut self, format: PcmFormat) -> Self {
+        self.output_format = format;
+        self
+    }
+
+    /// 设置量化抖动
+    pub fn with_dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// 设置导出声道路由
+    pub fn with_channel_routing(mut self, routing: ChannelRouting) -> Self {
+        self.channel_routing = routing;
+        self
+    }
+
+    /// 设置导出WAV时是否写入标记
+    pub fn with_cue_markers(mut self, enabled: bool) -> Self {
+        self.cue_markers = enabled;
+        self
+    }
+
+    /// 设置导出WAV时是否内嵌来源信息
+    pub fn with_wav_metadata(mut self, enabled: bool) -> Self {
+        self.wav_metadata = enabled;
+        self
+    }
+
+    /// 设置呼号
+    pub fn with_callsign<S: Into<String>>(mut self, callsign: S) -> Self {
+        self.callsign = Some(callsign.into());
+        self
+    }
+
+    /// 首尾组成
+    pub(crate) fn framing(&self) -> Framing {
+        Framing {
+            amplitude: self.amplitude,
+            leading_silence_ms: self.leading_silence_ms,
+            trailing_silence_ms: self.trailing_silence_ms,
+            vis_header: self.vis_header,
+            end_tones: self.end_tones,
+        }
+    }
+
+    /// 检查全部参数
+    ///
+    /// 采样率超出本模式的范围时返回 `InvalidSampleRate`；幅度、静音时长或样本格式无效时返回
+    /// `InvalidAudioParameter`；频率映射或整形参数无效时返回与调制时相同的错误。
+    pub fn validate(&self) -> Result<()> {
+        self.mode.validate_sample_rate(self.sample_rate)?;
+        self.framing().validate()?;
+        if self.audio_format == AudioFormat::Flac && !matches!(self.output_format, PcmFormat::Int16 | PcmFormat::Int24) {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "output_format".to_string(),
+                value: format!("{:?}（FLAC仅支持16/24位整数）", self.output_format),
+            });
+        }
+        self.audio_format.ensure_available()?;
+        self.frequency_mapping.validate(self.sample_rate)?;
+        self.tone_shaping.validate()
+    }
+
+    /// 校验后创建调制器
+    pub fn build(&self) -> Result<SstvModulator> {
+        SstvModulator::from_config(self)
+    }
+
+    /// 从JSON预设文件读取并校验
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let config: Self = serde_json::from_str(&text).map_err(|e| SstvError::InvalidFormat(format!("编码器配置: {}", e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 保存为JSON预设文件
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| SstvError::InvalidFormat(format!("编码器配置: {}", e)))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AuxChannel;
+    use crate::preprocess::{CropAnchor, FitMode};
+
+    #[test]
+    fn test_validation_errors() {
+        assert!(EncoderConfig::new(SstvMode::Pd120).validate().is_ok());
+        assert!(matches!(
+            EncoderConfig::new(SstvMode::Pd120).with_sample_rate(0).validate(),
+            Err(SstvError::InvalidSampleRate { sample_rate: 0, .. })
+        ));
+        assert!(matches!(
+            EncoderConfig::new(SstvMode::Pd120).with_sample_rate(400_000).validate(),
+            Err(SstvError::InvalidSampleRate { .. })
+        ));
+        for config in [
+            EncoderConfig::new(SstvMode::Pd120).with_amplitude(0.0),
+            EncoderConfig::new(SstvMode::Pd120).with_amplitude(1.5),
+            EncoderConfig::new(SstvMode::Pd120).with_amplitude(f32::NAN),
+            EncoderConfig::new(SstvMode::Pd120).with_silence(-1.0, 200.0),
+            EncoderConfig::new(SstvMode::Pd120).with_silence(200.0, f64::INFINITY),
+            EncoderConfig::new(SstvMode::Pd120).with_audio_format(AudioFormat::Flac).with_output_format(PcmFormat::Float32),
+        ] {
+            assert!(matches!(config.validate(), Err(SstvError::InvalidAudioParameter { .. })), "{:?}", config);
+            assert!(config.build().is_err());
+        }
+    }
+
+    #[test]
+    fn test_partial_preset() {
+        // 预设只写出需要修改的字段
+        let config: EncoderConfig = serde_json::from_str(
+            r#"{
+                "mode": "PD-120",
+                "sample_rate": 11025,
+                "end_tones": false,
+                "preprocess": { "fit_mode": { "strategy": "crop_to_fill", "anchor": "top" } },
+                "output_format": "int24",
+                "callsign": "VK7KSM"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.mode, SstvMode::Pd120);
+        assert_eq!(config.preprocess.fit_mode, FitMode::CropToFill(CropAnchor::Top));
+        assert_eq!(config.output_format, PcmFormat::Int24);
+        assert_eq!(config.amplitude, 1.0);
+        assert!(config.vis_header);
+        assert!(!config.end_tones);
+
+        let round_trip: EncoderConfig = serde_json::from_value(serde_json::to_value(&config).unwrap()).unwrap();
+        assert_eq!(round_trip, config);
+        assert_eq!(config.channel_routing, ChannelRouting::mono());
+    }
+
+    #[test]
+    fn test_channel_routing_preset() {
+        let config: EncoderConfig = serde_json::from_str(
+            r#"{
+                "channel_routing": {
+                    "channels": 2,
+                    "signal_channel": 0,
+                    "aux": { "type": "pilot_tone", "frequency": 1000.0, "amplitude": 0.5 }
+                }
+            }"#,
+        )
+        .unwrap();
+        let routing = ChannelRouting::stereo_left(AuxChannel::PilotTone { frequency: 1000.0, amplitude: 0.5 });
+        assert_eq!(config.channel_routing, routing);
+        let round_trip: EncoderConfig = serde_json::from_value(serde_json::to_value(&config).unwrap()).unwrap();
+        assert_eq!(round_trip, config);
+
+        // 反序列化时同样校验声道数和导频参数
+        for invalid in [
+            r#"{ "channels": 2, "signal_channel": 2, "aux": { "type": "silent" } }"#,
+            r#"{ "channels": 2, "signal_channel": 0, "aux": { "type": "pilot_tone", "frequency": 0.0, "amplitude": 0.5 } }"#,
+        ] {
+            assert!(serde_json::from_str::<ChannelRouting>(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
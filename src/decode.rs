@@ -0,0 +1,1148 @@
+//! SSTV解调模块
+//!
+//! 把SSTV音频还原为图像。输入先与1700Hz本振混频、低通滤波得到复基带，抽取到约11kHz后
+//! 由相邻样本的相位差得到瞬时频率。解码器在频率轨迹上搜索VIS码确定模式、图像起点和
+//! 频率偏移，逐个扫描块定位同步脉冲，用直线拟合估计收发双方的时钟误差（图像倾斜），
+//! 再按校正后的时序取像素。样本可以分块送入，长录音中的多幅图像依次输出，
+//! 缓冲区只保留当前图像所需的数据。
+
+use crate::error::{Result, SstvError};
+use crate::sstv::SstvMode;
+use image::{Rgb, RgbImage};
+use std::f64::consts::PI;
+
+/// 本振频率（Hz），位于SSTV音调1100-2300Hz的中间
+const CENTER_FREQUENCY: f64 = 1700.0;
+/// 基带低通滤波器的截止频率（Hz），通带覆盖1100-2300Hz加上允许的频率偏移
+const LOWPASS_CUTOFF: f64 = 1100.0;
+/// 基带低通滤波器的过渡带宽度（Hz）
+const LOWPASS_TRANSITION: f64 = 600.0;
+/// 抽取后工作采样率的下限（Hz）
+const MIN_WORKING_RATE: u32 = 11025;
+/// 默认允许的最大频率偏移（Hz）
+const DEFAULT_MAX_FREQUENCY_OFFSET: f64 = 250.0;
+/// VIS码各段的频率容差（Hz）
+const VIS_TOLERANCE: f64 = 60.0;
+/// VIS码每位时长（ms）
+pub(crate) const VIS_BIT_MS: f64 = 30.0;
+/// VIS码起点（第一段引导音）到起始位的时长（ms）：两段300ms引导音和10ms间隔
+pub(crate) const VIS_HEADER_MS: f64 = 610.0;
+/// 检查候选起始位时需要向前保留的数据（ms），最远用到起始位前590ms的第一段引导音
+pub(crate) const VIS_LOOKBACK_MS: f64 = 600.0;
+/// 同步脉冲判决门限（Hz），取1200Hz和1500Hz的中点
+pub(crate) const SYNC_THRESHOLD: f64 = 1350.0;
+/// 同步脉冲的搜索范围（ms，预测位置两侧）
+pub(crate) const SYNC_SEARCH_MS: f64 = 3.0;
+/// 窗口内低于门限的样本比例达到该值才视为同步脉冲
+const SYNC_MIN_FILL: f64 = 0.75;
+/// 可容忍的最大时钟误差（相对值）
+const MAX_CLOCK_ERROR: f64 = 0.005;
+/// 信噪比的参考带宽（Hz），按常见的3kHz话音信道计算
+const SNR_BANDWIDTH: f64 = 3000.0;
+/// 信噪比估计值上限（dB）
+const MAX_SNR_DB: f64 = 60.0;
+/// 亮度/色度值与频率的换算系数，与调制器一致
+const COLOR_FREQ_MULT: f64 = 3.1372549;
+
+/// 解码得到的图像及接收参数
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+    /// 解码图像（模式的原始分辨率）
+    pub image: RgbImage,
+    /// SSTV模式
+    pub mode: SstvMode,
+    /// VIS码数值
+    pub vis_code: u8,
+    /// VIS码起点在输入中的时间（秒）
+    pub start_time: f64,
+    /// 图像结束时间（秒）
+    pub end_time: f64,
+    /// VIS码起点的样本序号（输入采样率）
+    pub start_sample: usize,
+    /// 图像结束的样本序号（不含）
+    pub end_sample: usize,
+    /// 置信度（0-1）：VIS码与标称频率的吻合程度占1/4，检测到同步脉冲的扫描块比例占3/4
+    pub confidence: f64,
+    /// 信噪比估计（dB，按3kHz带宽计算），由VIS引导音和同步脉冲测得
+    pub snr_db: f64,
+    /// 频率偏移（Hz），正值表示音调整体偏高
+    pub frequency_offset: f64,
+    /// 录音相对发送端的时钟误差（ppm），正值表示录音中的扫描块偏长
+    pub clock_error_ppm: f64,
+    /// 不做校正时图像的倾斜角（度），正值表示越往下越向右偏
+    pub slant_degrees: f64,
+    /// 是否已按估计的时钟误差校正倾斜
+    pub slant_corrected: bool,
+    /// 检测到同步脉冲的扫描块数
+    pub sync_count: u32,
+    /// 完整接收的扫描块数
+    pub lines_decoded: u32,
+    /// 录音是否包含完整图像
+    pub complete: bool,
+}
+
+impl DecodedImage {
+    /// 接收参数的JSON表示（不含图像）
+    pub fn to_json(&self) -> serde_json::Value {
+        let (width, height) = self.mode.get_dimensions();
+        serde_json::json!({
+            "mode": self.mode.get_mode_name(),
+            "vis_code": self.vis_code,
+            "width": width,
+            "height": height,
+            "start_time": self.start_time,
+            "end_time": self.end_time,
+            "start_sample": self.start_sample,
+            "end_sample": self.end_sample,
+            "confidence": self.confidence,
+            "snr_db": self.snr_db,
+            "frequency_offset_hz": self.frequency_offset,
+            "clock_error_ppm": self.clock_error_ppm,
+            "slant_degrees": self.slant_degrees,
+            "slant_corrected": self.slant_corrected,
+            "sync_count": self.sync_count,
+            "lines_decoded": self.lines_decoded,
+            "line_count": self.mode.get_scan_line_count(),
+            "complete": self.complete
+        })
+    }
+}
+
+/// SSTV解码器
+///
+/// 用 [`push`](Self::push) 分块送入单声道样本，每当一幅图像接收完毕就返回它；
+/// 输入结束后调用 [`finish`](Self::finish) 取出最后一幅（可能不完整的）图像。
+pub struct SstvDecoder {
+    sample_rate: u32,
+    working_rate: f64,
+    slant_correction: bool,
+    max_frequency_offset: f64,
+    // 是否生成图像；只查找传输段时关闭
+    render: bool,
+    demodulator: Demodulator,
+    track: Track,
+    state: State,
+    scratch: Vec<[f32; 2]>,
+}
+
+impl SstvDecoder {
+    /// 创建解码器，采样率须在 `MIN_SAMPLE_RATE` 至 `MAX_SAMPLE_RATE` 之间，与调制器一致
+    pub fn new(sample_rate: u32) -> Result<Self> {
+        if !(crate::MIN_SAMPLE_RATE..=crate::MAX_SAMPLE_RATE).contains(&sample_rate) {
+            return Err(SstvError::invalid_sample_rate(sample_rate, crate::MIN_SAMPLE_RATE, crate::MAX_SAMPLE_RATE));
+        }
+        let demodulator = Demodulator::new(sample_rate);
+        let working_rate = sample_rate as f64 / demodulator.decimation as f64;
+        Ok(Self {
+            sample_rate,
+            working_rate,
+            slant_correction: true,
+            max_frequency_offset: DEFAULT_MAX_FREQUENCY_OFFSET,
+            render: true,
+            demodulator,
+            track: Track::new(working_rate),
+            state: State::Searching { next: 0.0 },
+            scratch: Vec::new(),
+        })
+    }
+
+    /// 设置是否按测得的时钟误差校正图像倾斜（默认开启）
+    pub fn with_slant_correction(mut self, enabled: bool) -> Self {
+        self.slant_correction = enabled;
+        self
+    }
+
+    /// 设置VIS检测允许的最大频率偏移（Hz，默认250）
+    pub fn with_max_frequency_offset(mut self, offset: f64) -> Self {
+        self.max_frequency_offset = offset.abs();
+        self
+    }
+
+    /// 获取采样率
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// 获取是否校正图像倾斜
+    pub fn get_slant_correction(&self) -> bool {
+        self.slant_correction
+    }
+
+    /// 获取允许的最大频率偏移（Hz）
+    pub fn get_max_frequency_offset(&self) -> f64 {
+        self.max_frequency_offset
+    }
+
+    /// 送入一段样本，返回其间接收完毕的图像
+    pub fn push(&mut self, samples: &[f32]) -> Vec<DecodedImage> {
+        self.scratch.clear();
+        self.demodulator.process(samples, &mut self.scratch);
+        self.track.extend(&self.scratch);
+        self.run(false)
+    }
+
+    /// 结束输入，返回剩余的图像（录音在图像中途结束时返回已接收的部分）
+    pub fn finish(mut self) -> Vec<DecodedImage> {
+        self.scratch.clear();
+        self.demodulator.flush(&mut self.scratch);
+        self.track.extend(&self.scratch);
+        self.run(true)
+    }
+
+    fn run(&mut self, finished: bool) -> Vec<DecodedImage> {
+        let ms = self.working_rate / 1000.0;
+        let mut images = Vec::new();
+        loop {
+            match std::mem::replace(&mut self.state, State::Searching { next: 0.0 }) {
+                State::Searching { next } => {
+                    let mut candidate = next.max(self.track.base as f64 + VIS_LOOKBACK_MS * ms);
+                    let last = self.track.end() as f64 - 10.0 * VIS_BIT_MS * ms;
+                    let mut reception = None;
+                    while candidate <= last {
+                        reception = self.check_vis(candidate);
+                        if reception.is_some() {
+                            break;
+                        }
+                        candidate += ms;
+                    }
+                    match reception {
+                        Some(reception) => self.state = State::Receiving(reception),
+                        None => {
+                            self.state = State::Searching { next: candidate };
+                            self.track.discard_before((candidate - VIS_LOOKBACK_MS * ms).max(0.0) as usize);
+                            break;
+                        }
+                    }
+                }
+                State::Receiving(reception) => {
+                    let timing = ModeTiming::of(reception.mode);
+                    let length = timing.transmission_ms(reception.mode) * (1.0 + MAX_CLOCK_ERROR) + 20.0;
+                    let required = reception.image_start(ms) + length * ms;
+                    if !finished && (self.track.end() as f64) < required {
+                        self.state = State::Receiving(reception);
+                        break;
+                    }
+                    let (image, end) = self.decode_image(&reception);
+                    // 只查找传输段时也保留没有图像数据的VIS码，由置信度区分
+                    if image.lines_decoded > 0 || !self.render {
+                        images.push(image);
+                    }
+                    self.state = State::Searching { next: end };
+                    if finished && end >= self.track.end() as f64 {
+                        break;
+                    }
+                }
+            }
+        }
+        images
+    }
+
+    /// 检查以 `start_bit`（起始位起点）为基准的VIS码
+    fn check_vis(&self, start_bit: f64) -> Option<Reception> {
+        let ms = self.working_rate / 1000.0;
+        let mean = |from: f64, to: f64| self.track.mean(start_bit + from * ms, start_bit + to * ms);
+
+        // 第二段引导音确定频率偏移，前后两半都要稳定
+        let leader = mean(-290.0, -10.0)?;
+        let offset = leader - 1900.0;
+        if offset.abs() > self.max_frequency_offset {
+            return None;
+        }
+        // 各段与标称频率的偏差按容差归一化，全部在容差内才接受，平均偏差用于置信度
+        let mut deviations = Vec::with_capacity(13);
+        let mut near = |value: f64, target: f64, tolerance: f64| {
+            let deviation = (value - offset - target).abs() / tolerance;
+            deviations.push(deviation.min(1.0));
+            deviation <= 1.0
+        };
+        if !near(mean(-290.0, -150.0)?, 1900.0, VIS_TOLERANCE / 2.0)
+            || !near(mean(-150.0, -10.0)?, 1900.0, VIS_TOLERANCE / 2.0)
+            || !near(mean(5.0, 25.0)?, 1200.0, VIS_TOLERANCE)
+            || !near(mean(-590.0, -320.0)?, 1900.0, VIS_TOLERANCE)
+            || mean(-308.0, -302.0)? - offset > 1650.0
+            || !near(mean(275.0, 295.0)?, 1200.0, VIS_TOLERANCE)
+        {
+            return None;
+        }
+
+        // 7位数据（低位在前）加偶校验位
+        let mut code = 0u8;
+        let mut ones = 0;
+        for bit in 0..8 {
+            let from = VIS_BIT_MS * (bit + 1) as f64 + 5.0;
+            let frequency = mean(from, from + 20.0)? - offset;
+            let (target, value) = if (frequency - 1100.0).abs() < (frequency - 1300.0).abs() { (1100.0, 1) } else { (1300.0, 0) };
+            if !near(frequency + offset, target, VIS_TOLERANCE) {
+                return None;
+            }
+            ones += value;
+            if bit < 7 {
+                code |= value << bit;
+            }
+        }
+        if ones % 2 != 0 {
+            return None;
+        }
+        let mode = SstvMode::from_vis_code(code)?;
+        let vis_quality = 1.0 - deviations.iter().sum::<f64>() / deviations.len() as f64;
+
+        let start_bit = self.refine_edge(start_bit, offset);
+        let mut reception = Reception {
+            mode,
+            vis_code: code,
+            start_bit,
+            frequency_offset: offset,
+            vis_quality,
+            signal_energy: 0.0,
+            noise_energy: 0.0,
+        };
+        let leader = (start_bit - 290.0 * ms) as usize..(start_bit - 10.0 * ms) as usize;
+        reception.add_tone(self.track.tone_power(leader, 1900.0 + offset));
+        Some(reception)
+    }
+
+    /// 在粗略位置附近寻找引导音到起始位（1900Hz→1200Hz）的跳变沿
+    fn refine_edge(&self, coarse: f64, offset: f64) -> f64 {
+        let ms = self.working_rate / 1000.0;
+        let span = 5.0 * ms;
+        let threshold = (1900.0 + 1200.0) / 2.0 + offset;
+        let from = (coarse - 8.0 * ms).floor() as usize;
+        let to = (coarse + 8.0 * ms).ceil() as usize;
+        let mut best = (coarse, f64::MIN);
+        for position in from..=to {
+            let x = position as f64;
+            if let (Some(before), Some(after)) = (self.track.mean(x - span, x), self.track.mean(x, x + span))
+                && before > threshold
+                && after < threshold
+                && before - after > best.1
+            {
+                best = (x, before - after);
+            }
+        }
+        best.0
+    }
+
+    /// 解码一幅图像，返回图像和结束位置（工作采样率下标）
+    fn decode_image(&self, reception: &Reception) -> (DecodedImage, f64) {
+        let mode = reception.mode;
+        let timing = ModeTiming::of(mode);
+        let ms = self.working_rate / 1000.0;
+        let offset = reception.frequency_offset;
+        let lines = mode.get_scan_line_count();
+        let period = timing.period * ms;
+        let image_start = reception.image_start(ms);
+        let nominal_sync = image_start + (timing.lead_in + timing.sync_start) * ms;
+
+        // 定位同步脉冲，拟合 位置 = 截距 + 斜率·扫描块序号
+        let syncs = self.find_syncs(&timing, nominal_sync, lines, offset);
+        let min_syncs = (lines as usize / 4).max(8);
+        let fit = fit_line(&syncs, ms)
+            .filter(|&(_, slope)| syncs.len() >= min_syncs && (slope / period - 1.0).abs() <= MAX_CLOCK_ERROR);
+        let clock_error = fit.map_or(0.0, |(_, slope)| slope / period - 1.0);
+        let (first_sync, line_period) = match fit {
+            Some(fit) if self.slant_correction => fit,
+            _ => (nominal_sync, period),
+        };
+        let scale = line_period / period;
+
+        // 信噪比：VIS引导音加各同步脉冲内部
+        let mut reception = reception.clone();
+        let trim = 1.0 * ms;
+        for &(_, position) in &syncs {
+            let length = timing.sync_length * ms;
+            if length > 3.0 * trim {
+                let range = (position + trim) as usize..(position + length - trim) as usize;
+                reception.add_tone(self.track.tone_power(range, 1200.0 + offset));
+            }
+        }
+
+        // 逐块取像素；数据末尾允许差2ms（拟合误差和滤波器拖尾），缺失部分按黑色处理
+        let line_start = |line: u32| first_sync + line as f64 * line_period - timing.sync_start * ms * scale;
+        let end = self.track.end() as f64;
+        let mut values: Vec<Vec<Vec<f32>>> = Vec::with_capacity(lines as usize);
+        let (width, height) = mode.get_dimensions();
+        let mut lines_decoded = 0;
+        for line in 0..lines {
+            let start = line_start(line);
+            if start < self.track.base as f64 || start + period * scale > end + 2.0 * ms {
+                break;
+            }
+            lines_decoded += 1;
+            if !self.render {
+                continue;
+            }
+            let scans = timing
+                .scans
+                .iter()
+                .map(|&(scan_start, pixel)| {
+                    (0..width)
+                        .map(|col| {
+                            let from = start + (scan_start + col as f64 * pixel) * ms * scale;
+                            let to = from + pixel * ms * scale;
+                            let frequency = self.track.mean(from, to).unwrap_or(1500.0 + offset);
+                            ((frequency - offset - 1500.0) / COLOR_FREQ_MULT).clamp(0.0, 255.0) as f32
+                        })
+                        .collect()
+                })
+                .collect();
+            values.push(scans);
+        }
+        let image = if self.render { assemble_image(mode, &values, width, height) } else { RgbImage::new(0, 0) };
+
+        // 未校正时每行图像的水平偏移（像素）
+        let rows_per_line = height as f64 / lines as f64;
+        let drift = clock_error * timing.period / timing.scans[0].1 / rows_per_line;
+        let image_end = line_start(lines).min(end);
+        let vis_start = reception.start_bit - VIS_HEADER_MS * ms;
+        let sync_ratio = if lines_decoded == 0 { 0.0 } else { (syncs.len() as f64 / lines_decoded as f64).min(1.0) };
+        let decimation = self.demodulator.decimation as f64;
+        let decoded = DecodedImage {
+            image,
+            mode,
+            vis_code: reception.vis_code,
+            start_time: vis_start / self.working_rate,
+            end_time: image_end / self.working_rate,
+            start_sample: (vis_start * decimation).round().max(0.0) as usize,
+            end_sample: (image_end * decimation).round() as usize,
+            confidence: 0.25 * reception.vis_quality + 0.75 * sync_ratio,
+            snr_db: reception.snr_db(self.demodulator.noise_gain, self.sample_rate),
+            frequency_offset: offset,
+            clock_error_ppm: clock_error * 1e6,
+            slant_degrees: drift.atan().to_degrees(),
+            slant_corrected: fit.is_some() && self.slant_correction,
+            sync_count: syncs.len() as u32,
+            lines_decoded,
+            complete: lines_decoded == lines,
+        };
+        (decoded, image_end)
+    }
+
+    /// 逐块在预测位置附近寻找同步脉冲，返回（扫描块序号，脉冲起点）
+    fn find_syncs(&self, timing: &ModeTiming, first: f64, lines: u32, offset: f64) -> Vec<(f64, f64)> {
+        let ms = self.working_rate / 1000.0;
+        let length = ((timing.sync_length * ms).round() as usize).max(1);
+        let window = (SYNC_SEARCH_MS * ms).ceil() as usize;
+        let threshold = SYNC_THRESHOLD + offset;
+        let mut line_period = timing.period * ms;
+        let mut anchor = (0.0, first);
+        let mut found: Vec<(f64, f64)> = Vec::new();
+        for line in 0..lines {
+            let line = line as f64;
+            let predicted = (anchor.1 + (line - anchor.0) * line_period).round() as usize;
+            let Some(from) = predicted.checked_sub(window) else { continue };
+            if from < self.track.base {
+                continue;
+            }
+            if predicted + window + length > self.track.end() {
+                break;
+            }
+
+            // 窗口内低于门限的样本数，取最大值所在平台的中点
+            let below: Vec<u32> = (from..predicted + window + length)
+                .map(|i| (self.track.frequency(i) < threshold) as u32)
+                .collect();
+            let mut count: u32 = below[..length].iter().sum();
+            let mut best = (count, 0, 0);
+            for shift in 1..=2 * window {
+                count = count + below[shift + length - 1] - below[shift - 1];
+                if count > best.0 {
+                    best = (count, shift, shift);
+                } else if count == best.0 && best.2 + 1 == shift {
+                    best.2 = shift;
+                }
+            }
+            if (best.0 as f64) < SYNC_MIN_FILL * length as f64 {
+                continue;
+            }
+            let position = from as f64 + (best.1 + best.2) as f64 / 2.0;
+            anchor = (line, position);
+            if let Some(&(first_line, first_position)) = found.first()
+                && line - first_line >= 8.0
+            {
+                line_period = (position - first_position) / (line - first_line);
+            }
+            found.push((line, position));
+        }
+        found
+    }
+}
+
+/// 解码一段完整的单声道录音
+pub fn decode_samples(samples: &[f32], sample_rate: u32) -> Result<Vec<DecodedImage>> {
+    let mut decoder = SstvDecoder::new(sample_rate)?;
+    let mut images = decoder.push(samples);
+    images.extend(decoder.finish());
+    Ok(images)
+}
+
+/// 录音中的一段SSTV传输
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    /// VIS码起点的样本序号
+    pub start: usize,
+    /// 图像结束的样本序号（不含）
+    pub end: usize,
+    /// 模式
+    pub mode: SstvMode,
+    /// 置信度（0-1），见 [`DecodedImage::confidence`]
+    pub confidence: f64,
+    /// 是否包含完整的图像
+    pub complete: bool,
+}
+
+impl Segment {
+    /// 段长度（样本数）
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// 段是否为空
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// 从录音中截取该段
+    pub fn slice<'a>(&self, samples: &'a [f32]) -> &'a [f32] {
+        &samples[self.start.min(samples.len())..self.end.min(samples.len())]
+    }
+}
+
+/// 扫描一段长录音，返回其中所有SSTV传输的位置
+///
+/// 只做VIS检测和同步跟踪，不生成图像，因此比 [`decode_samples`] 快。
+/// 每个段从VIS码起点到图像结束，截取后可单独交给 [`decode_samples`] 解码；
+/// 只有VIS码而没有图像数据的误检也会返回，置信度接近0.25或更低。
+pub fn find_segments(samples: &[f32], sample_rate: u32) -> Result<Vec<Segment>> {
+    let mut decoder = SstvDecoder::new(sample_rate)?;
+    decoder.render = false;
+    let mut images = decoder.push(samples);
+    images.extend(decoder.finish());
+    Ok(images
+        .into_iter()
+        .map(|image| Segment {
+            start: image.start_sample,
+            end: image.end_sample.min(samples.len()).max(image.start_sample),
+            mode: image.mode,
+            confidence: image.confidence,
+            complete: image.complete,
+        })
+        .collect())
+}
+
+/// 解码器状态
+enum State {
+    /// 搜索VIS码，`next` 为下一个候选的起始位位置
+    Searching { next: f64 },
+    /// 已检测到VIS码，等待整幅图像的数据
+    Receiving(Reception),
+}
+
+/// 正在接收的图像
+#[derive(Debug, Clone)]
+struct Reception {
+    mode: SstvMode,
+    vis_code: u8,
+    // 起始位起点（工作采样率下标）
+    start_bit: f64,
+    frequency_offset: f64,
+    // VIS码各段与标称频率的吻合程度（0-1）
+    vis_quality: f64,
+    // 单音窗口内的信号能量和噪声能量（基带功率乘样本数）
+    signal_energy: f64,
+    noise_energy: f64,
+}
+
+impl Reception {
+    /// 图像起点：起始位、8位数据和结束位之后
+    fn image_start(&self, ms: f64) -> f64 {
+        self.start_bit + 10.0 * VIS_BIT_MS * ms
+    }
+
+    fn add_tone(&mut self, power: Option<(f64, f64, usize)>) {
+        if let Some((signal, noise, count)) = power {
+            self.signal_energy += signal * count as f64;
+            self.noise_energy += noise * count as f64;
+        }
+    }
+
+    /// 折算到3kHz带宽的信噪比（dB）
+    ///
+    /// 实数单音的功率是基带相干分量的2倍；基带噪声功率等于输入噪声方差乘滤波器系数平方和。
+    fn snr_db(&self, noise_gain: f64, sample_rate: u32) -> f64 {
+        let signal = 2.0 * self.signal_energy;
+        let noise = self.noise_energy / noise_gain * SNR_BANDWIDTH / (sample_rate as f64 / 2.0);
+        if noise <= 0.0 {
+            return MAX_SNR_DB;
+        }
+        (10.0 * (signal / noise).log10()).min(MAX_SNR_DB)
+    }
+}
+
+/// 解码所需的模式时序（毫秒），与调制器的生成顺序一致
+pub(crate) struct ModeTiming {
+    /// VIS码之后、第一个扫描块之前的时长（只有Scottie有起始同步脉冲）
+    pub(crate) lead_in: f64,
+    /// 扫描块周期
+    pub(crate) period: f64,
+    /// 同步脉冲在扫描块内的起点
+    pub(crate) sync_start: f64,
+    /// 同步脉冲时长
+    pub(crate) sync_length: f64,
+    /// 各扫描段在扫描块内的起点和每像素时长，按传输顺序
+    pub(crate) scans: &'static [(f64, f64)],
+}
+
+impl ModeTiming {
+    pub(crate) fn of(mode: SstvMode) -> Self {
+        match mode {
+            // 分离 1.5、绿、分离 1.5、蓝、同步 9、分离 1.5、红
+            SstvMode::ScottieDx => Self {
+                lead_in: 9.0,
+                period: 1050.3,
+                sync_start: 694.2,
+                sync_length: 9.0,
+                scans: &[(1.5, 1.08), (348.6, 1.08), (704.7, 1.08)],
+            },
+            // 同步 9、Porch 3、亮度、分离 4.5、Porch 1.5、色度（偶数块R-Y，奇数块B-Y）
+            SstvMode::Robot36 => Self {
+                lead_in: 0.0,
+                period: 150.0,
+                sync_start: 0.0,
+                sync_length: 9.0,
+                scans: &[(12.0, 0.275), (106.0, 0.1375)],
+            },
+            // 同步 20、Porch 2.08、亮度0、R-Y、B-Y、亮度1
+            SstvMode::Pd120 => Self {
+                lead_in: 0.0,
+                period: 508.48,
+                sync_start: 0.0,
+                sync_length: 20.0,
+                scans: &[(22.08, 0.19), (143.68, 0.19), (265.28, 0.19), (386.88, 0.19)],
+            },
+            // 同步 4.862、分隔 0.572，之后绿、蓝、红各跟一个分隔
+            SstvMode::MartinM1 => Self {
+                lead_in: 0.0,
+                period: 446.446,
+                sync_start: 0.0,
+                sync_length: 4.862,
+                scans: &[(5.434, 0.4576), (152.438, 0.4576), (299.442, 0.4576)],
+            },
+        }
+    }
+
+    /// VIS码之后整幅图像的时长（ms）
+    pub(crate) fn transmission_ms(&self, mode: SstvMode) -> f64 {
+        self.lead_in + self.period * mode.get_scan_line_count() as f64
+    }
+}
+
+/// 由各扫描块的取值合成图像，未接收的行保持黑色
+fn assemble_image(mode: SstvMode, values: &[Vec<Vec<f32>>], width: u32, height: u32) -> RgbImage {
+    let mut image = RgbImage::new(width, height);
+    match mode {
+        // 扫描顺序为绿、蓝、红
+        SstvMode::ScottieDx | SstvMode::MartinM1 => {
+            for (row, scans) in values.iter().enumerate() {
+                for x in 0..width {
+                    let i = x as usize;
+                    let pixel = Rgb([scans[2][i] as u8, scans[0][i] as u8, scans[1][i] as u8]);
+                    image.put_pixel(x, row as u32, pixel);
+                }
+            }
+        }
+        // 偶数块携带两行平均的R-Y，奇数块携带B-Y
+        SstvMode::Robot36 => {
+            for (pair, lines) in values.chunks(2).enumerate() {
+                let row = pair as u32 * 2;
+                for x in 0..width {
+                    let i = x as usize;
+                    let ry = lines[0][1][i];
+                    let by = lines.get(1).map_or(128.0, |line| line[1][i]);
+                    image.put_pixel(x, row, yuv_to_rgb(lines[0][0][i], ry, by));
+                    if let Some(line) = lines.get(1) {
+                        image.put_pixel(x, row + 1, yuv_to_rgb(line[0][i], ry, by));
+                    }
+                }
+            }
+        }
+        // 每块两行：亮度0、R-Y、B-Y、亮度1
+        SstvMode::Pd120 => {
+            for (line, scans) in values.iter().enumerate() {
+                let row = line as u32 * 2;
+                for x in 0..width {
+                    let i = x as usize;
+                    let (ry, by) = (scans[1][i], scans[2][i]);
+                    image.put_pixel(x, row, yuv_to_rgb(scans[0][i], ry, by));
+                    if row + 1 < height {
+                        image.put_pixel(x, row + 1, yuv_to_rgb(scans[3][i], ry, by));
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// 调制器所用BT.601（16-235）亮度/色差的逆变换
+fn yuv_to_rgb(y: f32, ry: f32, by: f32) -> Rgb<u8> {
+    let y = 1.164 * (y - 16.0);
+    let (v, u) = (ry - 128.0, by - 128.0);
+    let r = y + 1.596 * v;
+    let g = y - 0.813 * v - 0.392 * u;
+    let b = y + 2.017 * u;
+    Rgb([r.round().clamp(0.0, 255.0) as u8, g.round().clamp(0.0, 255.0) as u8, b.round().clamp(0.0, 255.0) as u8])
+}
+
+/// 最小二乘直线拟合，剔除残差超过 `tolerance` 的点后再拟合一次
+pub(crate) fn fit_line(points: &[(f64, f64)], tolerance: f64) -> Option<(f64, f64)> {
+    fn least_squares<'a>(points: impl Iterator<Item = &'a (f64, f64)> + Clone) -> Option<(f64, f64)> {
+        let n = points.clone().count() as f64;
+        let (sx, sy) = points.clone().fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x, sy + y));
+        let (mx, my) = (sx / n, sy / n);
+        let (sxx, sxy) = points.fold((0.0, 0.0), |(sxx, sxy), &(x, y)| {
+            (sxx + (x - mx) * (x - mx), sxy + (x - mx) * (y - my))
+        });
+        if n < 2.0 || sxx == 0.0 {
+            return None;
+        }
+        let slope = sxy / sxx;
+        Some((my - slope * mx, slope))
+    }
+
+    let (intercept, slope) = least_squares(points.iter())?;
+    least_squares(points.iter().filter(|&&(x, y)| (y - intercept - slope * x).abs() <= tolerance))
+}
+
+/// 复基带解调器：混频、低通滤波并抽取
+pub(crate) struct Demodulator {
+    taps: Vec<f32>,
+    pub(crate) decimation: usize,
+    // 滤波器系数平方和，即白噪声通过滤波器后的功率增益
+    noise_gain: f64,
+    // 混频结果的环形缓冲区，存两份以便取连续窗口
+    history: Vec<[f32; 2]>,
+    position: usize,
+    mixer_phase: f64,
+    mixer_step: f64,
+    // 已输入的样本数
+    consumed: usize,
+}
+
+impl Demodulator {
+    pub(crate) fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+        // Blackman窗的过渡带约为 5.5·fs/N
+        let half = (5.5 * rate / LOWPASS_TRANSITION / 2.0).ceil() as usize;
+        let length = 2 * half + 1;
+        let cutoff = LOWPASS_CUTOFF / rate;
+        let taps: Vec<f64> = (0..length)
+            .map(|i| {
+                let n = i as f64 - half as f64;
+                let sinc = if n == 0.0 { 2.0 * cutoff } else { (2.0 * PI * cutoff * n).sin() / (PI * n) };
+                let x = i as f64 / (length - 1) as f64;
+                sinc * (0.42 - 0.5 * (2.0 * PI * x).cos() + 0.08 * (4.0 * PI * x).cos())
+            })
+            .collect();
+        let sum: f64 = taps.iter().sum();
+        let taps: Vec<f64> = taps.iter().map(|tap| tap / sum).collect();
+        Self {
+            noise_gain: taps.iter().map(|tap| tap * tap).sum(),
+            taps: taps.iter().map(|&tap| tap as f32).collect(),
+            decimation: (sample_rate / MIN_WORKING_RATE).max(1) as usize,
+            history: vec![[0.0; 2]; 2 * length],
+            position: 0,
+            mixer_phase: 0.0,
+            mixer_step: 2.0 * PI * CENTER_FREQUENCY / rate,
+            consumed: 0,
+        }
+    }
+
+    /// 处理输入样本，把抽取后的基带样本追加到 `out`；输出与输入对齐（已补偿滤波器延迟）
+    pub(crate) fn process(&mut self, input: &[f32], out: &mut Vec<[f32; 2]>) {
+        let length = self.taps.len();
+        let delay = length / 2;
+        for &sample in input {
+            let (sin, cos) = self.mixer_phase.sin_cos();
+            self.mixer_phase += self.mixer_step;
+            if self.mixer_phase >= 2.0 * PI {
+                self.mixer_phase -= 2.0 * PI;
+            }
+            let mixed = [sample * cos as f32, -sample * sin as f32];
+            self.history[self.position] = mixed;
+            self.history[self.position + length] = mixed;
+            self.position = (self.position + 1) % length;
+            self.consumed += 1;
+
+            // 滤波器中心对应的输入样本序号为 consumed - 1 - delay
+            if self.consumed > delay && (self.consumed - 1 - delay).is_multiple_of(self.decimation) {
+                let window = &self.history[self.position..self.position + length];
+                let mut acc = [0.0f32; 2];
+                for (tap, value) in self.taps.iter().zip(window) {
+                    acc[0] += tap * value[0];
+                    acc[1] += tap * value[1];
+                }
+                out.push(acc);
+            }
+        }
+    }
+
+    /// 补零冲出滤波器中剩余的样本
+    pub(crate) fn flush(&mut self, out: &mut Vec<[f32; 2]>) {
+        let zeros = vec![0.0; self.taps.len() / 2];
+        self.process(&zeros, out);
+    }
+}
+
+/// 工作采样率下的复基带和瞬时频率缓冲区
+pub(crate) struct Track {
+    // 缓冲区首个样本的下标（工作采样率，自输入开始计）
+    pub(crate) base: usize,
+    baseband: Vec<[f32; 2]>,
+    // 瞬时频率的前缀和，比 `baseband` 多一个元素
+    prefix: Vec<f64>,
+    previous: [f32; 2],
+    // 每弧度相位差对应的频率（Hz）
+    hz_per_radian: f64,
+}
+
+impl Track {
+    pub(crate) fn new(working_rate: f64) -> Self {
+        Self {
+            base: 0,
+            baseband: Vec::new(),
+            prefix: vec![0.0],
+            previous: [0.0; 2],
+            hz_per_radian: working_rate / (2.0 * PI),
+        }
+    }
+
+    pub(crate) fn end(&self) -> usize {
+        self.base + self.baseband.len()
+    }
+
+    pub(crate) fn extend(&mut self, samples: &[[f32; 2]]) {
+        let mut sum = *self.prefix.last().unwrap_or(&0.0);
+        for &z in samples {
+            let p = self.previous;
+            // z·conj(p) 的辐角即相邻样本的相位差
+            let re = (z[0] * p[0] + z[1] * p[1]) as f64;
+            let im = (z[1] * p[0] - z[0] * p[1]) as f64;
+            sum += CENTER_FREQUENCY + im.atan2(re) * self.hz_per_radian;
+            self.prefix.push(sum);
+            self.baseband.push(z);
+            self.previous = z;
+        }
+    }
+
+    /// 第 `index` 个样本的瞬时频率（Hz）
+    fn frequency(&self, index: usize) -> f64 {
+        let i = index - self.base;
+        self.prefix[i + 1] - self.prefix[i]
+    }
+
+    /// 瞬时频率从缓冲区起点到 `position` 的积分（频率在每个样本间隔内视为常数）
+    fn integral(&self, position: f64) -> f64 {
+        let relative = position - self.base as f64;
+        let i = relative.floor() as usize;
+        if i >= self.baseband.len() {
+            return self.prefix[self.baseband.len()];
+        }
+        self.prefix[i] + (relative - i as f64) * (self.prefix[i + 1] - self.prefix[i])
+    }
+
+    /// `[from, to)` 内的平均频率，超出缓冲区时返回 `None`
+    pub(crate) fn mean(&self, from: f64, to: f64) -> Option<f64> {
+        if from < self.base as f64 || to > self.end() as f64 || to <= from {
+            return None;
+        }
+        Some((self.integral(to) - self.integral(from)) / (to - from))
+    }
+
+    /// 单音窗口内基带的相干功率和剩余（噪声）功率，以及样本数
+    ///
+    /// 先按预期频率 `frequency`（Hz）反旋转，再用分块和的相位差修正残余频偏；
+    /// 滤波后的噪声在相邻样本间强相关，直接用逐样本相位差估计频率会有明显偏差。
+    fn tone_power(&self, range: std::ops::Range<usize>, frequency: f64) -> Option<(f64, f64, usize)> {
+        if range.start < self.base || range.end > self.end() || range.len() < 8 {
+            return None;
+        }
+        let window = &self.baseband[range.start - self.base..range.end - self.base];
+        let rotate = |step: f64| {
+            window.iter().enumerate().map(move |(n, z)| {
+                let (sin, cos) = (-step * n as f64).sin_cos();
+                let (x, y) = (z[0] as f64, z[1] as f64);
+                (x * cos - y * sin, x * sin + y * cos)
+            })
+        };
+
+        let expected = (frequency - CENTER_FREQUENCY) / self.hz_per_radian;
+        let block = (window.len() / 4).clamp(1, 32);
+        let sums: Vec<(f64, f64)> = rotate(expected)
+            .collect::<Vec<_>>()
+            .chunks_exact(block)
+            .map(|chunk| chunk.iter().fold((0.0, 0.0), |(re, im), &(x, y)| (re + x, im + y)))
+            .collect();
+        let (re, im) = sums.windows(2).fold((0.0, 0.0), |(re, im), pair| {
+            let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+            (re + bx * ax + by * ay, im + by * ax - bx * ay)
+        });
+        let step = expected + im.atan2(re) / block as f64;
+
+        let (coherent_re, coherent_im, power) = rotate(step)
+            .fold((0.0, 0.0, 0.0), |(re, im, power), (x, y)| (re + x, im + y, power + x * x + y * y));
+        let count = window.len() as f64;
+        let signal = (coherent_re * coherent_re + coherent_im * coherent_im) / (count * count);
+        Some((signal, (power / count - signal).max(0.0), window.len()))
+    }
+
+    /// 丢弃 `position` 之前的数据
+    fn discard_before(&mut self, position: usize) {
+        let count = position.saturating_sub(self.base).min(self.baseband.len());
+        self.baseband.drain(..count);
+        self.prefix.drain(..count);
+        self.base += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sstv::{FrequencyMapping, SstvModulator};
+    use image::DynamicImage;
+
+    /// 平滑的测试图像：水平红色渐变、垂直绿色渐变、对角蓝色渐变
+    fn test_image(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            Rgb([
+                (40 + x * 180 / width) as u8,
+                (30 + y * 200 / height) as u8,
+                (60 + (x + y) * 150 / (width + height)) as u8,
+            ])
+        })
+    }
+
+    fn modulate(mode: SstvMode, sample_rate: u32, image: &RgbImage) -> Vec<f32> {
+        let mut modulator = SstvModulator::new(mode).with_sample_rate(sample_rate).unwrap();
+        modulator.modulate_image(&DynamicImage::ImageRgb8(image.clone())).unwrap();
+        modulator.get_samples().to_vec()
+    }
+
+    fn mean_error(a: &RgbImage, b: &RgbImage) -> f64 {
+        let total: f64 = a.as_raw().iter().zip(b.as_raw()).map(|(&x, &y)| (x as f64 - y as f64).abs()).sum();
+        total / a.as_raw().len() as f64
+    }
+
+    /// 确定性的高斯噪声（xorshift + Box-Muller）
+    fn add_noise(samples: &mut [f32], sigma: f64, mut state: u64) {
+        let mut uniform = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            ((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+        };
+        for sample in samples.iter_mut() {
+            let noise = (-2.0 * uniform().ln()).sqrt() * (2.0 * PI * uniform()).cos();
+            *sample += (noise * sigma) as f32;
+        }
+    }
+
+    #[test]
+    fn test_vis_codes() {
+        for mode in [SstvMode::ScottieDx, SstvMode::Robot36, SstvMode::Pd120, SstvMode::MartinM1] {
+            let code = u8::from_str_radix(mode.get_vis_code(), 2).unwrap();
+            assert_eq!(SstvMode::from_vis_code(code).unwrap().get_mode_name(), mode.get_mode_name());
+        }
+        assert!(SstvMode::from_vis_code(0).is_none());
+    }
+
+    #[test]
+    fn test_round_trip_all_modes() {
+        for (mode, rate) in [
+            (SstvMode::Robot36, 11025),
+            (SstvMode::MartinM1, 8000),
+            (SstvMode::Pd120, 22050),
+            (SstvMode::ScottieDx, 11025),
+        ] {
+            let (width, height) = mode.get_dimensions();
+            let image = test_image(width, height);
+            let samples = modulate(mode, rate, &image);
+            let images = decode_samples(&samples, rate).unwrap();
+            assert_eq!(images.len(), 1, "{}", mode.get_mode_name());
+            let decoded = &images[0];
+            assert_eq!(decoded.mode.get_mode_name(), mode.get_mode_name());
+            assert!(decoded.complete);
+            assert_eq!(decoded.sync_count, mode.get_scan_line_count());
+            // 编码器开头有200ms静音和800ms前导音
+            assert!((decoded.start_time - 1.0).abs() < 0.005, "{}", decoded.start_time);
+            assert!(decoded.frequency_offset.abs() < 2.0);
+            assert!(decoded.clock_error_ppm.abs() < 30.0, "{}", decoded.clock_error_ppm);
+            assert!(decoded.snr_db > 40.0, "{}", decoded.snr_db);
+            let error = mean_error(&decoded.image, &image);
+            assert!(error < 4.0, "{} error {}", mode.get_mode_name(), error);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_default_sample_rate() {
+        // sstv-encode 默认输出各模式的默认采样率（PD120为6000Hz，Robot36为8000Hz），解码器须能直接读取
+        for mode in [SstvMode::Robot36, SstvMode::Pd120] {
+            let rate = mode.get_default_sample_rate();
+            let (width, height) = mode.get_dimensions();
+            let image = test_image(width, height);
+            let images = decode_samples(&modulate(mode, rate, &image), rate).unwrap();
+            assert_eq!(images.len(), 1, "{}", mode.get_mode_name());
+            let decoded = &images[0];
+            assert_eq!(decoded.mode.get_mode_name(), mode.get_mode_name());
+            assert!(decoded.complete);
+            assert_eq!(decoded.sync_count, mode.get_scan_line_count());
+            let error = mean_error(&decoded.image, &image);
+            assert!(error < 6.0, "{} error {}", mode.get_mode_name(), error);
+        }
+        assert!(SstvDecoder::new(crate::MIN_SAMPLE_RATE).is_ok());
+        assert!(SstvDecoder::new(crate::MIN_SAMPLE_RATE - 1).is_err());
+        assert!(SstvDecoder::new(crate::MAX_SAMPLE_RATE + 1).is_err());
+    }
+
+    #[test]
+    fn test_long_recording_streaming() {
+        // 静音、两幅图像（第二幅有频率偏移）、噪声，分小块送入
+        let rate = 11025;
+        let first = test_image(320, 240);
+        let second = RgbImage::from_fn(320, 240, |x, y| Rgb([(x % 256) as u8, 128, (y % 256) as u8]));
+        let mut recording = vec![0.0f32; rate as usize * 3];
+        recording.extend(modulate(SstvMode::Robot36, rate, &first));
+        // 两秒间隔加上第二段的静音和前导音
+        let second_start = recording.len() as f64 / rate as f64 + 3.0;
+        recording.extend(vec![0.0f32; rate as usize * 2]);
+        let mut shifted = SstvModulator::new(SstvMode::Robot36)
+            .with_sample_rate(rate).unwrap()
+            .with_frequency_mapping(FrequencyMapping::offset(80.0));
+        shifted.modulate_image(&DynamicImage::ImageRgb8(second.clone())).unwrap();
+        recording.extend(shifted.get_samples());
+        recording.extend(vec![0.0f32; rate as usize]);
+        add_noise(&mut recording, 0.02, 7);
+
+        let mut decoder = SstvDecoder::new(rate).unwrap();
+        let mut images = Vec::new();
+        for chunk in recording.chunks(1000) {
+            images.extend(decoder.push(chunk));
+        }
+        images.extend(decoder.finish());
+        assert_eq!(images.len(), 2);
+        assert!((images[0].start_time - 4.0).abs() < 0.005);
+        assert!((images[1].start_time - second_start).abs() < 0.005);
+        assert!(images[0].frequency_offset.abs() < 3.0);
+        assert!((images[1].frequency_offset - 80.0).abs() < 3.0);
+        assert!(mean_error(&images[0].image, &first) < 6.0);
+        assert!(mean_error(&images[1].image, &second) < 6.0);
+    }
+
+    #[test]
+    fn test_find_segments() {
+        // 噪声、图像、语音（1900Hz单音和扫频）、带偏移的图像、只有VIS码的残段
+        let rate = 11025;
+        let image = test_image(320, 240);
+        let clean = modulate(SstvMode::Robot36, rate, &image);
+        let mut recording = vec![0.0f32; rate as usize * 2];
+        let first_start = recording.len() + rate as usize;
+        recording.extend(&clean);
+        let mut phase = 0.0f64;
+        for n in 0..rate as usize * 2 {
+            let t = n as f64 / rate as f64;
+            let frequency = if t < 0.5 { 1900.0 } else { 300.0 + 2700.0 * (t - 0.5) / 1.5 };
+            phase += 2.0 * PI * frequency / rate as f64;
+            recording.push((0.5 * phase.sin()) as f32);
+        }
+        let second_start = recording.len() + rate as usize;
+        let mut shifted = SstvModulator::new(SstvMode::Robot36)
+            .with_sample_rate(rate).unwrap()
+            .with_frequency_mapping(FrequencyMapping::offset(-60.0));
+        shifted.modulate_image(&DynamicImage::ImageRgb8(image.clone())).unwrap();
+        recording.extend(shifted.get_samples());
+        recording.extend(vec![0.0f32; rate as usize]);
+        let third_start = recording.len() + rate as usize;
+        recording.extend(&clean[..rate as usize * 2]);
+        add_noise(&mut recording, 0.02, 11);
+
+        let segments = find_segments(&recording, rate).unwrap();
+        assert_eq!(segments.len(), 3, "{:?}", segments);
+        for (segment, start) in segments.iter().zip([first_start, second_start, third_start]) {
+            assert_eq!(segment.mode.get_mode_name(), "Robot36");
+            assert!(segment.start.abs_diff(start) < 60, "{} {}", segment.start, start);
+        }
+        for segment in &segments[..2] {
+            assert!(segment.complete);
+            assert!(segment.confidence > 0.9, "{}", segment.confidence);
+            // 150ms × 240行
+            assert!((segment.len() as f64 / rate as f64 - 0.91 - 36.0).abs() < 0.05, "{}", segment.len());
+        }
+        assert!(!segments[2].complete);
+        assert!(segments[2].confidence < 0.3, "{}", segments[2].confidence);
+
+        // 截出的段可以单独解码
+        let images = decode_samples(segments[1].slice(&recording), rate).unwrap();
+        assert_eq!(images.len(), 1);
+        assert!(images[0].complete);
+        assert!(images[0].start_time < 0.005);
+        assert!(mean_error(&images[0].image, &image) < 6.0);
+    }
+
+    #[test]
+    fn test_slant_estimate_and_correction() {
+        // 按11025Hz生成、声称11030Hz解码，相当于接收端时钟快了约453ppm
+        let image = test_image(320, 240);
+        let samples = modulate(SstvMode::Robot36, 11025, &image);
+        let expected_ppm = (11025.0 / 11030.0 - 1.0) * 1e6;
+
+        let mut decoder = SstvDecoder::new(11030).unwrap();
+        let mut images = decoder.push(&samples);
+        images.extend(decoder.finish());
+        let decoded = images.remove(0);
+        assert!(decoded.slant_corrected);
+        assert!((decoded.clock_error_ppm - expected_ppm).abs() < 20.0, "{}", decoded.clock_error_ppm);
+        assert!(decoded.slant_degrees < -1.0);
+        assert!(mean_error(&decoded.image, &image) < 4.0);
+
+        let mut raw = SstvDecoder::new(11030).unwrap().with_slant_correction(false);
+        let mut images = raw.push(&samples);
+        images.extend(raw.finish());
+        assert!(!images[0].slant_corrected);
+        assert!((images[0].clock_error_ppm - expected_ppm).abs() < 20.0);
+        assert!(mean_error(&images[0].image, &image) > mean_error(&decoded.image, &image) + 2.0);
+    }
+
+    #[test]
+    fn test_snr_estimate() {
+        let rate = 11025;
+        let image = test_image(320, 240);
+        let clean = modulate(SstvMode::Robot36, rate, &image);
+        let amplitude = clean.iter().fold(0.0f32, |max, s| max.max(s.abs())) as f64;
+        for snr in [10.0, 20.0, 30.0] {
+            // 3kHz带宽内的信噪比 = (A²/2) / (σ²·3000/(fs/2))
+            let sigma = (amplitude * amplitude / 2.0 / 10f64.powf(snr / 10.0) * (rate as f64 / 2.0) / 3000.0).sqrt();
+            let mut noisy = clean.clone();
+            add_noise(&mut noisy, sigma, 42);
+            let images = decode_samples(&noisy, rate).unwrap();
+            assert_eq!(images.len(), 1);
+            assert!((images[0].snr_db - snr).abs() < 1.5, "expected {} got {}", snr, images[0].snr_db);
+            assert!(images[0].sync_count >= 220);
+        }
+    }
+
+    #[test]
+    fn test_truncated_recording() {
+        let image = test_image(320, 256);
+        let samples = modulate(SstvMode::MartinM1, 8000, &image);
+        // 截掉一半
+        let images = decode_samples(&samples[..samples.len() / 2], 8000).unwrap();
+        assert_eq!(images.len(), 1);
+        let decoded = &images[0];
+        assert!(!decoded.complete);
+        assert!(decoded.lines_decoded > 100 && decoded.lines_decoded < 140, "{}", decoded.lines_decoded);
+        assert_eq!(decoded.image.dimensions(), (320, 256));
+        assert_eq!(decoded.to_json()["complete"], false);
+
+        assert!(SstvDecoder::new(4000).is_err());
+        assert!(decode_samples(&vec![0.0; 80000], 8000).unwrap().is_empty());
+    }
+}
@@ -50,6 +50,10 @@ pub enum SstvError {
     /// 无效的音频格式
     #[error("无效的音频格式: {0}")]
     InvalidFormat(String),
+
+    /// 操作被取消令牌中止
+    #[error("操作已取消")]
+    Cancelled,
 }
 
 /// 库的Result类型别名
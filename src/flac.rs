@@ -0,0 +1,785 @@
+//! FLAC无损压缩编码模块
+//!
+//! 纯Rust实现的FLAC编码器：固定预测（0-4阶）或量化线性预测（LPC）加分区Rice编码，
+//! 支持16/24位整数样本和1-8个声道。SSTV信号由平滑的正弦音调组成，
+//! 二阶LPC即可近乎完整地预测单一音调。
+
+use crate::audio::{ChannelMixer, ChannelRouting, Dither, PcmFormat, Quantizer, SampleSink};
+use crate::error::{Result, SstvError};
+use md5::{Digest, Md5};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// 每帧每声道的样本数
+const BLOCK_SIZE: usize = 4096;
+/// 分区Rice编码的最大分区阶数
+const MAX_PARTITION_ORDER: u32 = 8;
+/// 最大固定预测阶数
+const MAX_FIXED_ORDER: usize = 4;
+/// 最大LPC阶数
+const MAX_LPC_ORDER: usize = 12;
+/// 参与比较的LPC阶数
+const LPC_ORDERS: [usize; 6] = [2, 3, 4, 6, 8, 12];
+/// STREAMINFO元数据块长度
+const STREAMINFO_LENGTH: usize = 34;
+
+/// FLAC文件写入器
+///
+/// 作为 [`SampleSink`] 使用：样本按块缓冲，每满一块编码一帧写出。
+/// `finish` 时编码剩余样本，并回写STREAMINFO中的总样本数、帧长度范围和MD5。
+///
+/// ```rust,no_run
+/// use sstv_rust::{FlacWriter, PcmFormat, Dither, ChannelRouting, SampleSink};
+///
+/// let mut writer = FlacWriter::create("out.flac", 48000, PcmFormat::Int16, Dither::None, ChannelRouting::mono()).unwrap();
+/// writer.write_samples(&[0.0, 0.5, -0.5]).unwrap();
+/// writer.finish().unwrap();
+/// ```
+pub struct FlacWriter<W: Write + Seek> {
+    writer: std::io::BufWriter<W>,
+    start_position: u64,
+    sample_rate: u32,
+    quantizer: Quantizer,
+    mixer: ChannelMixer,
+    bits_per_sample: u32,
+    // 每个声道当前块的样本
+    block: Vec<Vec<i32>>,
+    frame_number: u64,
+    total_samples: u64,
+    min_frame_size: u32,
+    max_frame_size: u32,
+    md5: Md5,
+    md5_scratch: Vec<u8>,
+    frame: BitWriter,
+    finished: bool,
+}
+
+impl FlacWriter<std::fs::File> {
+    /// 创建FLAC文件
+    pub fn create<P: AsRef<Path>>(
+        filename: P,
+        sample_rate: u32,
+        format: PcmFormat,
+        dither: Dither,
+        routing: ChannelRouting,
+    ) -> Result<Self> {
+        // 先校验参数，避免参数无效时截断已有文件
+        check_parameters(sample_rate, format)?;
+        let file = std::fs::File::create(filename)?;
+        Self::new(file, sample_rate, format, dither, routing)
+    }
+}
+
+/// 检查FLAC能否以该采样率和样本格式编码，返回位深度
+fn check_parameters(sample_rate: u32, format: PcmFormat) -> Result<u32> {
+    let bits_per_sample = match format {
+        PcmFormat::Int16 | PcmFormat::Int24 => format.bits_per_sample() as u32,
+        PcmFormat::Int32 | PcmFormat::Float32 => {
+            return Err(SstvError::InvalidFormat(format!(
+                "FLAC不支持{:?}样本格式，请使用16位或24位整数",
+                format
+            )));
+        }
+    };
+    if sample_rate == 0 || sample_rate >= 1 << 20 {
+        return Err(SstvError::InvalidAudioParameter {
+            parameter: "sample_rate".to_string(),
+            value: sample_rate.to_string(),
+        });
+    }
+    Ok(bits_per_sample)
+}
+
+impl<W: Write + Seek> FlacWriter<W> {
+    /// 在可回写的输出上创建FLAC写入器
+    ///
+    /// FLAC只支持整数样本，`format` 必须为16位或24位。
+    pub fn new(
+        writer: W,
+        sample_rate: u32,
+        format: PcmFormat,
+        dither: Dither,
+        routing: ChannelRouting,
+    ) -> Result<Self> {
+        let bits_per_sample = check_parameters(sample_rate, format)?;
+
+        let mut writer = std::io::BufWriter::new(writer);
+        let start_position = writer.stream_position()?;
+        let channels = routing.channels() as usize;
+
+        let mut flac = Self {
+            writer,
+            start_position,
+            sample_rate,
+            quantizer: Quantizer::new(format, dither),
+            mixer: ChannelMixer::new(routing, sample_rate),
+            bits_per_sample,
+            block: (0..channels).map(|_| Vec::with_capacity(BLOCK_SIZE)).collect(),
+            frame_number: 0,
+            total_samples: 0,
+            min_frame_size: 0,
+            max_frame_size: 0,
+            md5: Md5::new(),
+            md5_scratch: Vec::new(),
+            frame: BitWriter::new(),
+            finished: false,
+        };
+
+        // 文件头和占位的STREAMINFO（总样本数0表示未知），finish时回写
+        flac.writer.write_all(b"fLaC")?;
+        flac.writer.write_all(&[0x80, 0, 0, STREAMINFO_LENGTH as u8])?;
+        let streaminfo = flac.streaminfo([0; 16]);
+        flac.writer.write_all(&streaminfo)?;
+
+        Ok(flac)
+    }
+
+    /// 取回内部的写入器（应先调用 `finish`）
+    pub fn into_inner(self) -> Result<W> {
+        self.writer.into_inner().map_err(|e| SstvError::IoError(e.into_error()))
+    }
+
+    fn streaminfo(&self, md5: [u8; 16]) -> Vec<u8> {
+        let mut bits = BitWriter::new();
+        bits.write(BLOCK_SIZE as u64, 16);
+        bits.write(BLOCK_SIZE as u64, 16);
+        bits.write(self.min_frame_size as u64, 24);
+        bits.write(self.max_frame_size as u64, 24);
+        bits.write(self.sample_rate as u64, 20);
+        bits.write(self.block.len() as u64 - 1, 3);
+        bits.write(self.bits_per_sample as u64 - 1, 5);
+        bits.write(self.total_samples >> 32, 4);
+        bits.write(self.total_samples & 0xFFFF_FFFF, 32);
+        let mut bytes = bits.into_bytes();
+        bytes.extend_from_slice(&md5);
+        bytes
+    }
+
+    // 编码并写出当前块
+    fn flush_block(&mut self) -> Result<()> {
+        let block_size = self.block[0].len();
+        if block_size == 0 {
+            return Ok(());
+        }
+
+        // MD5按交错的小端有符号样本计算
+        let bytes_per_sample = self.bits_per_sample as usize / 8;
+        self.md5_scratch.clear();
+        for i in 0..block_size {
+            for channel in &self.block {
+                self.md5_scratch.extend_from_slice(&channel[i].to_le_bytes()[..bytes_per_sample]);
+            }
+        }
+        self.md5.update(&self.md5_scratch);
+
+        let bits = &mut self.frame;
+        bits.clear();
+
+        // 帧头：同步码 + 保留位 + 固定块长
+        bits.write(0xFFF8, 16);
+        let (size_code, explicit_size) = if block_size == BLOCK_SIZE {
+            (0b1100, None)
+        } else {
+            (0b0111, Some(block_size as u64 - 1))
+        };
+        bits.write(size_code, 4);
+        // 采样率取自STREAMINFO
+        bits.write(0b0000, 4);
+        // 各声道独立编码
+        bits.write(self.block.len() as u64 - 1, 4);
+        bits.write(if self.bits_per_sample == 16 { 0b100 } else { 0b110 }, 3);
+        bits.write(0, 1);
+        write_utf8(bits, self.frame_number);
+        if let Some(size) = explicit_size {
+            bits.write(size, 16);
+        }
+        let crc = crc8(bits.bytes());
+        bits.write(crc as u64, 8);
+
+        for channel in &self.block {
+            encode_subframe(bits, channel, self.bits_per_sample);
+        }
+
+        bits.align();
+        let crc = crc16(bits.bytes());
+        bits.write(crc as u64, 16);
+
+        let frame = bits.bytes();
+        self.writer.write_all(frame)?;
+
+        let frame_size = frame.len() as u32;
+        self.min_frame_size = if self.frame_number == 0 {
+            frame_size
+        } else {
+            self.min_frame_size.min(frame_size)
+        };
+        self.max_frame_size = self.max_frame_size.max(frame_size);
+        self.frame_number += 1;
+        self.total_samples += block_size as u64;
+
+        for channel in self.block.iter_mut() {
+            channel.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SampleSink for FlacWriter<W> {
+    fn write_sample(&mut self, sample: f32) -> Result<()> {
+        let (quantizer, block) = (&mut self.quantizer, &mut self.block);
+        let mut channel = 0;
+        self.mixer.write_frame(sample, |value| {
+            block[channel].push(quantizer.quantize(value));
+            channel += 1;
+            Ok(())
+        })?;
+
+        if self.block[0].len() == BLOCK_SIZE {
+            self.flush_block()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.flush_block()?;
+
+        let md5: [u8; 16] = self.md5.clone().finalize().into();
+        let streaminfo = self.streaminfo(md5);
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.start_position + 8))?;
+        self.writer.write_all(&streaminfo)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+
+        self.finished = true;
+        Ok(())
+    }
+}
+
+// 预测器
+enum Predictor {
+    // 固定多项式预测，阶数0-4
+    Fixed(usize),
+    // 量化线性预测
+    Lpc {
+        coefficients: Vec<i32>,
+        precision: u32,
+        shift: u32,
+    },
+}
+
+impl Predictor {
+    fn order(&self) -> usize {
+        match self {
+            Predictor::Fixed(order) => *order,
+            Predictor::Lpc { coefficients, .. } => coefficients.len(),
+        }
+    }
+
+    // 除预热样本和残差外的头部比特数
+    fn header_bits(&self) -> u64 {
+        match self {
+            Predictor::Fixed(_) => 0,
+            Predictor::Lpc { coefficients, precision, .. } => 4 + 5 + coefficients.len() as u64 * *precision as u64,
+        }
+    }
+}
+
+// 编码一个声道的子帧：在常量、逐样本、固定预测和LPC中选最短的
+fn encode_subframe(bits: &mut BitWriter, samples: &[i32], bits_per_sample: u32) {
+    // 子帧头：填充位0 + 6位类型 + 无wasted bits
+    if samples.iter().all(|&s| s == samples[0]) {
+        bits.write(0b0000_0000, 8);
+        bits.write_signed(samples[0] as i64, bits_per_sample);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * bits_per_sample as u64;
+    let mut best: Option<(Predictor, Vec<i64>, RicePlan)> = None;
+    let mut best_bits = verbatim_bits;
+
+    let mut candidates: Vec<Predictor> = (0..=MAX_FIXED_ORDER.min(samples.len() - 1))
+        .map(Predictor::Fixed)
+        .collect();
+    candidates.extend(lpc_candidates(samples, bits_per_sample));
+
+    for predictor in candidates {
+        let order = predictor.order();
+        let residual = match &predictor {
+            Predictor::Fixed(order) => fixed_residual(samples, *order),
+            Predictor::Lpc { coefficients, shift, .. } => match lpc_residual(samples, coefficients, *shift) {
+                Some(residual) => residual,
+                None => continue,
+            },
+        };
+        let plan = RicePlan::choose(&residual, order);
+        let total = order as u64 * bits_per_sample as u64 + predictor.header_bits() + plan.estimated_bits;
+        if total < best_bits {
+            best_bits = total;
+            best = Some((predictor, residual, plan));
+        }
+    }
+
+    match best {
+        Some((predictor, residual, plan)) => {
+            let order = predictor.order();
+            match &predictor {
+                Predictor::Fixed(_) => bits.write(0b0001_0000 | (order as u64) << 1, 8),
+                Predictor::Lpc { .. } => bits.write(0b0100_0000 | (order as u64 - 1) << 1, 8),
+            }
+            for &warmup in &samples[..order] {
+                bits.write_signed(warmup as i64, bits_per_sample);
+            }
+            if let Predictor::Lpc { coefficients, precision, shift } = &predictor {
+                bits.write(*precision as u64 - 1, 4);
+                bits.write(*shift as u64, 5);
+                for &coefficient in coefficients {
+                    bits.write_signed(coefficient as i64, *precision);
+                }
+            }
+            plan.write(bits, &residual);
+        }
+        None => {
+            bits.write(0b0000_0010, 8);
+            for &sample in samples {
+                bits.write_signed(sample as i64, bits_per_sample);
+            }
+        }
+    }
+}
+
+// 用加窗自相关和Levinson-Durbin递推求各阶LPC系数，再量化为整数
+fn lpc_candidates(samples: &[i32], bits_per_sample: u32) -> Vec<Predictor> {
+    let max_order = MAX_LPC_ORDER.min(samples.len().saturating_sub(1));
+    if max_order == 0 {
+        return Vec::new();
+    }
+
+    // Welch窗
+    let n = samples.len() as f64;
+    let half = (n - 1.0) / 2.0;
+    let windowed: Vec<f64> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let t = (i as f64 - half) / (half + 1.0);
+            x as f64 * (1.0 - t * t)
+        })
+        .collect();
+
+    let autocorrelation: Vec<f64> = (0..=max_order)
+        .map(|lag| windowed[lag..].iter().zip(&windowed).map(|(a, b)| a * b).sum())
+        .collect();
+    if autocorrelation[0] <= 0.0 {
+        return Vec::new();
+    }
+
+    let precision = if bits_per_sample <= 16 { 14 } else { 15 };
+    let mut candidates = Vec::new();
+    let mut lpc = vec![0.0f64; max_order];
+    let mut error = autocorrelation[0];
+
+    for order in 1..=max_order {
+        let mut reflection = -autocorrelation[order];
+        for j in 0..order - 1 {
+            reflection -= lpc[j] * autocorrelation[order - 1 - j];
+        }
+        reflection /= error;
+
+        let previous = lpc.clone();
+        lpc[order - 1] = reflection;
+        for j in 0..order - 1 {
+            lpc[j] = previous[j] + reflection * previous[order - 2 - j];
+        }
+        error *= 1.0 - reflection * reflection;
+
+        // 预测系数为 -lpc
+        if LPC_ORDERS.contains(&order) {
+            let predictor: Vec<f64> = lpc[..order].iter().map(|&a| -a).collect();
+            candidates.extend(quantize_lpc(&predictor, precision));
+        }
+        if error <= 0.0 {
+            break;
+        }
+    }
+
+    candidates
+}
+
+// 按给定精度量化LPC系数（带误差反馈的舍入）
+fn quantize_lpc(coefficients: &[f64], precision: u32) -> Option<Predictor> {
+    let max_abs = coefficients.iter().fold(0.0f64, |m, &c| m.max(c.abs()));
+    if !max_abs.is_finite() || max_abs == 0.0 {
+        return None;
+    }
+
+    let limit = (1i64 << (precision - 1)) - 1;
+    let mut shift = (precision as i32 - 1) - (max_abs.log2().floor() as i32 + 1);
+    shift = shift.clamp(0, 15);
+
+    let scale = (1u64 << shift) as f64;
+    let mut carry = 0.0;
+    let quantized = coefficients
+        .iter()
+        .map(|&c| {
+            let value = c * scale + carry;
+            let rounded = value.round().clamp(-(limit as f64) - 1.0, limit as f64);
+            carry = value - rounded;
+            rounded as i32
+        })
+        .collect();
+
+    Some(Predictor::Lpc {
+        coefficients: quantized,
+        precision,
+        shift: shift as u32,
+    })
+}
+
+// LPC预测残差；残差超出32位有符号范围时放弃该预测器
+fn lpc_residual(samples: &[i32], coefficients: &[i32], shift: u32) -> Option<Vec<i64>> {
+    let order = coefficients.len();
+    let mut residual = Vec::with_capacity(samples.len() - order);
+    for i in order..samples.len() {
+        let prediction: i64 = coefficients
+            .iter()
+            .enumerate()
+            .map(|(j, &c)| c as i64 * samples[i - 1 - j] as i64)
+            .sum();
+        let r = samples[i] as i64 - (prediction >> shift);
+        if r < i32::MIN as i64 || r > i32::MAX as i64 {
+            return None;
+        }
+        residual.push(r);
+    }
+    Some(residual)
+}
+
+// 固定预测残差（从第 order 个样本开始）
+fn fixed_residual(samples: &[i32], order: usize) -> Vec<i64> {
+    let x = |i: usize| samples[i] as i64;
+    (order..samples.len())
+        .map(|i| match order {
+            0 => x(i),
+            1 => x(i) - x(i - 1),
+            2 => x(i) - 2 * x(i - 1) + x(i - 2),
+            3 => x(i) - 3 * x(i - 1) + 3 * x(i - 2) - x(i - 3),
+            _ => x(i) - 4 * x(i - 1) + 6 * x(i - 2) - 4 * x(i - 3) + x(i - 4),
+        })
+        .collect()
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+// 分区Rice编码方案
+struct RicePlan {
+    predictor_order: usize,
+    partition_order: u32,
+    parameters: Vec<u32>,
+    // 参数超过14时使用5位参数（RICE2）
+    wide_parameters: bool,
+    estimated_bits: u64,
+}
+
+impl RicePlan {
+    fn choose(residual: &[i64], predictor_order: usize) -> Self {
+        let block_size = residual.len() + predictor_order;
+
+        // 在最细分区上统计残差和，较粗的分区由相邻分区合并得到
+        let mut finest_order = 0;
+        while finest_order < MAX_PARTITION_ORDER
+            && block_size.is_multiple_of(1 << (finest_order + 1))
+            && (block_size >> (finest_order + 1)) > predictor_order
+        {
+            finest_order += 1;
+        }
+
+        let partitions = 1usize << finest_order;
+        let partition_size = block_size >> finest_order;
+        let mut sums = vec![0u64; partitions];
+        let mut counts = vec![0u64; partitions];
+        for (i, &r) in residual.iter().enumerate() {
+            let partition = (i + predictor_order) / partition_size;
+            sums[partition] += zigzag(r);
+            counts[partition] += 1;
+        }
+
+        let mut best: Option<RicePlan> = None;
+        let mut order = finest_order;
+        loop {
+            let (parameters, data_bits): (Vec<u32>, u64) = sums
+                .iter()
+                .zip(&counts)
+                .map(|(&sum, &count)| best_parameter(sum, count))
+                .fold((Vec::new(), 0), |(mut parameters, total), (k, cost)| {
+                    parameters.push(k);
+                    (parameters, total + cost)
+                });
+            let wide_parameters = parameters.iter().any(|&k| k > 14);
+            let parameter_bits = if wide_parameters { 5 } else { 4 };
+            let estimated_bits = 6 + parameters.len() as u64 * parameter_bits + data_bits;
+
+            if best.as_ref().is_none_or(|b| estimated_bits < b.estimated_bits) {
+                best = Some(RicePlan {
+                    predictor_order,
+                    partition_order: order,
+                    parameters,
+                    wide_parameters,
+                    estimated_bits,
+                });
+            }
+
+            if order == 0 {
+                break;
+            }
+            order -= 1;
+            sums = sums.chunks(2).map(|c| c[0] + c[1]).collect();
+            counts = counts.chunks(2).map(|c| c[0] + c[1]).collect();
+        }
+
+        best.expect("至少有一个分区阶数")
+    }
+
+    fn write(&self, bits: &mut BitWriter, residual: &[i64]) {
+        let (method, parameter_bits) = if self.wide_parameters { (1, 5) } else { (0, 4) };
+        bits.write(method, 2);
+        bits.write(self.partition_order as u64, 4);
+
+        let partition_size = (residual.len() + self.predictor_order) >> self.partition_order;
+        let mut offset = 0;
+        for (partition, &k) in self.parameters.iter().enumerate() {
+            // 首分区不含预热样本
+            let count = if partition == 0 {
+                partition_size - self.predictor_order
+            } else {
+                partition_size
+            };
+            bits.write(k as u64, parameter_bits);
+            for &r in &residual[offset..offset + count] {
+                let u = zigzag(r);
+                bits.write_unary(u >> k);
+                bits.write(u, k);
+            }
+            offset += count;
+        }
+    }
+}
+
+// 根据分区残差和估计最佳Rice参数及比特数
+fn best_parameter(sum: u64, count: u64) -> (u32, u64) {
+    if count == 0 {
+        return (0, 0);
+    }
+    let cost = |k: u32| count * (k as u64 + 1) + (sum >> k);
+    let mean = sum / count;
+    let estimate = if mean == 0 { 0 } else { 63 - mean.leading_zeros() };
+    let k = estimate.min(30);
+    let candidates = [k.saturating_sub(1), k, (k + 1).min(30)];
+    candidates
+        .into_iter()
+        .map(|k| (k, cost(k)))
+        .min_by_key(|&(_, c)| c)
+        .unwrap()
+}
+
+// 类UTF-8变长编码的帧号
+fn write_utf8(bits: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        bits.write(value, 8);
+        return;
+    }
+    let mut length = 2;
+    while value >= 1 << (5 * length + 1) {
+        length += 1;
+    }
+    let prefix = (0xFF00u64 >> length) & 0xFF;
+    bits.write(prefix | (value >> (6 * (length - 1))), 8);
+    for i in (0..length - 1).rev() {
+        bits.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// 高位在前的比特写入器
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    pending: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            accumulator: 0,
+            pending: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.bytes.clear();
+        self.accumulator = 0;
+        self.pending = 0;
+    }
+
+    // 写入 value 的低 count 位（count ≤ 32）
+    fn write(&mut self, value: u64, count: u32) {
+        if count == 0 {
+            return;
+        }
+        self.accumulator = (self.accumulator << count) | (value & ((1u64 << count) - 1));
+        self.pending += count;
+        while self.pending >= 8 {
+            self.pending -= 8;
+            self.bytes.push((self.accumulator >> self.pending) as u8);
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, count: u32) {
+        self.write(value as u64, count);
+    }
+
+    // q个0后跟一个1
+    fn write_unary(&mut self, mut q: u64) {
+        while q >= 32 {
+            self.write(0, 32);
+            q -= 32;
+        }
+        self.write(1, q as u32 + 1);
+    }
+
+    fn align(&mut self) {
+        if self.pending > 0 {
+            self.write(0, 8 - self.pending);
+        }
+    }
+
+    // 已完整写出的字节
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(samples: &[f32], sample_rate: u32, format: PcmFormat, routing: ChannelRouting) -> Vec<u8> {
+        let mut writer = FlacWriter::new(Cursor::new(Vec::new()), sample_rate, format, Dither::None, routing).unwrap();
+        writer.write_samples(samples).unwrap();
+        writer.finish().unwrap();
+        writer.into_inner().unwrap().into_inner()
+    }
+
+    fn sstv_like(len: usize, sample_rate: u32) -> Vec<f32> {
+        // 频率在1500-2300Hz之间跳变的连续相位音调，每个“像素”约0.5ms
+        let pixel = sample_rate as usize / 2000;
+        let mut phase = 0.0f64;
+        (0..len)
+            .map(|i| {
+                let frequency = 1500.0 + 800.0 * ((i / pixel) % 7) as f64 / 6.0;
+                phase += 2.0 * std::f64::consts::PI * frequency / sample_rate as f64;
+                (0.9 * phase.sin()) as f32
+            })
+            .collect()
+    }
+
+    fn decode(bytes: &[u8]) -> (claxon::metadata::StreamInfo, Vec<i32>) {
+        let mut reader = claxon::FlacReader::new(Cursor::new(bytes)).unwrap();
+        let info = reader.streaminfo();
+        let samples = reader.samples().collect::<std::result::Result<Vec<_>, _>>().unwrap();
+        (info, samples)
+    }
+
+    #[test]
+    fn test_flac_round_trip_16bit() {
+        let samples = sstv_like(BLOCK_SIZE * 3 + 123, 48000);
+        let bytes = encode(&samples, 48000, PcmFormat::Int16, ChannelRouting::mono());
+
+        let (info, decoded) = decode(&bytes);
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.samples, Some(samples.len() as u64));
+
+        let expected: Vec<i32> = samples.iter().map(|&s| crate::audio::quantize_i16(s) as i32).collect();
+        assert_eq!(decoded, expected);
+
+        // MD5与原始PCM一致
+        let pcm: Vec<u8> = expected.iter().flat_map(|&s| (s as i16).to_le_bytes()).collect();
+        let digest: [u8; 16] = Md5::digest(&pcm).into();
+        assert_eq!(info.md5sum, digest);
+
+        // 高采样率下SSTV音调应压缩到WAV的一半左右
+        assert!(bytes.len() < pcm.len() * 3 / 5, "{} / {}", bytes.len(), pcm.len());
+    }
+
+    #[test]
+    fn test_flac_round_trip_24bit_stereo() {
+        let samples = sstv_like(5000, 8000);
+        let routing = ChannelRouting::stereo_left(crate::audio::AuxChannel::Silent);
+        let bytes = encode(&samples, 8000, PcmFormat::Int24, routing);
+
+        let (info, decoded) = decode(&bytes);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bits_per_sample, 24);
+
+        let mut quantizer = Quantizer::new(PcmFormat::Int24, Dither::None);
+        for (i, &sample) in samples.iter().enumerate() {
+            assert_eq!(decoded[2 * i], quantizer.quantize(sample));
+            assert_eq!(decoded[2 * i + 1], 0);
+        }
+    }
+
+    #[test]
+    fn test_frame_number_coding() {
+        for (value, expected) in [(0x7Fu64, vec![0x7F]), (0x80, vec![0xC2, 0x80]), (0x10000, vec![0xF0, 0x90, 0x80, 0x80])] {
+            let mut bits = BitWriter::new();
+            write_utf8(&mut bits, value);
+            assert_eq!(bits.into_bytes(), expected);
+        }
+    }
+
+    #[test]
+    fn test_flac_rejects_float() {
+        let result = FlacWriter::new(
+            Cursor::new(Vec::new()),
+            8000,
+            PcmFormat::Float32,
+            Dither::None,
+            ChannelRouting::mono(),
+        );
+        assert!(result.is_err());
+    }
+}
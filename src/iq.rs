@@ -0,0 +1,407 @@
+//! 复基带I/Q输出模块
+//!
+//! 把调制器生成的音频转换为SDR发射机可直接播放的交错复数样本（cf32/cs16/cs8）。
+//! 单边带（USB/LSB）用希尔伯特变换构造解析信号，窄带调频按频偏对音频积分得到相位。
+//! I/Q先在音频采样率下生成，再经带限重采样器转换到I/Q采样率，分段写出。
+
+use crate::audio::utils::Resampler;
+use crate::error::{Result, SstvError};
+use std::io::Write;
+
+/// SSTV音频的最高频率（Hz）
+const MAX_AUDIO_FREQUENCY: f64 = 2500.0;
+/// 希尔伯特滤波器的低频过渡带宽度（Hz），低于SSTV最低音调1100Hz
+const HILBERT_TRANSITION: f64 = 600.0;
+/// 每次重采样并写出的I/Q样本数
+const CHUNK_SIZE: usize = 16384;
+
+/// 复数样本格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IqFormat {
+    /// 32位浮点I/Q（GNU Radio、SDR++等的 `.cf32`/`.raw`）
+    #[default]
+    Cf32,
+    /// 16位有符号整数I/Q
+    Cs16,
+    /// 8位有符号整数I/Q（HackRF的 `hackrf_transfer` 格式）
+    Cs8,
+}
+
+impl IqFormat {
+    /// 格式名称，同时用作文件扩展名
+    pub fn name(&self) -> &'static str {
+        match self {
+            IqFormat::Cf32 => "cf32",
+            IqFormat::Cs16 => "cs16",
+            IqFormat::Cs8 => "cs8",
+        }
+    }
+
+    /// 每个分量（I或Q）的字节数
+    pub fn bytes_per_component(&self) -> usize {
+        match self {
+            IqFormat::Cf32 => 4,
+            IqFormat::Cs16 => 2,
+            IqFormat::Cs8 => 1,
+        }
+    }
+
+    /// 把一个分量编码为小端字节
+    fn encode(&self, value: f32, out: &mut Vec<u8>) {
+        match self {
+            IqFormat::Cf32 => out.extend_from_slice(&value.to_le_bytes()),
+            IqFormat::Cs16 => {
+                let scaled = (value.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                out.extend_from_slice(&scaled.to_le_bytes());
+            }
+            IqFormat::Cs8 => {
+                let scaled = (value.clamp(-1.0, 1.0) * i8::MAX as f32).round() as i8;
+                out.push(scaled as u8);
+            }
+        }
+    }
+}
+
+/// 基带调制方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IqModulation {
+    /// 上边带
+    Usb,
+    /// 下边带
+    Lsb,
+    /// 窄带调频
+    NarrowbandFm {
+        /// 满幅音频对应的频偏（Hz）
+        deviation: f64,
+    },
+}
+
+/// I/Q输出配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IqConfig {
+    /// 调制方式
+    pub modulation: IqModulation,
+    /// I/Q采样率（Hz）
+    pub iq_rate: u32,
+    /// 样本格式
+    pub format: IqFormat,
+    /// 输出幅度（0.0 ~ 1.0），SSB为满幅音频对应的包络，FM为恒定包络
+    pub level: f32,
+}
+
+impl IqConfig {
+    /// 上边带，cf32
+    pub fn usb(iq_rate: u32) -> Self {
+        Self {
+            modulation: IqModulation::Usb,
+            iq_rate,
+            format: IqFormat::default(),
+            level: 0.9,
+        }
+    }
+
+    /// 下边带，cf32
+    pub fn lsb(iq_rate: u32) -> Self {
+        Self {
+            modulation: IqModulation::Lsb,
+            ..Self::usb(iq_rate)
+        }
+    }
+
+    /// 窄带调频，cf32
+    pub fn nbfm(iq_rate: u32, deviation: f64) -> Self {
+        Self {
+            modulation: IqModulation::NarrowbandFm { deviation },
+            ..Self::usb(iq_rate)
+        }
+    }
+
+    /// 设置样本格式
+    pub fn with_format(mut self, format: IqFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// 设置输出幅度
+    pub fn with_level(mut self, level: f32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// 该调制方式所需的最低I/Q采样率
+    pub fn min_iq_rate(&self) -> u32 {
+        match self.modulation {
+            // I、Q分别是带宽2.5kHz的实信号
+            IqModulation::Usb | IqModulation::Lsb => (2.0 * MAX_AUDIO_FREQUENCY) as u32,
+            // 卡森带宽
+            IqModulation::NarrowbandFm { deviation } => (2.0 * (deviation + MAX_AUDIO_FREQUENCY)).ceil() as u32,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let IqModulation::NarrowbandFm { deviation } = self.modulation
+            && (!deviation.is_finite() || deviation <= 0.0)
+        {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "deviation".to_string(),
+                value: deviation.to_string(),
+            });
+        }
+        if self.iq_rate < self.min_iq_rate() {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "iq_rate".to_string(),
+                value: format!("{}（至少需要{}Hz）", self.iq_rate, self.min_iq_rate()),
+            });
+        }
+        if !(0.0..=1.0).contains(&self.level) {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "level".to_string(),
+                value: self.level.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// I/Q调制器
+///
+/// ```rust,no_run
+/// use sstv_rust::{SstvModulator, SstvMode};
+/// use sstv_rust::iq::{IqConfig, IqFormat, IqModulator};
+///
+/// let image = image::open("input.jpg").unwrap();
+/// let mut modulator = SstvModulator::new(SstvMode::MartinM1).with_sample_rate(48000).unwrap();
+/// modulator.modulate_image(&image).unwrap();
+///
+/// // HackRF：2MHz上边带，cs8
+/// let config = IqConfig::usb(2_000_000).with_format(IqFormat::Cs8);
+/// let iq = IqModulator::new(config, 48000).unwrap();
+/// let file = std::fs::File::create("sstv.cs8").unwrap();
+/// iq.write_to(modulator.get_samples(), file).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct IqModulator {
+    config: IqConfig,
+    audio_rate: u32,
+    resampler: Resampler,
+}
+
+impl IqModulator {
+    /// 创建I/Q调制器，`audio_rate` 为输入音频的采样率
+    pub fn new(config: IqConfig, audio_rate: u32) -> Result<Self> {
+        config.validate()?;
+        let resampler = Resampler::new(audio_rate, config.iq_rate)?;
+        Ok(Self {
+            config,
+            audio_rate,
+            resampler,
+        })
+    }
+
+    /// 获取配置
+    pub fn config(&self) -> &IqConfig {
+        &self.config
+    }
+
+    /// 给定音频长度时输出的复数样本数
+    pub fn output_len(&self, audio_len: usize) -> usize {
+        self.resampler.output_len(audio_len)
+    }
+
+    /// 把整段音频转换为 `[I, Q]` 样本
+    pub fn modulate(&self, audio: &[f32]) -> Vec<[f32; 2]> {
+        let mut output = Vec::with_capacity(self.output_len(audio.len()));
+        self.render(audio, |chunk| {
+            output.extend_from_slice(chunk);
+            Ok(())
+        })
+        .expect("写入内存不会失败");
+        output
+    }
+
+    /// 把音频转换为交错I/Q并分段写出，返回复数样本数
+    pub fn write_to<W: Write>(&self, audio: &[f32], writer: W) -> Result<usize> {
+        let mut writer = std::io::BufWriter::new(writer);
+        let format = self.config.format;
+        let mut bytes = Vec::with_capacity(CHUNK_SIZE * 2 * format.bytes_per_component());
+        let count = self.render(audio, |chunk| {
+            bytes.clear();
+            for &[i, q] in chunk {
+                format.encode(i, &mut bytes);
+                format.encode(q, &mut bytes);
+            }
+            writer.write_all(&bytes)?;
+            Ok(())
+        })?;
+        writer.flush()?;
+        Ok(count)
+    }
+
+    // 按块生成I/Q样本并交给 `emit`
+    fn render<F>(&self, audio: &[f32], mut emit: F) -> Result<usize>
+    where
+        F: FnMut(&[[f32; 2]]) -> Result<()>,
+    {
+        let total = self.output_len(audio.len());
+        let level = self.config.level;
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+
+        match self.config.modulation {
+            IqModulation::Usb | IqModulation::Lsb => {
+                // 解析信号 x + jH{x} 只含正频率（上边带），取共轭得到下边带
+                let mut quadrature = hilbert_transform(audio, self.audio_rate);
+                if self.config.modulation == IqModulation::Lsb {
+                    quadrature.iter_mut().for_each(|q| *q = -*q);
+                }
+
+                let (mut i_buffer, mut q_buffer) = (Vec::new(), Vec::new());
+                for start in (0..total).step_by(CHUNK_SIZE) {
+                    let range = start..(start + CHUNK_SIZE).min(total);
+                    i_buffer.clear();
+                    q_buffer.clear();
+                    self.resampler.process_range(audio, range.clone(), &mut i_buffer);
+                    self.resampler.process_range(&quadrature, range, &mut q_buffer);
+
+                    chunk.clear();
+                    chunk.extend(i_buffer.iter().zip(&q_buffer).map(|(&i, &q)| [i * level, q * level]));
+                    emit(&chunk)?;
+                }
+            }
+            IqModulation::NarrowbandFm { deviation } => {
+                let step = 2.0 * std::f64::consts::PI * deviation / self.config.iq_rate as f64;
+                let mut phase = 0.0f64;
+                let mut buffer = Vec::new();
+                for start in (0..total).step_by(CHUNK_SIZE) {
+                    buffer.clear();
+                    self.resampler.process_range(audio, start..(start + CHUNK_SIZE).min(total), &mut buffer);
+
+                    chunk.clear();
+                    for &sample in &buffer {
+                        chunk.push([level * phase.cos() as f32, level * phase.sin() as f32]);
+                        phase = (phase + step * sample.clamp(-1.0, 1.0) as f64) % (2.0 * std::f64::consts::PI);
+                    }
+                    emit(&chunk)?;
+                }
+            }
+        }
+
+        Ok(total)
+    }
+}
+
+// 零相位的加窗FIR希尔伯特变换
+fn hilbert_transform(input: &[f32], sample_rate: u32) -> Vec<f32> {
+    // Blackman窗的过渡带约为 5.5·fs/N，跨越 ±HILBERT_TRANSITION
+    let half = (5.5 * sample_rate as f64 / (4.0 * HILBERT_TRANSITION)).ceil() as usize;
+    let kernel: Vec<(usize, f64)> = (1..=half)
+        .step_by(2)
+        .map(|k| {
+            let x = k as f64 / (half + 1) as f64;
+            let window = 0.42 + 0.5 * (std::f64::consts::PI * x).cos() + 0.08 * (2.0 * std::f64::consts::PI * x).cos();
+            (k, 2.0 / (std::f64::consts::PI * k as f64) * window)
+        })
+        .collect();
+
+    // 奇对称核：y[n] = Σ h[k]·(x[n-k] - x[n+k])
+    (0..input.len())
+        .map(|n| {
+            kernel
+                .iter()
+                .map(|&(k, h)| {
+                    let before = if n >= k { input[n - k] as f64 } else { 0.0 };
+                    let after = input.get(n + k).copied().unwrap_or(0.0) as f64;
+                    h * (before - after)
+                })
+                .sum::<f64>() as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequency: f64, sample_rate: u32, seconds: f64) -> Vec<f32> {
+        (0..(sample_rate as f64 * seconds) as usize)
+            .map(|i| (2.0 * std::f64::consts::PI * frequency * i as f64 / sample_rate as f64).sin() as f32)
+            .collect()
+    }
+
+    // 复数信号在频率 f 处的DFT幅度（归一化）
+    fn spectrum(iq: &[[f32; 2]], sample_rate: u32, frequency: f64) -> f64 {
+        let (mut re, mut im) = (0.0f64, 0.0f64);
+        for (n, &[i, q]) in iq.iter().enumerate() {
+            let angle = -2.0 * std::f64::consts::PI * frequency * n as f64 / sample_rate as f64;
+            let (sin, cos) = angle.sin_cos();
+            re += i as f64 * cos - q as f64 * sin;
+            im += i as f64 * sin + q as f64 * cos;
+        }
+        (re * re + im * im).sqrt() / iq.len() as f64
+    }
+
+    #[test]
+    fn test_ssb_sideband_suppression() {
+        for &frequency in &[1200.0, 1500.0, 1900.0, 2300.0] {
+            let audio = tone(frequency, 8000, 0.5);
+            let usb = IqModulator::new(IqConfig::usb(16000), 8000).unwrap().modulate(&audio);
+            let body = &usb[1000..usb.len() - 1000];
+
+            let wanted = spectrum(body, 16000, frequency);
+            let image = spectrum(body, 16000, -frequency);
+            assert!((wanted - 0.9).abs() < 0.02, "{}Hz 幅度 {}", frequency, wanted);
+            assert!(20.0 * (image / wanted).log10() < -40.0, "{}Hz 镜像抑制不足", frequency);
+
+            let lsb = IqModulator::new(IqConfig::lsb(16000), 8000).unwrap().modulate(&audio);
+            let body = &lsb[1000..lsb.len() - 1000];
+            assert!(spectrum(body, 16000, -frequency) > 100.0 * spectrum(body, 16000, frequency));
+        }
+    }
+
+    #[test]
+    fn test_nbfm_deviation() {
+        // 恒定输入0.5、频偏2kHz，应得到+1kHz的载波偏移
+        let audio = vec![0.5f32; 4800];
+        let iq = IqModulator::new(IqConfig::nbfm(48000, 2000.0), 48000).unwrap().modulate(&audio);
+
+        let body = &iq[1000..4000];
+        let mut total = 0.0;
+        for pair in body.windows(2) {
+            let [i0, q0] = pair[0];
+            let [i1, q1] = pair[1];
+            // 相邻样本的相位差
+            let re = i1 as f64 * i0 as f64 + q1 as f64 * q0 as f64;
+            let im = q1 as f64 * i0 as f64 - i1 as f64 * q0 as f64;
+            total += im.atan2(re);
+        }
+        let frequency = total / (body.len() - 1) as f64 * 48000.0 / (2.0 * std::f64::consts::PI);
+        assert!((frequency - 1000.0).abs() < 1.0, "瞬时频率 {}", frequency);
+
+        // 恒定包络
+        assert!(body.iter().all(|&[i, q]| ((i * i + q * q).sqrt() - 0.9).abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_iq_formats() {
+        let audio = tone(1500.0, 8000, 0.1);
+        for format in [IqFormat::Cf32, IqFormat::Cs16, IqFormat::Cs8] {
+            let modulator = IqModulator::new(IqConfig::usb(24000).with_format(format), 8000).unwrap();
+            let mut bytes = Vec::new();
+            let count = modulator.write_to(&audio, &mut bytes).unwrap();
+            assert_eq!(count, 2400);
+            assert_eq!(bytes.len(), count * 2 * format.bytes_per_component());
+        }
+
+        let mut bytes = Vec::new();
+        IqFormat::Cs8.encode(-1.5, &mut bytes);
+        IqFormat::Cs16.encode(0.5, &mut bytes);
+        assert_eq!(bytes, vec![0x81, 0x00, 0x40]);
+    }
+
+    #[test]
+    fn test_iq_validation() {
+        assert!(IqModulator::new(IqConfig::usb(4000), 8000).is_err());
+        assert!(IqModulator::new(IqConfig::nbfm(8000, 5000.0), 8000).is_err());
+        assert!(IqModulator::new(IqConfig::nbfm(48000, 5000.0), 8000).is_ok());
+        assert!(IqModulator::new(IqConfig::usb(48000).with_level(1.5), 8000).is_err());
+    }
+}
@@ -8,15 +8,32 @@
 //! ## 特性
 //!
 //! - 支持标准SSTV模式（Scottie-DX, Robot-36, PD-120, Martin-M1）
-//! - 相位连续性优化算法
+//! - 相位累加器数控振荡器（NCO），音调边界相位严格连续，可选查表正弦
+//! - 生成时的音调频率偏移和频谱镜像（补偿电台失谐和LSB反转）
+//! - 可选的首尾包络渐变和像素间频率平滑，减少咔嗒声和带外辐射
 //! - 采样精度补偿技术
+//! - 多线程并行生成扫描块，输出与串行逐样本一致
+//! - 逐扫描块的进度回调和取消令牌
+//! - 可序列化的编码器配置（幅度、首尾静音、VIS码和结束音开关、预处理、输出格式），可保存为电台预设
+//! - 传输时间轴（VIS各位、同步脉冲、颜色扫描的样本位置），可导出JSON和WAV标记
 //! - 高性能音频生成和处理
 //! - 完整的错误处理和类型安全
-//! - WAV文件导出功能
+//! - 可选的图像适配策略（黑边/模糊背景留白、裁剪铺满、拉伸）
+//! - 可选缩放滤波器、EXIF自动旋转、ICC配置文件转换到sRGB和色彩调整
+//! - WAV文件导出功能（16/24/32位整数或32位浮点，可选TPDF抖动），可内嵌模式、呼号、时间和源图像哈希
+//! - FLAC无损压缩（默认 `flac` 特性）和无文件头原始PCM导出
+//! - SDR复基带I/Q输出（USB/LSB/窄带FM，cf32/cs16/cs8）
+//! - 多声道输出和声道路由（镜像、静音或VOX/PTT导频）
+//! - `sstv-encode` 命令行工具（默认 `cli` 特性）
+//! - 批量编码：多张图像 × 模式 × 采样率 × 格式并行处理，单个任务失败互不影响
+//! - SSTV解码：VIS检测、频率偏移和倾斜（时钟误差）校正、信噪比估计，流式处理长录音，从长录音中分割出各次传输；
+//!   `sstv-decode` 命令行工具
+//! - 传输时序检查：VIS码、同步脉冲频率和时长、扫描块周期漂移和总长度，按容差报告偏差
+//! - 流式输出到任意 `io::Write`（WAV、原始PCM）或内存，恒定内存占用
 //!
 //! ## 使用示例
 //!
-//! ```rust
+//! ```rust,no_run
 //! use sstv_rust::{SstvModulator, SstvMode, WavWriter};
 //! use image::open;
 //!
@@ -35,7 +52,7 @@
 //!
 //! ## 便捷函数
 //!
-//! ```rust
+//! ```rust,no_run
 //! use sstv_rust::{generate_sstv_from_file, SstvMode};
 //!
 //! // 一行代码生成SSTV音频
@@ -45,11 +62,35 @@
 pub mod sstv;
 pub mod audio;
 pub mod error;
+pub mod preprocess;
+#[cfg(feature = "flac")]
+pub mod flac;
+pub mod iq;
+pub mod nco;
+pub mod batch;
+pub mod decode;
+pub mod progress;
+pub mod timeline;
+pub mod metadata;
+pub mod config;
+pub mod lint;
 
 // 重新导出主要类型
 pub use error::{SstvError, Result};
-pub use sstv::{SstvMode, SstvModulator, ImageSaveConfig, ProcessingMetadata, MemoryUsage, MemoryUsageMB};
-pub use audio::{AudioGenerator, WavWriter, effects};
+pub use sstv::{SstvMode, SstvModulator, SampleStream, TimingJitter, FrequencyMapping, ToneShaping, ImageSaveConfig, ProcessingMetadata, MemoryUsage, MemoryUsageMB};
+pub use audio::{AudioGenerator, WavWriter, SampleSink, RawPcmSink, WavStreamSink, AudioReader, AudioFormat, PcmFormat, Dither, Quantizer, ChannelRouting, AuxChannel, effects};
+#[cfg(feature = "flac")]
+pub use flac::FlacWriter;
+pub use iq::{IqConfig, IqFormat, IqModulation, IqModulator};
+pub use nco::{Nco, SineMode};
+pub use batch::{BatchEncoder, BatchJob, BatchOutput, BatchFailure, BatchReport};
+pub use decode::{SstvDecoder, DecodedImage, Segment, decode_samples, find_segments};
+pub use progress::{Progress, CancellationToken};
+pub use timeline::{Timeline, TimelineSegment, SegmentKind, ColorChannel};
+pub use metadata::{WavMetadata, image_sha256, read_wav_info};
+pub use config::EncoderConfig;
+pub use lint::{TransmissionLinter, LintReport, LintCheck, LintTolerances, lint_wav};
+pub use preprocess::{FitMode, BarFill, CropAnchor, ResizeFilter, ColorAdjustment, PreprocessOptions, TextOverlay, OverlayPosition, load_image};
 
 /// 库版本信息
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -57,18 +98,29 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// 默认采样率 - 优化为6kHz以减少文件大小（基于奈奎斯特定理，SSTV最大频率2.5kHz）
 pub const DEFAULT_SAMPLE_RATE: u32 = 6000;
 
+/// 调制器、解码器和音频生成器共用的最低采样率
+///
+/// 最高音调2300Hz在 `fs - 2300` 处有镜像，6000Hz时镜像位于3700Hz，与SSTV频带之间留出1400Hz过渡带，
+/// 接收端的滤波器（包括本库解码器在±250Hz频偏内）才能把它滤除；仅高于奈奎斯特频率的采样率（如4601Hz）
+/// 会让镜像紧贴2300Hz。各模式另有最短像素的要求（Robot36须7273Hz），见 [`SstvMode::get_min_sample_rate`]。
+pub const MIN_SAMPLE_RATE: u32 = 6000;
+
+/// 调制器支持的最高采样率
+pub const MAX_SAMPLE_RATE: u32 = 192000;
+
 /// 便捷函数：从图像文件直接生成SSTV音频
 ///
 /// # 参数
 /// * `image_path` - 输入图像文件路径
-/// * `output_path` - 输出WAV文件路径
+/// * `output_path` - 输出音频文件路径，按扩展名选择格式（`.flac`、`.raw`/`.pcm`，其他为WAV）
 /// * `mode` - SSTV模式
 ///
 /// # 示例
-/// ```rust
+/// ```rust,no_run
 /// use sstv_rust::{generate_sstv_from_file, SstvMode};
 ///
 /// generate_sstv_from_file("input.jpg", "output.wav", SstvMode::Robot36).unwrap();
+/// generate_sstv_from_file("input.jpg", "archive.flac", SstvMode::Robot36).unwrap();
 /// ```
 pub fn generate_sstv_from_file<P1, P2>(
     image_path: P1,
@@ -79,29 +131,21 @@ where
     P1: AsRef<std::path::Path>,
     P2: AsRef<std::path::Path>,
 {
-    // 加载图像
-    let image = image::open(image_path)
-        .map_err(|e| SstvError::ImageProcessing(format!("无法加载图像: {}", e)))?;
+    // 加载图像（按EXIF方向自动旋转）
+    let image = preprocess::load_image(image_path)?;
 
-    // 创建调制器并调制
-    let mut modulator = SstvModulator::new(mode);
-    modulator.modulate_image(&image)?;
-
-    // 导出WAV文件
-    modulator.export_wav(output_path)?;
-
-    Ok(())
+    generate_sstv_from_image(&image, output_path, mode)
 }
 
 /// 便捷函数：从内存中的图像数据生成SSTV音频
 ///
 /// # 参数
 /// * `image` - 图像数据
-/// * `output_path` - 输出WAV文件路径
+/// * `output_path` - 输出音频文件路径，按扩展名选择格式（`.flac`、`.raw`/`.pcm`，其他为WAV）
 /// * `mode` - SSTV模式
 ///
 /// # 示例
-/// ```rust
+/// ```rust,no_run
 /// use sstv_rust::{generate_sstv_from_image, SstvMode};
 /// use image::DynamicImage;
 ///
@@ -120,8 +164,9 @@ where
     let mut modulator = SstvModulator::new(mode);
     modulator.modulate_image(image)?;
 
-    // 导出WAV文件
-    modulator.export_wav(output_path)?;
+    // 按扩展名导出，无法识别时使用WAV
+    let format = AudioFormat::from_path(&output_path).unwrap_or_default();
+    modulator.export_audio(output_path, format)?;
 
     Ok(())
 }
@@ -168,7 +213,7 @@ pub fn estimate_file_size(mode: SstvMode, sample_rate: u32, bit_depth: u16) -> u
 /// 返回 (音频文件路径, 图片文件路径)
 ///
 /// # 示例
-/// ```rust
+/// ```rust,no_run
 /// use sstv_rust::{generate_sstv_with_image_save, SstvMode, ImageSaveConfig};
 ///
 /// let (audio_path, image_path) = generate_sstv_with_image_save(
@@ -191,9 +236,8 @@ where
     P2: AsRef<std::path::Path>,
     P3: AsRef<str>,
 {
-    // 加载图像
-    let image = image::open(image_path)
-        .map_err(|e| SstvError::ImageProcessing(format!("无法加载图像: {}", e)))?;
+    // 加载图像（按EXIF方向自动旋转）
+    let image = preprocess::load_image(image_path)?;
 
     // 创建调制器并批处理
     let mut modulator = SstvModulator::new(mode);
@@ -225,13 +269,11 @@ pub fn estimate_memory_usage(
     // 目标图像内存：目标分辨率 * 3字节/像素
     let target_image_memory = (target_width * target_height * 3) as usize;
     
-    // 音频内存：采样率 * 时长 * 2字节/样本
-    let audio_memory = (sample_rate as f64 * duration * 2.0) as usize;
+    // 音频内存：采样率 * 时长 * 4字节/样本（内部以f32存储）
+    let audio_memory = (sample_rate as f64 * duration * 4.0) as usize;
     
     // 处理过程中的峰值内存使用（原图+目标图+音频+开销）
-    let peak_memory = source_image_memory + target_image_memory + audio_memory + 1024;
-    
-    peak_memory
+    source_image_memory + target_image_memory + audio_memory + 1024
 }
 
 /// 检查系统是否有足够内存处理指定的SSTV任务
@@ -295,16 +337,15 @@ where
     P2: AsRef<std::path::Path>,
     P3: AsRef<str>,
 {
-    // 加载图像并检查内存需求
-    let image = image::open(&input_path)
-        .map_err(|e| SstvError::ImageProcessing(format!("无法加载图像: {}", e)))?;
+    // 加载图像（按EXIF方向自动旋转）并检查内存需求
+    let image = preprocess::load_image(&input_path)?;
     
     let (width, height) = (image.width(), image.height());
     
     // 内存检查
     if let Some(limit) = memory_limit_mb {
         let (has_enough, required_mb, _suggested_size) = check_memory_requirements(
-            width, height, mode, DEFAULT_SAMPLE_RATE
+            width, height, mode, mode.get_default_sample_rate()
         );
         
         if !has_enough && required_mb > limit as f64 {
@@ -335,6 +376,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use image::ImageFormat;
 
     #[test]
     fn test_version() {
@@ -366,7 +408,7 @@ mod tests {
         // Robot-36模式约36秒，44.1kHz采样率，16位
         let expected_samples = 36.0 * 44100.0;
         let expected_size = (expected_samples * 2.0) as usize + 44;
-        assert!((size as f32 - expected_size as f32).abs() / expected_size as f32 < 0.1);
+        assert!((size as f32 - expected_size as f32).abs() / (expected_size as f32) < 0.1);
     }
 
     #[test]
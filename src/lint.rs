@@ -0,0 +1,555 @@
+//! 传输时序检查模块
+//!
+//! 发射前检查编码结果是否符合模式的时序规范：解调WAV或样本后，测量VIS码各段的频率和跳变沿、
+//! 每个扫描块同步脉冲的频率、时长和位置、扫描块周期的漂移以及整个传输的长度，与标称值比较并按容差
+//! 给出每一项的结果。测量使用与解码器相同的混频、低通和瞬时频率估计；文件中有多次传输时只检查第一次。
+
+use crate::audio::AudioReader;
+use crate::decode::{
+    Demodulator, ModeTiming, SYNC_SEARCH_MS, SYNC_THRESHOLD, Track, VIS_BIT_MS, VIS_HEADER_MS, VIS_LOOKBACK_MS, fit_line,
+};
+use crate::error::{Result, SstvError};
+use crate::sstv::SstvMode;
+use serde_json::{Value, json};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// 定位VIS码时允许的频率偏差（Hz），比检查容差宽，偏频的录音也能定位并报告偏差
+const SEARCH_TOLERANCE: f64 = 150.0;
+/// 测量跳变沿时平滑窗口的半宽（ms）
+const EDGE_SMOOTHING_MS: f64 = 0.25;
+/// 测量音调频率时两端各去掉的时长（ms），避开滤波器造成的过渡
+const TONE_TRIM_MS: f64 = 2.0;
+/// 在标称位置附近搜索VIS码跳变沿和同步脉冲上升沿的范围（ms）
+const EDGE_SEARCH_MS: f64 = 8.0;
+/// 读取WAV时每次读取的帧数
+const READ_CHUNK: usize = 65536;
+
+/// 检查容差
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LintTolerances {
+    /// 音调频率（Hz）
+    pub frequency_hz: f64,
+    /// 跳变沿位置和同步脉冲时长（ms）
+    pub timing_ms: f64,
+    /// 扫描块周期相对标称值的漂移（ppm）
+    pub drift_ppm: f64,
+    /// 传输总长度（ms）
+    pub length_ms: f64,
+}
+
+impl Default for LintTolerances {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 20.0,
+            timing_ms: 0.5,
+            drift_ppm: 20.0,
+            length_ms: 2.0,
+        }
+    }
+}
+
+/// 单项检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintCheck {
+    /// 检查项名称，如 `vis.bit3.frequency`、`sync.duration`
+    pub name: String,
+    /// 标称值
+    pub expected: f64,
+    /// 测量值，未能测到时为 `None`
+    pub measured: Option<f64>,
+    /// 允许的偏差
+    pub tolerance: f64,
+    /// 单位（`Hz`、`ms`、`ppm`，计数为空）
+    pub unit: &'static str,
+    /// 汇总多个扫描块的检查项中偏差最大的扫描块
+    pub line: Option<u32>,
+}
+
+impl LintCheck {
+    fn new(name: impl Into<String>, expected: f64, measured: Option<f64>, tolerance: f64, unit: &'static str) -> Self {
+        Self {
+            name: name.into(),
+            expected,
+            measured,
+            tolerance,
+            unit,
+            line: None,
+        }
+    }
+
+    /// 测量值与标称值之差
+    pub fn deviation(&self) -> Option<f64> {
+        self.measured.map(|measured| measured - self.expected)
+    }
+
+    /// 测量到且偏差不超过容差
+    pub fn passed(&self) -> bool {
+        self.deviation().is_some_and(|deviation| deviation.abs() <= self.tolerance)
+    }
+
+    /// 转换为JSON
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "expected": self.expected,
+            "measured": self.measured,
+            "deviation": self.deviation(),
+            "tolerance": self.tolerance,
+            "unit": self.unit,
+            "line": self.line,
+            "passed": self.passed(),
+        })
+    }
+}
+
+impl fmt::Display for LintCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed() { "通过" } else { "超差" };
+        write!(f, "[{}] {}: ", verdict, self.name)?;
+        match self.measured {
+            Some(measured) => write!(f, "{:.3}{}", measured, self.unit)?,
+            None => write!(f, "未测到")?,
+        }
+        write!(f, "（标称 {:.3}{}，容差 ±{}{}）", self.expected, self.unit, self.tolerance, self.unit)?;
+        if let Some(line) = self.line {
+            write!(f, "，扫描块 {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// 检查报告
+#[derive(Debug, Clone)]
+pub struct LintReport {
+    /// 期望的模式
+    pub mode: SstvMode,
+    /// 输入采样率
+    pub sample_rate: u32,
+    /// VIS码（第一段引导音）起点的样本序号，未找到VIS码时为 `None`
+    pub vis_start: Option<usize>,
+    /// 各项检查结果
+    pub checks: Vec<LintCheck>,
+}
+
+impl LintReport {
+    /// 全部检查项都通过
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(LintCheck::passed)
+    }
+
+    /// 未通过的检查项
+    pub fn failures(&self) -> impl Iterator<Item = &LintCheck> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+
+    /// 按名称查找检查项
+    pub fn check(&self, name: &str) -> Option<&LintCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// 转换为JSON
+    pub fn to_json(&self) -> Value {
+        json!({
+            "mode": self.mode.to_string(),
+            "sample_rate": self.sample_rate,
+            "vis_start_sample": self.vis_start,
+            "passed": self.passed(),
+            "checks": self.checks.iter().map(LintCheck::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    /// 保存为JSON文件
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = serde_json::to_string_pretty(&self.to_json()).map_err(|e| SstvError::InvalidFormat(e.to_string()))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// 传输时序检查器
+///
+/// ```rust
+/// use sstv_rust::{SstvModulator, SstvMode, TransmissionLinter};
+/// use image::{DynamicImage, RgbImage};
+///
+/// let mut modulator = SstvModulator::new(SstvMode::Robot36).with_sample_rate(8000).unwrap();
+/// modulator.modulate_image(&DynamicImage::ImageRgb8(RgbImage::new(320, 240))).unwrap();
+///
+/// let report = TransmissionLinter::new(SstvMode::Robot36).lint_samples(modulator.get_samples(), 8000).unwrap();
+/// for failure in report.failures() {
+///     println!("{}", failure);
+/// }
+/// assert!(report.passed());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TransmissionLinter {
+    mode: SstvMode,
+    tolerances: LintTolerances,
+}
+
+impl TransmissionLinter {
+    /// 以默认容差创建指定模式的检查器
+    pub fn new(mode: SstvMode) -> Self {
+        Self {
+            mode,
+            tolerances: LintTolerances::default(),
+        }
+    }
+
+    /// 设置容差
+    pub fn with_tolerances(mut self, tolerances: LintTolerances) -> Self {
+        self.tolerances = tolerances;
+        self
+    }
+
+    /// 获取容差
+    pub fn get_tolerances(&self) -> LintTolerances {
+        self.tolerances
+    }
+
+    /// 检查单声道样本
+    ///
+    /// 采样率超出本模式的范围时返回 `InvalidSampleRate`；没有找到VIS码时报告中只有未通过的
+    /// `vis.detected` 一项。
+    pub fn lint_samples(&self, samples: &[f32], sample_rate: u32) -> Result<LintReport> {
+        self.mode.validate_sample_rate(sample_rate)?;
+        let mut demodulator = Demodulator::new(sample_rate);
+        let decimation = demodulator.decimation;
+        let mut baseband = Vec::with_capacity(samples.len() / decimation + 1);
+        demodulator.process(samples, &mut baseband);
+        demodulator.flush(&mut baseband);
+        let mut track = Track::new(sample_rate as f64 / decimation as f64);
+        track.extend(&baseband);
+
+        let measurer = Measurer {
+            track,
+            ms: sample_rate as f64 / decimation as f64 / 1000.0,
+        };
+        let mut report = LintReport {
+            mode: self.mode,
+            sample_rate,
+            vis_start: None,
+            checks: Vec::new(),
+        };
+        let Some(start_bit) = measurer.find_start_bit() else {
+            report.checks.push(LintCheck::new("vis.detected", 1.0, None, 0.0, ""));
+            return Ok(report);
+        };
+        let vis_start = start_bit - VIS_HEADER_MS * measurer.ms;
+        report.vis_start = Some((vis_start.max(0.0) * decimation as f64).round() as usize);
+        self.check_vis(&measurer, start_bit, &mut report.checks);
+        self.check_lines(&measurer, start_bit, &mut report.checks);
+        Ok(report)
+    }
+
+    /// 检查WAV文件的第 `channel` 声道（从0开始，与 `sstv-decode --channel` 相同）
+    pub fn lint_wav<P: AsRef<Path>>(&self, path: P, channel: u16) -> Result<LintReport> {
+        let mut reader = AudioReader::wav(BufReader::new(File::open(path)?))?;
+        let mut samples = Vec::new();
+        while reader.read_channel(channel, READ_CHUNK, &mut samples)? > 0 {}
+        self.lint_samples(&samples, reader.sample_rate())
+    }
+
+    /// VIS码各段的频率、跳变沿和码值
+    fn check_vis(&self, measurer: &Measurer, start_bit: f64, checks: &mut Vec<LintCheck>) {
+        let tolerances = &self.tolerances;
+        let code = u8::from_str_radix(self.mode.get_vis_code(), 2).unwrap_or(0);
+        let bit_frequency = |bit: bool| if bit { 1100.0 } else { 1300.0 };
+
+        // （名称，相对起始位的起点ms，时长ms，频率Hz）
+        let mut sections = vec![
+            ("vis.leader1".to_string(), -VIS_HEADER_MS, 300.0, 1900.0),
+            ("vis.break".to_string(), -310.0, 10.0, 1200.0),
+            ("vis.leader2".to_string(), -300.0, 300.0, 1900.0),
+            ("vis.start_bit".to_string(), 0.0, VIS_BIT_MS, 1200.0),
+        ];
+        for k in 0..7 {
+            let start = VIS_BIT_MS * (k + 1) as f64;
+            sections.push((format!("vis.bit{}", k), start, VIS_BIT_MS, bit_frequency((code >> k) & 1 == 1)));
+        }
+        let parity = code.count_ones() % 2 == 1;
+        sections.push(("vis.parity".to_string(), 8.0 * VIS_BIT_MS, VIS_BIT_MS, bit_frequency(parity)));
+        sections.push(("vis.stop_bit".to_string(), 9.0 * VIS_BIT_MS, VIS_BIT_MS, 1200.0));
+
+        let mut measured_code = Some(0u8);
+        for (index, (name, start, length, frequency)) in sections.iter().enumerate() {
+            let measured = measurer.tone(start_bit + start * measurer.ms, *length);
+            if let Some(bit) = index.checked_sub(4).filter(|&bit| bit < 7) {
+                // 按更接近1100Hz还是1300Hz判定数据位
+                measured_code = match (measured_code, measured) {
+                    (Some(code), Some(value)) => Some(code | (((value < 1200.0) as u8) << bit)),
+                    _ => None,
+                };
+            }
+            checks.push(LintCheck::new(format!("{}.frequency", name), *frequency, measured, tolerances.frequency_hz, "Hz"));
+        }
+        checks.push(LintCheck::new("vis.code", code as f64, measured_code.map(f64::from), 0.0, ""));
+
+        // 相邻两段频率不同处的跳变沿，位置相对VIS码起点；起始位的下降沿是基准，不参与比较
+        let mut edges = Vec::new();
+        for pair in sections.windows(2) {
+            let (from, to) = (pair[0].3, pair[1].3);
+            if from == to || pair[1].1 == 0.0 {
+                continue;
+            }
+            let nominal = start_bit + pair[1].1 * measurer.ms;
+            let crossing = measurer.crossing(nominal, EDGE_SEARCH_MS, (from + to) / 2.0, to < from);
+            edges.push((pair[1].1, crossing.map(|position| (position - start_bit) / measurer.ms)));
+        }
+        checks.push(worst(
+            edges.iter().map(|&(nominal, measured)| {
+                LintCheck::new("vis.edge", nominal + VIS_HEADER_MS, measured.map(|m| m + VIS_HEADER_MS), tolerances.timing_ms, "ms")
+            }),
+            "vis.edge",
+        ));
+        let break_duration = match (edges.first(), edges.get(1)) {
+            (Some(&(_, Some(falling))), Some(&(_, Some(rising)))) => Some(rising - falling),
+            _ => None,
+        };
+        checks.push(LintCheck::new("vis.break.duration", 10.0, break_duration, tolerances.timing_ms, "ms"));
+    }
+
+    /// 各扫描块的同步脉冲、周期漂移和总长度
+    fn check_lines(&self, measurer: &Measurer, start_bit: f64, checks: &mut Vec<LintCheck>) {
+        let tolerances = &self.tolerances;
+        let timing = ModeTiming::of(self.mode);
+        let ms = measurer.ms;
+        let lines = self.mode.get_scan_line_count();
+        let vis_start = start_bit - VIS_HEADER_MS * ms;
+        let image_start = start_bit + 10.0 * VIS_BIT_MS * ms;
+
+        let mut frequencies = Vec::new();
+        let mut durations = Vec::new();
+        let mut positions = Vec::new();
+        let mut points = Vec::new();
+        for line in 0..lines {
+            let nominal = image_start + (timing.lead_in + timing.sync_start + line as f64 * timing.period) * ms;
+            let rising_search = EDGE_SEARCH_MS.min(timing.sync_length);
+            let check = |name: &str, expected: f64, measured: Option<f64>, tolerance: f64, unit: &'static str| LintCheck {
+                line: Some(line),
+                ..LintCheck::new(name, expected, measured, tolerance, unit)
+            };
+            let falling = match measurer.crossing(nominal, SYNC_SEARCH_MS, SYNC_THRESHOLD, true) {
+                Some(falling) => {
+                    let rising = measurer.crossing(falling + timing.sync_length * ms, rising_search, SYNC_THRESHOLD, false);
+                    durations.push(check("sync.duration", timing.sync_length, rising.map(|r| (r - falling) / ms), tolerances.timing_ms, "ms"));
+                    falling
+                }
+                // 紧接VIS停止位（同为1200Hz）的第一个同步脉冲没有下降沿，由上升沿反推起点
+                None if line == 0 && timing.lead_in + timing.sync_start == 0.0 => {
+                    let Some(rising) = measurer.crossing(nominal + timing.sync_length * ms, rising_search, SYNC_THRESHOLD, false) else {
+                        continue;
+                    };
+                    rising - timing.sync_length * ms
+                }
+                None => continue,
+            };
+            points.push((line as f64, falling));
+            frequencies.push(check("sync.frequency", 1200.0, measurer.tone(falling, timing.sync_length), tolerances.frequency_hz, "Hz"));
+            positions.push(check(
+                "line.position",
+                (nominal - vis_start) / ms,
+                Some((falling - vis_start) / ms),
+                tolerances.timing_ms,
+                "ms",
+            ));
+        }
+        checks.push(LintCheck::new("sync.count", lines as f64, Some(points.len() as f64), 0.0, ""));
+        checks.push(worst(frequencies, "sync.frequency"));
+        checks.push(worst(durations, "sync.duration"));
+        checks.push(worst(positions, "line.position"));
+
+        // 用同步脉冲位置拟合扫描块周期；总长度取拟合的最后一个扫描块终点，录音提前结束时取录音终点
+        let fit = fit_line(&points, timing.sync_length * ms);
+        let drift = fit.map(|(_, slope)| (slope / (timing.period * ms) - 1.0) * 1e6);
+        checks.push(LintCheck::new("line.drift", 0.0, drift, tolerances.drift_ppm, "ppm"));
+        let length = fit.map(|(intercept, slope)| {
+            let end = intercept + slope * lines as f64 - timing.sync_start * slope / timing.period;
+            (end.min(measurer.track.end() as f64) - vis_start) / ms
+        });
+        let expected = VIS_HEADER_MS + 10.0 * VIS_BIT_MS + timing.transmission_ms(self.mode);
+        checks.push(LintCheck::new("transmission.length", expected, length, tolerances.length_ms, "ms"));
+    }
+}
+
+/// 以默认容差检查WAV文件的第 `channel` 声道
+pub fn lint_wav<P: AsRef<Path>>(path: P, mode: SstvMode, channel: u16) -> Result<LintReport> {
+    TransmissionLinter::new(mode).lint_wav(path, channel)
+}
+
+/// 汇总多个扫描块或跳变沿：取未测到或偏差最大的一项；没有任何测量时记为未测到
+fn worst(checks: impl IntoIterator<Item = LintCheck>, name: &str) -> LintCheck {
+    checks
+        .into_iter()
+        .max_by(|a, b| {
+            let severity = |check: &LintCheck| check.deviation().map_or(f64::INFINITY, f64::abs);
+            severity(a).total_cmp(&severity(b))
+        })
+        .unwrap_or_else(|| LintCheck::new(name, 0.0, None, 0.0, ""))
+}
+
+/// 工作采样率下的频率测量
+struct Measurer {
+    track: Track,
+    // 每毫秒的工作采样率样本数
+    ms: f64,
+}
+
+impl Measurer {
+    /// 自 `start` 起 `length_ms` 内的音调频率，两端各去掉一小段过渡
+    fn tone(&self, start: f64, length_ms: f64) -> Option<f64> {
+        let trim = TONE_TRIM_MS.min(length_ms / 4.0);
+        self.track.mean(start + trim * self.ms, start + (length_ms - trim) * self.ms)
+    }
+
+    /// 在 `nominal` 附近 `search_ms` 内查找平滑后的频率穿过 `threshold` 的位置，取最接近标称位置的一处
+    fn crossing(&self, nominal: f64, search_ms: f64, threshold: f64, falling: bool) -> Option<f64> {
+        let half = EDGE_SMOOTHING_MS * self.ms;
+        let smoothed = |x: f64| self.track.mean(x - half, x + half);
+        let from = (nominal - search_ms * self.ms).floor() as i64;
+        let to = (nominal + search_ms * self.ms).ceil() as i64;
+        let mut best: Option<f64> = None;
+        let mut previous: Option<f64> = None;
+        for i in from..=to {
+            let x = i as f64;
+            let value = smoothed(x);
+            if let (Some(p), Some(v)) = (previous, value) {
+                let crossed = if falling { p > threshold && v <= threshold } else { p < threshold && v >= threshold };
+                if crossed {
+                    let position = x - 1.0 + (threshold - p) / (v - p);
+                    if best.is_none_or(|b| (position - nominal).abs() < (b - nominal).abs()) {
+                        best = Some(position);
+                    }
+                }
+            }
+            previous = value;
+        }
+        best
+    }
+
+    /// 查找第一个VIS码起始位的下降沿：之前约300ms为1900Hz引导音，再之前是1200Hz间隔，之后为1200Hz
+    fn find_start_bit(&self) -> Option<f64> {
+        let ms = self.ms;
+        let near = |value: Option<f64>, target: f64| value.is_some_and(|v| (v - target).abs() <= SEARCH_TOLERANCE);
+        let mut x = (VIS_LOOKBACK_MS * ms).ceil();
+        let last = self.track.end() as f64 - 10.0 * VIS_BIT_MS * ms;
+        while x < last {
+            if near(self.track.mean(x - 290.0 * ms, x - 10.0 * ms), 1900.0)
+                && near(self.track.mean(x + 5.0 * ms, x + 25.0 * ms), 1200.0)
+                && self.track.mean(x - 308.0 * ms, x - 302.0 * ms).is_some_and(|v| v < 1650.0)
+            {
+                return Some(self.crossing(x, EDGE_SEARCH_MS, 1550.0, true).unwrap_or(x));
+            }
+            x += ms;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AuxChannel, ChannelRouting};
+    use crate::sstv::{FrequencyMapping, SstvModulator};
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    fn modulate(modulator: SstvModulator) -> Vec<f32> {
+        let mut modulator = modulator;
+        let (width, height) = modulator.get_mode().get_dimensions();
+        let image = RgbImage::from_fn(width, height, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8]));
+        modulator.modulate_image(&DynamicImage::ImageRgb8(image)).unwrap();
+        modulator.get_samples().to_vec()
+    }
+
+    #[test]
+    fn test_all_generators_pass() {
+        for mode in [SstvMode::ScottieDx, SstvMode::Robot36, SstvMode::Pd120, SstvMode::MartinM1] {
+            let samples = modulate(SstvModulator::new(mode).with_sample_rate(8000).unwrap());
+            let report = TransmissionLinter::new(mode).lint_samples(&samples, 8000).unwrap();
+            let failures: Vec<String> = report.failures().map(|check| check.to_string()).collect();
+            assert!(failures.is_empty(), "{}: {:#?}", mode, failures);
+            // 默认200ms静音和800ms校准前导音之后是VIS码
+            assert!(report.vis_start.unwrap().abs_diff(8000) <= 1, "{:?}", report.vis_start);
+            assert_eq!(report.check("sync.count").unwrap().measured, Some(mode.get_scan_line_count() as f64));
+        }
+    }
+
+    #[test]
+    fn test_detects_broken_encodes() {
+        let clean = modulate(SstvModulator::new(SstvMode::Robot36).with_sample_rate(11025).unwrap());
+        let linter = TransmissionLinter::new(SstvMode::Robot36);
+
+        // 整体偏频：频率检查失败，时序仍然正确
+        let shifted = modulate(
+            SstvModulator::new(SstvMode::Robot36).with_sample_rate(11025).unwrap().with_frequency_mapping(FrequencyMapping::offset(40.0)),
+        );
+        let report = linter.lint_samples(&shifted, 11025).unwrap();
+        assert!(!report.check("sync.frequency").unwrap().passed());
+        assert!(!report.check("vis.leader1.frequency").unwrap().passed());
+        assert!(report.check("vis.code").unwrap().passed());
+        assert!(report.check("line.drift").unwrap().passed());
+
+        // 图像中途丢失3ms样本：之后的扫描块位置和总长度超差
+        let mut dropped = clean.clone();
+        let at = 11025 + (910.0 + 150.0 * 100.5) as usize * 11025 / 1000;
+        dropped.drain(at..at + 33);
+        let report = linter.lint_samples(&dropped, 11025).unwrap();
+        let position = report.check("line.position").unwrap();
+        assert!(!position.passed());
+        assert!(position.line.unwrap() > 100);
+        assert!(!report.check("transmission.length").unwrap().passed());
+
+        // 模式不符：VIS码不一致
+        let report = TransmissionLinter::new(SstvMode::MartinM1).lint_samples(&clean, 11025).unwrap();
+        assert!(!report.check("vis.code").unwrap().passed());
+
+        // 没有VIS码
+        let report = TransmissionLinter::new(SstvMode::Robot36)
+            .lint_samples(&modulate(SstvModulator::new(SstvMode::Robot36).with_sample_rate(11025).unwrap().with_vis_header(false)), 11025)
+            .unwrap();
+        assert_eq!(report.vis_start, None);
+        assert_eq!(report.failures().next().unwrap().name, "vis.detected");
+    }
+
+    #[test]
+    fn test_lint_wav_file() {
+        let path = std::env::temp_dir().join(format!("sstv_lint_{}.wav", std::process::id()));
+        let mut modulator = SstvModulator::new(SstvMode::MartinM1).with_sample_rate(22050).unwrap();
+        let (width, height) = SstvMode::MartinM1.get_dimensions();
+        modulator.encode_to_file(&DynamicImage::ImageRgb8(RgbImage::new(width, height)), &path).unwrap();
+        let report = lint_wav(&path, SstvMode::MartinM1, 0).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(report.passed(), "{:#?}", report.failures().collect::<Vec<_>>());
+        let json = report.to_json();
+        assert_eq!(json["passed"], true);
+        assert_eq!(json["sample_rate"], 22050);
+        assert!(json["checks"].as_array().unwrap().iter().any(|check| check["name"] == "line.drift"));
+    }
+
+    #[test]
+    fn test_lint_wav_channel() {
+        // SSTV信号在右声道，左声道静音
+        let path = std::env::temp_dir().join(format!("sstv_lint_stereo_{}.wav", std::process::id()));
+        let mut modulator = SstvModulator::new(SstvMode::Robot36)
+            .with_sample_rate(8000)
+            .unwrap()
+            .with_channel_routing(ChannelRouting::stereo_right(AuxChannel::Silent));
+        let (width, height) = SstvMode::Robot36.get_dimensions();
+        modulator.encode_to_file(&DynamicImage::ImageRgb8(RgbImage::new(width, height)), &path).unwrap();
+        let right = lint_wav(&path, SstvMode::Robot36, 1);
+        let left = lint_wav(&path, SstvMode::Robot36, 0);
+        let missing = lint_wav(&path, SstvMode::Robot36, 2);
+        std::fs::remove_file(&path).ok();
+
+        let right = right.unwrap();
+        assert!(right.passed(), "{:#?}", right.failures().collect::<Vec<_>>());
+        let left = left.unwrap();
+        assert_eq!(left.vis_start, None);
+        assert!(!left.passed());
+        assert!(matches!(missing, Err(SstvError::InvalidAudioParameter { .. })));
+    }
+}
@@ -0,0 +1,216 @@
+//! WAV内嵌元数据模块
+//!
+//! 把模式、VIS码、采样率、呼号、生成时间和源图像哈希写入WAV文件，使音频离开原目录后仍能追溯来源。
+//! 同一份信息写两处：标准的 `LIST/INFO` 块供音频编辑器和播放器显示，
+//! 自定义的 `sstv` 块保存完整的JSON，供 [`WavMetadata::read_wav`] 无损读回。
+
+use crate::error::{Result, SstvError};
+use crate::sstv::SstvMode;
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use image::DynamicImage;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// 自定义块的ID
+pub const SSTV_CHUNK_ID: [u8; 4] = *b"sstv";
+
+/// 一次SSTV传输的来源信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct WavMetadata {
+    /// 模式
+    pub mode: SstvMode,
+    /// VIS码
+    pub vis_code: u8,
+    /// 采样率（Hz）
+    pub sample_rate: u32,
+    /// 发射台呼号
+    pub callsign: Option<String>,
+    /// 生成时间（UTC）
+    pub timestamp: DateTime<Utc>,
+    /// 源图像的SHA-256（十六进制），见 [`image_sha256`]
+    pub image_sha256: Option<String>,
+    /// 生成软件及版本
+    pub software: String,
+}
+
+impl WavMetadata {
+    /// 以当前时间（精确到毫秒，与写入的精度一致）创建元数据
+    pub fn new(mode: SstvMode, sample_rate: u32) -> Self {
+        Self {
+            mode,
+            vis_code: u8::from_str_radix(mode.get_vis_code(), 2).unwrap_or(0),
+            sample_rate,
+            callsign: None,
+            timestamp: Utc::now().trunc_subsecs(3),
+            image_sha256: None,
+            software: format!("sstv-rust {}", crate::VERSION),
+        }
+    }
+
+    /// 设置呼号
+    pub fn with_callsign<S: Into<String>>(mut self, callsign: S) -> Self {
+        self.callsign = Some(callsign.into());
+        self
+    }
+
+    /// 按源图像计算并设置哈希
+    pub fn with_source_image(mut self, image: &DynamicImage) -> Self {
+        self.image_sha256 = Some(image_sha256(image));
+        self
+    }
+
+    /// 导出为JSON（即 `sstv` 块的内容）
+    pub fn to_json(&self) -> Value {
+        json!({
+            "mode": self.mode.get_mode_name(),
+            "vis_code": self.vis_code,
+            "sample_rate": self.sample_rate,
+            "callsign": self.callsign,
+            "timestamp": self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "image_sha256": self.image_sha256,
+            "software": self.software,
+        })
+    }
+
+    /// 从JSON解析；模式以VIS码为准
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let invalid = |field: &str| SstvError::InvalidFormat(format!("SSTV元数据缺少或无效的字段: {}", field));
+        let vis_code = value["vis_code"].as_u64().and_then(|code| u8::try_from(code).ok()).ok_or_else(|| invalid("vis_code"))?;
+        let mode = SstvMode::from_vis_code(vis_code).ok_or_else(|| invalid("vis_code"))?;
+        let sample_rate = value["sample_rate"]
+            .as_u64()
+            .and_then(|rate| u32::try_from(rate).ok())
+            .ok_or_else(|| invalid("sample_rate"))?;
+        let timestamp = value["timestamp"]
+            .as_str()
+            .and_then(|text| DateTime::parse_from_rfc3339(text).ok())
+            .ok_or_else(|| invalid("timestamp"))?
+            .with_timezone(&Utc);
+        let text = |field: &str| value[field].as_str().map(str::to_string);
+        Ok(Self {
+            mode,
+            vis_code,
+            sample_rate,
+            callsign: text("callsign"),
+            timestamp,
+            image_sha256: text("image_sha256"),
+            software: text("software").unwrap_or_default(),
+        })
+    }
+
+    /// `LIST/INFO` 块的条目：标题、软件、日期、作者（呼号）和注释
+    pub fn info_entries(&self) -> Vec<([u8; 4], String)> {
+        let mut entries = vec![
+            (*b"INAM", format!("SSTV {}", self.mode.get_mode_name())),
+            (*b"ISFT", self.software.clone()),
+            (*b"ICRD", self.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ];
+        if let Some(callsign) = &self.callsign {
+            entries.push((*b"IART", callsign.clone()));
+        }
+        let mut comment = format!("mode={} vis={} rate={}", self.mode.get_mode_name(), self.vis_code, self.sample_rate);
+        if let Some(hash) = &self.image_sha256 {
+            comment.push_str(&format!(" sha256={}", hash));
+        }
+        entries.push((*b"ICMT", comment));
+        entries
+    }
+
+    /// 要追加到WAV文件的 `LIST/INFO` 块和 `sstv` 块
+    pub(crate) fn wav_chunks(&self) -> Vec<([u8; 4], Vec<u8>)> {
+        let mut info = b"INFO".to_vec();
+        for (id, text) in self.info_entries() {
+            let mut data = text.into_bytes();
+            data.push(0);
+            info.extend(id);
+            info.extend((data.len() as u32).to_le_bytes());
+            info.extend(&data);
+            if data.len() % 2 == 1 {
+                info.push(0);
+            }
+        }
+        vec![(*b"LIST", info), (SSTV_CHUNK_ID, self.to_json().to_string().into_bytes())]
+    }
+
+    /// 在已有WAV文件末尾追加元数据块
+    pub fn write_wav<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        crate::audio::append_wav_chunks(path.as_ref(), &self.wav_chunks())
+    }
+
+    /// 从WAV文件读取元数据；文件不含 `sstv` 块时返回 `None`
+    pub fn read_wav<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let chunks = crate::audio::read_wav_chunks(path.as_ref())?;
+        let Some((_, data)) = chunks.iter().find(|(id, _)| *id == SSTV_CHUNK_ID) else {
+            return Ok(None);
+        };
+        let value: Value = serde_json::from_slice(data).map_err(|e| SstvError::InvalidFormat(format!("SSTV元数据: {}", e)))?;
+        Self::from_json(&value).map(Some)
+    }
+}
+
+/// 读取WAV文件 `LIST/INFO` 块中的全部条目（ID, 文本）
+pub fn read_wav_info<P: AsRef<Path>>(path: P) -> Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for (_, data) in crate::audio::read_wav_chunks(path.as_ref())?.iter().filter(|(id, _)| id == b"LIST") {
+        if data.len() < 4 || &data[0..4] != b"INFO" {
+            continue;
+        }
+        let mut position = 4;
+        while position + 8 <= data.len() {
+            let id = String::from_utf8_lossy(&data[position..position + 4]).into_owned();
+            let length = u32::from_le_bytes(data[position + 4..position + 8].try_into().unwrap()) as usize;
+            let end = (position + 8 + length).min(data.len());
+            let text = String::from_utf8_lossy(&data[position + 8..end]).trim_end_matches('\0').to_string();
+            entries.push((id, text));
+            position = end + (length & 1);
+        }
+    }
+    Ok(entries)
+}
+
+/// 源图像的SHA-256（十六进制）
+///
+/// 对转换为8位RGB后的宽、高（小端u32）和像素数据计算，与图像文件的编码格式和元数据无关。
+pub fn image_sha256(image: &DynamicImage) -> String {
+    let rgb = image.to_rgb8();
+    let mut hasher = Sha256::new();
+    hasher.update(rgb.width().to_le_bytes());
+    hasher.update(rgb.height().to_le_bytes());
+    hasher.update(rgb.as_raw());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn test_json_round_trip() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(8, 4, |x, y| image::Rgb([x as u8, y as u8, 7])));
+        let metadata = WavMetadata::new(SstvMode::Pd120, 48000).with_callsign("VK7KSM").with_source_image(&image);
+        assert_eq!(metadata.vis_code, 95);
+        assert_eq!(metadata.image_sha256.as_ref().unwrap().len(), 64);
+
+        let parsed = WavMetadata::from_json(&metadata.to_json()).unwrap();
+        assert_eq!(parsed.mode.get_mode_name(), "PD120");
+        assert_eq!(parsed.callsign.as_deref(), Some("VK7KSM"));
+        assert_eq!(parsed.image_sha256, metadata.image_sha256);
+        assert_eq!(parsed, metadata);
+        assert!(WavMetadata::from_json(&json!({ "vis_code": 1, "sample_rate": 8000 })).is_err());
+    }
+
+    #[test]
+    fn test_image_hash() {
+        let a = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, image::Rgb([1, 2, 3])));
+        let b = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(4, 4, image::Rgba([1, 2, 3, 255])));
+        // 像素相同时与存储格式无关，尺寸不同时哈希不同
+        assert_eq!(image_sha256(&a), image_sha256(&b));
+        let c = DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 8, image::Rgb([1, 2, 3])));
+        assert_ne!(image_sha256(&a), image_sha256(&c));
+        // 空图像的哈希是固定值
+        let empty = DynamicImage::ImageRgb8(RgbImage::new(0, 0));
+        assert_eq!(image_sha256(&empty), "af5570f5a1810b7af78caf4bc70a660f0df51e42baf91d4de5b2328de0e83dfc");
+    }
+}
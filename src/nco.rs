@@ -0,0 +1,230 @@
+//! 数控振荡器（NCO）模块
+//!
+//! 用32位整数相位累加器生成正弦音调：每个样本把频率控制字加到相位上，自然回绕，
+//! 音调切换时相位严格连续，长时间运行也不会累积浮点误差。正弦值可以直接由相位计算，
+//! 也可以查表并线性插值；查表模式只使用整数和固定的浮点运算，输出逐位可复现。
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+/// 相位累加器满量程（2^32对应一个完整周期）
+const PHASE_SCALE: f64 = 4_294_967_296.0;
+/// 正弦表索引位数（4096个点）
+const TABLE_BITS: u32 = 12;
+/// 正弦表点数
+const TABLE_SIZE: usize = 1 << TABLE_BITS;
+/// 表内插值的小数位数
+const FRACTION_BITS: u32 = 32 - TABLE_BITS;
+
+/// 正弦值的计算方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SineMode {
+    /// 由相位直接计算 `sin`（默认，精度最高）
+    #[default]
+    Exact,
+    /// 4096点正弦表加线性插值，误差约3e-7（低于24位量化步长），速度更快且跨平台逐位一致
+    LookupTable,
+}
+
+/// 相位累加器数控振荡器
+#[derive(Debug, Clone)]
+pub struct Nco {
+    sample_rate: u32,
+    mode: SineMode,
+    // 当前相位，2^32对应2π
+    phase: u32,
+}
+
+impl Nco {
+    /// 创建振荡器，初始相位为0
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            mode: SineMode::default(),
+            phase: 0,
+        }
+    }
+
+    /// 设置正弦值的计算方式
+    pub fn with_mode(mut self, mode: SineMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 获取正弦值的计算方式
+    pub fn mode(&self) -> SineMode {
+        self.mode
+    }
+
+    /// 获取采样率
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// 把频率（Hz）换算为每样本的相位增量（频率控制字）
+    ///
+    /// 频率分辨率为 `采样率 / 2^32`，48kHz时约为1.1e-5Hz。负频率按补码回绕，
+    /// 等价于相位反向旋转。
+    pub fn frequency_word(&self, frequency: f64) -> u32 {
+        let cycles = frequency / self.sample_rate as f64;
+        let cycles = cycles - cycles.floor();
+        ((cycles * PHASE_SCALE).round() as u64) as u32
+    }
+
+    /// 当前相位（原始32位累加器值）
+    pub fn phase(&self) -> u32 {
+        self.phase
+    }
+
+    /// 设置当前相位（原始32位累加器值）
+    pub fn set_phase(&mut self, phase: u32) {
+        self.phase = phase;
+    }
+
+    /// 当前相位（弧度，范围[0, 2π)）
+    pub fn phase_radians(&self) -> f64 {
+        self.phase as f64 * (2.0 * PI / PHASE_SCALE)
+    }
+
+    /// 相位归零
+    pub fn reset(&mut self) {
+        self.phase = 0;
+    }
+
+    /// 当前相位的正弦值（不推进相位）
+    pub fn current(&self) -> f32 {
+        match self.mode {
+            SineMode::Exact => self.phase_radians().sin() as f32,
+            SineMode::LookupTable => table_sine(self.phase),
+        }
+    }
+
+    /// 输出当前样本并按频率控制字推进相位
+    pub fn next_sample(&mut self, word: u32) -> f32 {
+        let sample = self.current();
+        self.phase = self.phase.wrapping_add(word);
+        sample
+    }
+
+    /// 不生成样本，只把相位推进 `count` 个样本
+    ///
+    /// 相位累加器按2^32取模，结果与逐样本推进完全相同。
+    pub fn advance(&mut self, word: u32, count: usize) {
+        self.phase = self.phase.wrapping_add(word.wrapping_mul(count as u32));
+    }
+
+    /// 以固定频率控制字填满缓冲区
+    pub fn fill(&mut self, word: u32, out: &mut [f32]) {
+        match self.mode {
+            SineMode::Exact => {
+                for sample in out.iter_mut() {
+                    *sample = self.phase_radians().sin() as f32;
+                    self.phase = self.phase.wrapping_add(word);
+                }
+            }
+            SineMode::LookupTable => {
+                let table = sine_table();
+                for sample in out.iter_mut() {
+                    *sample = interpolate(table, self.phase);
+                    self.phase = self.phase.wrapping_add(word);
+                }
+            }
+        }
+    }
+}
+
+/// 正弦表（多一个点，插值时无需回绕索引）
+fn sine_table() -> &'static [f32; TABLE_SIZE + 1] {
+    static TABLE: OnceLock<[f32; TABLE_SIZE + 1]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0.0f32; TABLE_SIZE + 1];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = (2.0 * PI * i as f64 / TABLE_SIZE as f64).sin() as f32;
+        }
+        table
+    })
+}
+
+fn table_sine(phase: u32) -> f32 {
+    interpolate(sine_table(), phase)
+}
+
+#[inline]
+fn interpolate(table: &[f32; TABLE_SIZE + 1], phase: u32) -> f32 {
+    let index = (phase >> FRACTION_BITS) as usize;
+    let fraction = (phase & ((1 << FRACTION_BITS) - 1)) as f32 * (1.0 / (1u32 << FRACTION_BITS) as f32);
+    let a = table[index];
+    let b = table[index + 1];
+    a + (b - a) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frequency_word() {
+        let nco = Nco::new(48000);
+        assert_eq!(nco.frequency_word(0.0), 0);
+        assert_eq!(nco.frequency_word(12000.0), 1 << 30);
+        assert_eq!(nco.frequency_word(24000.0), 1 << 31);
+        // 负频率按补码回绕
+        assert_eq!(nco.frequency_word(-12000.0), 3 << 30);
+        // 分辨率远小于1Hz
+        let word = nco.frequency_word(1900.0);
+        let actual = word as f64 * 48000.0 / PHASE_SCALE;
+        assert!((actual - 1900.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_lookup_table_accuracy() {
+        let mut exact = Nco::new(44100);
+        let mut table = Nco::new(44100).with_mode(SineMode::LookupTable);
+        let word = exact.frequency_word(1234.5);
+        let mut a = vec![0.0f32; 10000];
+        let mut b = vec![0.0f32; 10000];
+        exact.fill(word, &mut a);
+        table.fill(word, &mut b);
+        let max_error = a.iter().zip(&b).map(|(x, y)| (x - y).abs()).fold(0.0f32, f32::max);
+        assert!(max_error < 1e-6, "max error {}", max_error);
+        assert_eq!(exact.phase(), table.phase());
+    }
+
+    #[test]
+    fn test_fill_matches_next_sample() {
+        for mode in [SineMode::Exact, SineMode::LookupTable] {
+            let mut a = Nco::new(8000).with_mode(mode);
+            let mut b = Nco::new(8000).with_mode(mode);
+            let word = a.frequency_word(1500.0);
+            let mut filled = [0.0f32; 257];
+            a.fill(word, &mut filled);
+            let stepped: Vec<f32> = (0..257).map(|_| b.next_sample(word)).collect();
+            assert_eq!(filled.as_slice(), stepped.as_slice());
+            assert_eq!(a.phase(), b.phase());
+        }
+    }
+
+    #[test]
+    fn test_advance_matches_fill() {
+        let mut a = Nco::new(11025);
+        let mut b = Nco::new(11025);
+        let word = a.frequency_word(2299.7);
+        let mut buffer = vec![0.0f32; 123_457];
+        a.fill(word, &mut buffer);
+        b.advance(word, buffer.len());
+        assert_eq!(a.phase(), b.phase());
+    }
+
+    #[test]
+    fn test_phase_wraps_without_drift() {
+        // 频率控制字整除2^32时，整周期后相位精确回到原点
+        let mut nco = Nco::new(48000);
+        let word = nco.frequency_word(12000.0);
+        let mut buffer = vec![0.0f32; 48000 * 10];
+        nco.fill(word, &mut buffer);
+        assert_eq!(nco.phase(), 0);
+        assert!(buffer.iter().step_by(4).all(|&s| s == 0.0));
+    }
+}
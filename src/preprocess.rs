@@ -0,0 +1,864 @@
+//! 图像预处理模块
+//!
+//! 本模块负责把任意尺寸的输入图像适配到SSTV模式的目标分辨率，
+//! 提供等比留边、裁剪铺满和直接拉伸三种适配策略，以及缩放滤波器选择、
+//! EXIF自动旋转、ICC色彩配置文件到sRGB的转换、面向SSTV接收效果的色彩调整，
+//! 以及呼号、台标等文字叠加。
+
+use crate::error::{Result, SstvError};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageBuffer, ImageDecoder, ImageReader, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 预处理选项
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreprocessOptions {
+    /// 图像适配策略
+    pub fit_mode: FitMode,
+    /// 缩放滤波器
+    pub resize_filter: ResizeFilter,
+    /// 色彩调整
+    pub color_adjustment: ColorAdjustment,
+    /// 文字叠加（按顺序绘制在色彩调整之后）
+    pub overlays: Vec<TextOverlay>,
+}
+
+/// 缩放滤波器
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeFilter {
+    /// 最近邻（速度最快，适合像素画）
+    Nearest,
+    /// 双线性
+    Triangle,
+    /// Catmull-Rom三次插值
+    CatmullRom,
+    /// 高斯（较柔和）
+    Gaussian,
+    /// Lanczos3（默认，最锐利）
+    #[default]
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    /// 获取滤波器名称（用于元数据）
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResizeFilter::Nearest => "nearest",
+            ResizeFilter::Triangle => "triangle",
+            ResizeFilter::CatmullRom => "catmull_rom",
+            ResizeFilter::Gaussian => "gaussian",
+            ResizeFilter::Lanczos3 => "lanczos3",
+        }
+    }
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// 色彩调整参数（全部为1.0时不做任何修改）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorAdjustment {
+    /// 伽马值（大于1提亮中间调，小于1压暗）
+    pub gamma: f32,
+    /// 对比度倍数（围绕中灰缩放）
+    pub contrast: f32,
+    /// 饱和度倍数（0为灰度）
+    pub saturation: f32,
+}
+
+impl Default for ColorAdjustment {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl ColorAdjustment {
+    /// 不做调整
+    pub fn identity() -> Self {
+        Self { gamma: 1.0, contrast: 1.0, saturation: 1.0 }
+    }
+
+    /// 针对SSTV接收效果的推荐调整
+    ///
+    /// 亮度信号被量化到16-235的YUV范围，色差信号在两行间取平均，
+    /// 接收端画面通常偏灰、偏暗且颜色变淡，因此略微提亮中间调并提高对比度和饱和度。
+    pub fn sstv_tuned() -> Self {
+        Self { gamma: 1.1, contrast: 1.1, saturation: 1.25 }
+    }
+
+    /// 是否为不做任何修改的参数
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    /// 对图像指定区域应用调整
+    pub fn apply_to_region(&self, image: &mut RgbImage, region: (u32, u32, u32, u32)) {
+        if self.is_identity() {
+            return;
+        }
+
+        // 伽马和对比度只与单个通道值有关，预先计算查找表
+        let gamma = self.gamma.max(0.01);
+        let mut lut = [0.0f32; 256];
+        for (i, value) in lut.iter_mut().enumerate() {
+            let v = (i as f32 / 255.0).powf(1.0 / gamma);
+            *value = ((v - 0.5) * self.contrast + 0.5) * 255.0;
+        }
+
+        let (x0, y0, width, height) = region;
+        for y in y0..(y0 + height).min(image.height()) {
+            for x in x0..(x0 + width).min(image.width()) {
+                let pixel = image.get_pixel_mut(x, y);
+                let r = lut[pixel[0] as usize];
+                let g = lut[pixel[1] as usize];
+                let b = lut[pixel[2] as usize];
+                let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+                let saturate = |c: f32| (luma + (c - luma) * self.saturation).round().clamp(0.0, 255.0) as u8;
+                *pixel = Rgb([saturate(r), saturate(g), saturate(b)]);
+            }
+        }
+    }
+}
+
+/// 加载图像文件，根据EXIF方向信息自动旋转，并把内嵌ICC配置文件的图像转换到sRGB
+///
+/// 手机照片通常以传感器方向存储像素，并在EXIF中记录显示方向，
+/// 直接使用 `image::open` 会得到旋转后的画面；Display P3等广色域照片若按sRGB解释会显得发灰。
+/// 无法解析的ICC配置文件会被忽略（打印警告），像素按sRGB处理。
+pub fn load_image<P: AsRef<Path>>(path: P) -> Result<DynamicImage> {
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(|e| SstvError::ImageProcessing(format!("无法加载图像: {}", e)))?;
+    let orientation = decoder.orientation()
+        .map_err(|e| SstvError::ImageProcessing(format!("无法读取图像方向: {}", e)))?;
+    let icc_profile = decoder.icc_profile()
+        .map_err(|e| SstvError::ImageProcessing(format!("无法读取ICC配置文件: {}", e)))?;
+    let mut image = DynamicImage::from_decoder(decoder)
+        .map_err(|e| SstvError::ImageProcessing(format!("无法加载图像: {}", e)))?;
+    if let Some(profile) = icc_profile {
+        match convert_to_srgb(&image, &profile) {
+            Ok(converted) => image = converted,
+            Err(e) => eprintln!("警告：忽略无法使用的ICC配置文件（{}），按sRGB处理", e),
+        }
+    }
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// 按ICC配置文件把图像转换到sRGB（8位RGB，有透明通道时保留为RGBA）
+fn convert_to_srgb(image: &DynamicImage, profile: &[u8]) -> std::result::Result<DynamicImage, moxcms::CmsError> {
+    use moxcms::{ColorProfile, Layout, TransformOptions};
+
+    let source = ColorProfile::new_from_slice(profile)?;
+    let srgb = ColorProfile::new_srgb();
+    if image.color().has_alpha() {
+        let pixels = image.to_rgba8();
+        let transform = source.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, TransformOptions::default())?;
+        let mut converted = pixels.clone();
+        transform.transform(pixels.as_raw(), &mut converted)?;
+        Ok(DynamicImage::ImageRgba8(converted))
+    } else {
+        let pixels = image.to_rgb8();
+        let transform = source.create_transform_8bit(Layout::Rgb, &srgb, Layout::Rgb, TransformOptions::default())?;
+        let mut converted = pixels.clone();
+        transform.transform(pixels.as_raw(), &mut converted)?;
+        Ok(DynamicImage::ImageRgb8(converted))
+    }
+}
+
+/// 按预处理选项把图像适配到目标尺寸并进行色彩调整
+pub fn preprocess_image(
+    image: &DynamicImage,
+    target: (u32, u32),
+    options: &PreprocessOptions,
+) -> (RgbImage, FitGeometry) {
+    let (mut target_image, geometry) = fit_image(image, target, options.fit_mode, options.resize_filter.into());
+
+    // 纯色留边保持用户指定的颜色，只调整图像内容区域
+    let region = match options.fit_mode {
+        FitMode::Letterbox(BarFill::Color(_)) => {
+            let (left, top, right, bottom) = geometry.black_bars;
+            (left, top, target.0 - left - right, target.1 - top - bottom)
+        }
+        _ => (0, 0, target.0, target.1),
+    };
+    options.color_adjustment.apply_to_region(&mut target_image, region);
+
+    // 文字最后绘制，保持指定的颜色
+    for overlay in &options.overlays {
+        overlay.draw(&mut target_image);
+    }
+
+    (target_image, geometry)
+}
+
+/// 文字叠加的位置（贴近画面的角或上下边的中间）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayPosition {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// 文字叠加（呼号、台标等），使用内置的5×7点阵字体
+///
+/// 支持ASCII字母（小写按大写绘制）、数字、空格和常用标点，其他字符绘制为 `?`；
+/// 超出画面的部分被裁掉。
+///
+/// ```rust
+/// use sstv_rust::{OverlayPosition, TextOverlay};
+///
+/// let station_id = TextOverlay::new("VK7KSM")
+///     .with_position(OverlayPosition::BottomRight)
+///     .with_color([255, 255, 0])
+///     .with_scale(3);
+/// assert_eq!(station_id.dimensions(), (111, 27));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextOverlay {
+    /// 文字内容
+    pub text: String,
+    /// 位置
+    pub position: OverlayPosition,
+    /// 文字颜色（RGB）
+    pub color: [u8; 3],
+    /// 底色（RGB）；为 `None` 时文字直接绘制在图像上
+    pub background: Option<[u8; 3]>,
+    /// 放大倍数，每个字体点占 scale×scale 像素（0按1处理）
+    pub scale: u32,
+}
+
+impl Default for TextOverlay {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            position: OverlayPosition::default(),
+            color: [255, 255, 255],
+            background: Some([0, 0, 0]),
+            scale: 2,
+        }
+    }
+}
+
+/// 字形宽度和高度（字体点）
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// 字符间距（字体点）
+const GLYPH_SPACING: u32 = 1;
+
+impl TextOverlay {
+    /// 以默认样式（黑底白字，左上角，2倍大小）创建文字叠加
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), ..Self::default() }
+    }
+
+    /// 设置位置
+    pub fn with_position(mut self, position: OverlayPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// 设置文字颜色
+    pub fn with_color(mut self, color: [u8; 3]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// 设置底色，`None` 表示不绘制底色
+    pub fn with_background(mut self, background: Option<[u8; 3]>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// 设置放大倍数
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// 叠加区域（含底色边距）的宽和高，文字为空时为 (0, 0)
+    pub fn dimensions(&self) -> (u32, u32) {
+        let count = self.text.chars().count() as u32;
+        if count == 0 {
+            return (0, 0);
+        }
+        let scale = self.scale.max(1);
+        let width = (count * (GLYPH_WIDTH + GLYPH_SPACING) - GLYPH_SPACING + 2) * scale;
+        let height = (GLYPH_HEIGHT + 2) * scale;
+        (width, height)
+    }
+
+    /// 把文字绘制到图像上
+    pub fn draw(&self, image: &mut RgbImage) {
+        let (width, height) = self.dimensions();
+        if width == 0 {
+            return;
+        }
+        let scale = self.scale.max(1);
+
+        // 叠加区域与画面边缘留出两个字体点的距离
+        let margin = 2 * scale;
+        let (image_width, image_height) = image.dimensions();
+        let left = match self.position {
+            OverlayPosition::TopLeft | OverlayPosition::BottomLeft => margin,
+            OverlayPosition::Top | OverlayPosition::Bottom => image_width.saturating_sub(width) / 2,
+            OverlayPosition::TopRight | OverlayPosition::BottomRight => image_width.saturating_sub(width + margin),
+        };
+        let top = match self.position {
+            OverlayPosition::TopLeft | OverlayPosition::Top | OverlayPosition::TopRight => margin,
+            _ => image_height.saturating_sub(height + margin),
+        };
+
+        if let Some(background) = self.background {
+            fill_rect(image, (left, top, width, height), Rgb(background));
+        }
+        for (index, c) in self.text.chars().enumerate() {
+            let x0 = left + (1 + index as u32 * (GLYPH_WIDTH + GLYPH_SPACING)) * scale;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (0x10 >> column) != 0 {
+                        let x = x0 + column * scale;
+                        let y = top + (1 + row as u32) * scale;
+                        fill_rect(image, (x, y, scale, scale), Rgb(self.color));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 填充矩形区域（x, y, width, height），超出图像的部分忽略
+fn fill_rect(image: &mut RgbImage, region: (u32, u32, u32, u32), color: Rgb<u8>) {
+    let (x0, y0, width, height) = region;
+    for y in y0..y0.saturating_add(height).min(image.height()) {
+        for x in x0..x0.saturating_add(width).min(image.width()) {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+/// 5×7点阵字形，每行低5位从左到右为一个字体点
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x00, 0x00, 0x04],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        '&' => [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D],
+        '\'' => [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '*' => [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '@' => [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// 图像适配策略
+///
+/// 序列化格式与元数据文件一致，例如 `{"strategy": "crop_to_fill", "anchor": "top"}`。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "FitModeRepr", from = "FitModeRepr")]
+pub enum FitMode {
+    /// 保持宽高比完整显示，空白区域按指定方式填充（默认，黑边）
+    Letterbox(BarFill),
+    /// 保持宽高比缩放至铺满画面，超出部分按锚点裁剪（适合人像）
+    CropToFill(CropAnchor),
+    /// 忽略宽高比直接拉伸到目标尺寸（适合图表）
+    Stretch,
+}
+
+impl Default for FitMode {
+    fn default() -> Self {
+        FitMode::Letterbox(BarFill::default())
+    }
+}
+
+impl FitMode {
+    /// 黑边留白（与旧版本行为一致）
+    pub fn letterbox() -> Self {
+        FitMode::Letterbox(BarFill::Color([0, 0, 0]))
+    }
+
+    /// 居中裁剪铺满
+    pub fn crop_center() -> Self {
+        FitMode::CropToFill(CropAnchor::Center)
+    }
+
+    /// 获取策略名称（用于元数据）
+    pub fn strategy_name(&self) -> &'static str {
+        match self {
+            FitMode::Letterbox(_) => "letterbox",
+            FitMode::CropToFill(_) => "crop_to_fill",
+            FitMode::Stretch => "stretch",
+        }
+    }
+}
+
+/// `FitMode` 的序列化形式：以 `strategy` 字段区分策略
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+enum FitModeRepr {
+    Letterbox { fill: BarFill },
+    CropToFill { anchor: CropAnchor },
+    Stretch,
+}
+
+impl From<FitMode> for FitModeRepr {
+    fn from(mode: FitMode) -> Self {
+        match mode {
+            FitMode::Letterbox(fill) => FitModeRepr::Letterbox { fill },
+            FitMode::CropToFill(anchor) => FitModeRepr::CropToFill { anchor },
+            FitMode::Stretch => FitModeRepr::Stretch,
+        }
+    }
+}
+
+impl From<FitModeRepr> for FitMode {
+    fn from(repr: FitModeRepr) -> Self {
+        match repr {
+            FitModeRepr::Letterbox { fill } => FitMode::Letterbox(fill),
+            FitModeRepr::CropToFill { anchor } => FitMode::CropToFill(anchor),
+            FitModeRepr::Stretch => FitMode::Stretch,
+        }
+    }
+}
+
+/// 留边区域的填充方式
+///
+/// 序列化为 `{"type": "color", "color": [r, g, b]}` 或 `{"type": "blur", "sigma": 8.0}`。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "BarFillRepr", from = "BarFillRepr")]
+pub enum BarFill {
+    /// 纯色填充（RGB）
+    Color([u8; 3]),
+    /// 使用同一图像放大铺满后的模糊版本作为背景
+    Blur {
+        /// 高斯模糊强度
+        sigma: f32,
+    },
+}
+
+impl Default for BarFill {
+    fn default() -> Self {
+        BarFill::Color([0, 0, 0])
+    }
+}
+
+/// `BarFill` 的序列化形式
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BarFillRepr {
+    Color { color: [u8; 3] },
+    Blur { sigma: f32 },
+}
+
+impl From<BarFill> for BarFillRepr {
+    fn from(fill: BarFill) -> Self {
+        match fill {
+            BarFill::Color(color) => BarFillRepr::Color { color },
+            BarFill::Blur { sigma } => BarFillRepr::Blur { sigma },
+        }
+    }
+}
+
+impl From<BarFillRepr> for BarFill {
+    fn from(repr: BarFillRepr) -> Self {
+        match repr {
+            BarFillRepr::Color { color } => BarFill::Color(color),
+            BarFillRepr::Blur { sigma } => BarFill::Blur { sigma },
+        }
+    }
+}
+
+/// 裁剪锚点（决定裁剪时保留图像的哪一部分）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CropAnchor {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl CropAnchor {
+    /// 获取锚点名称（用于元数据）
+    pub fn name(&self) -> &'static str {
+        match self {
+            CropAnchor::Center => "center",
+            CropAnchor::Top => "top",
+            CropAnchor::Bottom => "bottom",
+            CropAnchor::Left => "left",
+            CropAnchor::Right => "right",
+            CropAnchor::TopLeft => "top_left",
+            CropAnchor::TopRight => "top_right",
+            CropAnchor::BottomLeft => "bottom_left",
+            CropAnchor::BottomRight => "bottom_right",
+        }
+    }
+
+    /// 根据多余的宽高计算裁剪起点
+    fn offset(&self, excess_x: u32, excess_y: u32) -> (u32, u32) {
+        let x = match self {
+            CropAnchor::Left | CropAnchor::TopLeft | CropAnchor::BottomLeft => 0,
+            CropAnchor::Right | CropAnchor::TopRight | CropAnchor::BottomRight => excess_x,
+            _ => excess_x / 2,
+        };
+        let y = match self {
+            CropAnchor::Top | CropAnchor::TopLeft | CropAnchor::TopRight => 0,
+            CropAnchor::Bottom | CropAnchor::BottomLeft | CropAnchor::BottomRight => excess_y,
+            _ => excess_y / 2,
+        };
+        (x, y)
+    }
+}
+
+/// 适配结果的几何信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitGeometry {
+    /// 缩放比例（拉伸模式下为水平方向比例）
+    pub scale_factor: f64,
+    /// 留边宽度：left, top, right, bottom
+    pub black_bars: (u32, u32, u32, u32),
+    /// 裁剪区域（缩放后坐标）：x, y, width, height
+    pub crop_region: Option<(u32, u32, u32, u32)>,
+}
+
+/// 按指定策略把图像适配到目标尺寸
+pub fn fit_image(
+    image: &DynamicImage,
+    target: (u32, u32),
+    fit_mode: FitMode,
+    filter: FilterType,
+) -> (RgbImage, FitGeometry) {
+    let (target_width, target_height) = target;
+    let (src_width, src_height) = (image.width(), image.height());
+    let scale_x = target_width as f64 / src_width as f64;
+    let scale_y = target_height as f64 / src_height as f64;
+
+    match fit_mode {
+        FitMode::Letterbox(fill) => {
+            // 使用较小的比例以确保图像完全适合
+            let scale = scale_x.min(scale_y);
+            let scaled_width = ((src_width as f64 * scale) as u32).clamp(1, target_width);
+            let scaled_height = ((src_height as f64 * scale) as u32).clamp(1, target_height);
+
+            let scaled_rgb = image
+                .resize_exact(scaled_width, scaled_height, filter)
+                .to_rgb8();
+
+            let mut target_image = match fill {
+                BarFill::Color(color) => {
+                    ImageBuffer::from_pixel(target_width, target_height, Rgb(color))
+                }
+                BarFill::Blur { sigma } => {
+                    let (background, _) = crop_to_fill(image, target, CropAnchor::Center, filter);
+                    imageops::blur(&background, sigma.max(0.1))
+                }
+            };
+
+            // 将缩放后的图像复制到目标图像的中心
+            let offset_x = (target_width - scaled_width) / 2;
+            let offset_y = (target_height - scaled_height) / 2;
+            imageops::replace(&mut target_image, &scaled_rgb, offset_x as i64, offset_y as i64);
+
+            let geometry = FitGeometry {
+                scale_factor: scale,
+                black_bars: (
+                    offset_x,
+                    offset_y,
+                    target_width - offset_x - scaled_width,
+                    target_height - offset_y - scaled_height,
+                ),
+                crop_region: None,
+            };
+            (target_image, geometry)
+        }
+        FitMode::CropToFill(anchor) => {
+            let (target_image, geometry) = crop_to_fill(image, target, anchor, filter);
+            (target_image, geometry)
+        }
+        FitMode::Stretch => {
+            let target_image = image
+                .resize_exact(target_width, target_height, filter)
+                .to_rgb8();
+            let geometry = FitGeometry {
+                scale_factor: scale_x,
+                black_bars: (0, 0, 0, 0),
+                crop_region: None,
+            };
+            (target_image, geometry)
+        }
+    }
+}
+
+/// 等比放大到铺满目标尺寸，再按锚点裁掉多余部分
+fn crop_to_fill(
+    image: &DynamicImage,
+    target: (u32, u32),
+    anchor: CropAnchor,
+    filter: FilterType,
+) -> (RgbImage, FitGeometry) {
+    let (target_width, target_height) = target;
+    let (src_width, src_height) = (image.width(), image.height());
+
+    // 使用较大的比例以确保画面被完全覆盖
+    let scale = (target_width as f64 / src_width as f64).max(target_height as f64 / src_height as f64);
+    let scaled_width = ((src_width as f64 * scale).ceil() as u32).max(target_width);
+    let scaled_height = ((src_height as f64 * scale).ceil() as u32).max(target_height);
+
+    let scaled_rgb = image
+        .resize_exact(scaled_width, scaled_height, filter)
+        .to_rgb8();
+
+    let (crop_x, crop_y) = anchor.offset(scaled_width - target_width, scaled_height - target_height);
+    let target_image = imageops::crop_imm(&scaled_rgb, crop_x, crop_y, target_width, target_height).to_image();
+
+    let geometry = FitGeometry {
+        scale_factor: scale,
+        black_bars: (0, 0, 0, 0),
+        crop_region: Some((crop_x, crop_y, target_width, target_height)),
+    };
+    (target_image, geometry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wide_image() -> DynamicImage {
+        // 左半红、右半蓝的 400x100 图像
+        let image = RgbImage::from_fn(400, 100, |x, _| {
+            if x < 200 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) }
+        });
+        DynamicImage::ImageRgb8(image)
+    }
+
+    #[test]
+    fn test_letterbox_adds_bars() {
+        let (image, geometry) = fit_image(&wide_image(), (320, 256), FitMode::letterbox(), FilterType::Triangle);
+        assert_eq!(image.dimensions(), (320, 256));
+        assert_eq!(geometry.black_bars.0, 0);
+        assert!(geometry.black_bars.1 > 0);
+        assert_eq!(*image.get_pixel(160, 0), Rgb([0, 0, 0]));
+        assert!(geometry.crop_region.is_none());
+    }
+
+    #[test]
+    fn test_letterbox_custom_color() {
+        let fit = FitMode::Letterbox(BarFill::Color([10, 20, 30]));
+        let (image, _) = fit_image(&wide_image(), (320, 256), fit, FilterType::Triangle);
+        assert_eq!(*image.get_pixel(160, 0), Rgb([10, 20, 30]));
+    }
+
+    #[test]
+    fn test_crop_to_fill_anchor() {
+        let fit = FitMode::CropToFill(CropAnchor::Left);
+        let (image, geometry) = fit_image(&wide_image(), (320, 256), fit, FilterType::Triangle);
+        assert_eq!(image.dimensions(), (320, 256));
+        assert_eq!(geometry.black_bars, (0, 0, 0, 0));
+        assert_eq!(geometry.crop_region.unwrap().0, 0);
+        // 左对齐裁剪只保留红色部分
+        assert_eq!(*image.get_pixel(300, 128), Rgb([255, 0, 0]));
+
+        let fit = FitMode::CropToFill(CropAnchor::Right);
+        let (image, _) = fit_image(&wide_image(), (320, 256), fit, FilterType::Triangle);
+        assert_eq!(*image.get_pixel(20, 128), Rgb([0, 0, 255]));
+    }
+
+    #[test]
+    fn test_color_adjustment() {
+        let mut image = RgbImage::from_pixel(4, 4, Rgb([200, 100, 50]));
+        ColorAdjustment::identity().apply_to_region(&mut image, (0, 0, 4, 4));
+        assert_eq!(*image.get_pixel(0, 0), Rgb([200, 100, 50]));
+
+        // 饱和度为0时输出灰度
+        let grey = ColorAdjustment { saturation: 0.0, ..ColorAdjustment::identity() };
+        grey.apply_to_region(&mut image, (0, 0, 2, 4));
+        let pixel = image.get_pixel(0, 0);
+        assert_eq!(pixel[0], pixel[1]);
+        assert_eq!(pixel[1], pixel[2]);
+        // 区域外的像素不受影响
+        assert_eq!(*image.get_pixel(3, 0), Rgb([200, 100, 50]));
+    }
+
+    #[test]
+    fn test_preprocess_keeps_bar_color() {
+        let options = PreprocessOptions {
+            fit_mode: FitMode::Letterbox(BarFill::Color([40, 40, 40])),
+            resize_filter: ResizeFilter::Triangle,
+            color_adjustment: ColorAdjustment::sstv_tuned(),
+            overlays: Vec::new(),
+        };
+        let (image, _) = preprocess_image(&wide_image(), (320, 256), &options);
+        assert_eq!(*image.get_pixel(160, 0), Rgb([40, 40, 40]));
+    }
+
+    #[test]
+    fn test_text_overlay() {
+        let black = Rgb([0, 0, 0]);
+        let white = Rgb([255, 255, 255]);
+        let mut image = RgbImage::from_pixel(100, 40, black);
+        TextOverlay::new("T1").with_background(None).with_scale(1).draw(&mut image);
+        // 边距2、内边距1，文字从 (3, 3) 开始；T的第一行5个点全亮，竖画在第3列
+        assert!((3..8).all(|x| *image.get_pixel(x, 3) == white));
+        assert_eq!(*image.get_pixel(5, 9), white);
+        assert_eq!(*image.get_pixel(4, 9), black);
+        assert_eq!(*image.get_pixel(2, 3), black);
+
+        // 右下角的底色区域紧贴边距，小写字母按大写绘制，未知字符绘制为问号
+        let overlay = TextOverlay::new("k?").with_position(OverlayPosition::BottomRight).with_color([255, 0, 0]);
+        let (width, height) = overlay.dimensions();
+        assert_eq!((width, height), (26, 18));
+        let mut image = RgbImage::from_pixel(100, 40, Rgb([9, 9, 9]));
+        overlay.draw(&mut image);
+        assert_eq!(*image.get_pixel(100 - 4 - width, 40 - 4 - height), black);
+        assert_eq!(*image.get_pixel(100 - 4 - width - 1, 40 - 4 - height), Rgb([9, 9, 9]));
+        assert_eq!(*image.get_pixel(97, 37), Rgb([9, 9, 9]));
+        let mut expected = RgbImage::from_pixel(100, 40, Rgb([9, 9, 9]));
+        TextOverlay { text: "K€".to_string(), ..overlay.clone() }.draw(&mut expected);
+        assert_eq!(image, expected);
+
+        // 超出画面的文字被裁掉，不会越界
+        let mut small = RgbImage::from_pixel(8, 8, black);
+        TextOverlay::new("CQ CQ DE VK7KSM").with_position(OverlayPosition::Bottom).with_scale(4).draw(&mut small);
+        assert_eq!(TextOverlay::new("").dimensions(), (0, 0));
+    }
+
+    #[test]
+    fn test_preprocess_applies_overlays() {
+        let options = PreprocessOptions {
+            fit_mode: FitMode::Stretch,
+            overlays: vec![TextOverlay::new("VK7KSM").with_background(Some([0, 255, 0]))],
+            ..PreprocessOptions::default()
+        };
+        let (image, _) = preprocess_image(&wide_image(), (320, 256), &options);
+        assert_eq!(*image.get_pixel(4, 4), Rgb([0, 255, 0]));
+        assert_eq!(*image.get_pixel(4, 200), Rgb([255, 0, 0]));
+
+        let value = serde_json::to_value(&options).unwrap();
+        assert_eq!(value["overlays"][0]["position"], "top_left");
+        assert_eq!(serde_json::from_value::<PreprocessOptions>(value).unwrap(), options);
+        let partial: PreprocessOptions = serde_json::from_str(r#"{"overlays": [{"text": "CQ"}]}"#).unwrap();
+        assert_eq!(partial.overlays, vec![TextOverlay::new("CQ")]);
+    }
+
+    #[test]
+    fn test_stretch_fills_target() {
+        let (image, geometry) = fit_image(&wide_image(), (320, 256), FitMode::Stretch, FilterType::Triangle);
+        assert_eq!(image.dimensions(), (320, 256));
+        assert_eq!(geometry.black_bars, (0, 0, 0, 0));
+        assert_eq!(*image.get_pixel(10, 0), Rgb([255, 0, 0]));
+        assert_eq!(*image.get_pixel(310, 255), Rgb([0, 0, 255]));
+    }
+
+    #[test]
+    fn test_options_serde_format() {
+        let options = PreprocessOptions {
+            fit_mode: FitMode::CropToFill(CropAnchor::TopLeft),
+            resize_filter: ResizeFilter::CatmullRom,
+            color_adjustment: ColorAdjustment::identity(),
+            overlays: Vec::new(),
+        };
+        let value = serde_json::to_value(&options).unwrap();
+        assert_eq!(value["fit_mode"], serde_json::json!({ "strategy": "crop_to_fill", "anchor": "top_left" }));
+        assert_eq!(value["resize_filter"], "catmull_rom");
+        assert_eq!(serde_json::from_value::<PreprocessOptions>(value).unwrap(), options);
+
+        let blur = FitMode::Letterbox(BarFill::Blur { sigma: 8.0 });
+        let value = serde_json::to_value(blur).unwrap();
+        assert_eq!(value, serde_json::json!({ "strategy": "letterbox", "fill": { "type": "blur", "sigma": 8.0 } }));
+        assert_eq!(serde_json::from_value::<FitMode>(value).unwrap(), blur);
+        assert_eq!(serde_json::to_value(FitMode::Stretch).unwrap(), serde_json::json!({ "strategy": "stretch" }));
+    }
+
+    #[test]
+    fn test_load_image_orientation_and_icc() {
+        // 32x16 的Display P3 JPEG，左半 (200, 100, 50)、右半中灰，EXIF方向为6（需顺时针旋转90°）
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/rotated_display_p3.jpg");
+        let image = load_image(path).unwrap().to_rgb8();
+        assert_eq!(image.dimensions(), (16, 32));
+
+        // 旋转后原来的左半在上方；P3颜色转换到sRGB后饱和度更高，约为 (215, 93, 31)
+        let top = image.get_pixel(8, 8);
+        for (channel, expected) in top.0.iter().zip([215u8, 93, 31]) {
+            assert!(channel.abs_diff(expected) <= 6, "{:?}", top);
+        }
+        // 中灰在两种色彩空间中相同
+        let bottom = image.get_pixel(8, 24);
+        assert!(bottom.0.iter().all(|&c| c.abs_diff(128) <= 3), "{:?}", bottom);
+
+        // 不处理方向和配置文件时仍是传感器方向的原始像素
+        let raw = image::open(path).unwrap().to_rgb8();
+        assert_eq!(raw.dimensions(), (32, 16));
+        assert!(raw.get_pixel(8, 8)[0].abs_diff(200) <= 6);
+    }
+}
@@ -0,0 +1,91 @@
+//! 调制进度和取消模块
+//!
+//! 调制器每生成一个扫描块调用一次进度回调，并在扫描块之间检查取消令牌；
+//! 令牌可以克隆后交给其他线程（例如GUI的"停止"按钮），取消后调制返回 [`SstvError::Cancelled`]。
+//!
+//! [`SstvError::Cancelled`]: crate::SstvError::Cancelled
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 调制进度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// 已完成的扫描块数
+    pub lines_done: u32,
+    /// 扫描块总数
+    pub total_lines: u32,
+    /// 已生成音频的时长（秒），包含开头的静音、前导音和VIS码
+    pub elapsed: f64,
+}
+
+impl Progress {
+    /// 完成百分比（0-100），按扫描块计
+    pub fn percent(&self) -> f64 {
+        if self.total_lines == 0 {
+            100.0
+        } else {
+            self.lines_done as f64 * 100.0 / self.total_lines as f64
+        }
+    }
+}
+
+/// 进度回调类型
+pub(crate) type ProgressCallback = dyn FnMut(&Progress) + Send;
+
+/// 取消令牌
+///
+/// 克隆得到的令牌共享同一个状态，任意一个调用 `cancel` 后所有克隆都变为已取消。
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// 创建未取消的令牌
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// 已取消时返回 `SstvError::Cancelled`
+    pub(crate) fn check(&self) -> crate::Result<()> {
+        if self.is_cancelled() {
+            Err(crate::SstvError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_percent() {
+        let progress = Progress { lines_done: 60, total_lines: 240, elapsed: 10.0 };
+        assert_eq!(progress.percent(), 25.0);
+        assert_eq!(Progress { lines_done: 0, total_lines: 0, elapsed: 0.0 }.percent(), 100.0);
+    }
+
+    #[test]
+    fn test_cancellation_token_shared() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        assert!(token.check().is_ok());
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert!(matches!(clone.check(), Err(crate::SstvError::Cancelled)));
+    }
+}
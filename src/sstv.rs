@@ -1,11 +1,24 @@
-use crate::audio::{AudioProcessor, WavWriter};
+use crate::audio::{self, AudioFormat, AudioProcessor, ChannelRouting, Dither, PcmFormat, RawPcmSink, SampleSink, WavWriter};
+#[cfg(feature = "flac")]
+use crate::flac::FlacWriter;
+use crate::iq::{IqConfig, IqModulator};
+use crate::nco::{Nco, SineMode};
+use crate::progress::{CancellationToken, Progress, ProgressCallback};
+use crate::timeline::{ColorChannel, SegmentKind, Timeline, TimelineRecorder};
+use crate::metadata::WavMetadata;
+use crate::config::EncoderConfig;
 use crate::error::SstvError;
-use image::{DynamicImage, RgbImage, Rgb, ImageBuffer, ImageFormat};
+use crate::preprocess::{self, ColorAdjustment, FitMode, PreprocessOptions, ResizeFilter, TextOverlay};
+use image::{DynamicImage, RgbImage, ImageFormat};
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::f64::consts::PI;
+use std::fmt;
 use std::path::Path;
+use std::str::FromStr;
 
 // SSTV模式定义
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SstvMode {
     ScottieDx,  // 320x256, 269.6秒
     Robot36,    // 320x240, 36.0秒
@@ -23,6 +36,13 @@ impl SstvMode {
         }
     }
     
+    /// 根据VIS码数值（7位，低位先发送）查找模式
+    pub fn from_vis_code(code: u8) -> Option<Self> {
+        [SstvMode::ScottieDx, SstvMode::Robot36, SstvMode::Pd120, SstvMode::MartinM1]
+            .into_iter()
+            .find(|mode| u8::from_str_radix(mode.get_vis_code(), 2) == Ok(code))
+    }
+    
     pub fn get_dimensions(&self) -> (u32, u32) {
         match self {
             SstvMode::ScottieDx => (320, 256),
@@ -41,6 +61,15 @@ impl SstvMode {
         }
     }
     
+    /// 获取扫描块数量（PD120每个扫描块传输两行图像）
+    pub fn get_scan_line_count(&self) -> u32 {
+        let (_, height) = self.get_dimensions();
+        match self {
+            SstvMode::Pd120 => height.div_ceil(2),
+            _ => height,
+        }
+    }
+    
     pub fn get_mode_name(&self) -> &'static str {
         match self {
             SstvMode::ScottieDx => "ScottieDX",
@@ -49,12 +78,135 @@ impl SstvMode {
             SstvMode::MartinM1 => "MartinM1",
         }
     }
+    
+    /// 最短的像素时长（毫秒），Robot36和PD120为色度扫描
+    pub fn get_shortest_pixel_ms(&self) -> f64 {
+        match self {
+            SstvMode::ScottieDx => 1.08,
+            SstvMode::Robot36 => 0.1375,
+            SstvMode::Pd120 => 0.19,
+            SstvMode::MartinM1 => 0.4576,
+        }
+    }
+    
+    /// 本模式可用的最低采样率
+    ///
+    /// 不低于 `MIN_SAMPLE_RATE`，且最短像素（`get_shortest_pixel_ms`）至少占一个样本，否则像素边界的
+    /// 取整抖动达到整个像素。Robot36的色差像素只有0.1375ms，因此须至少7273Hz。
+    pub fn get_min_sample_rate(&self) -> u32 {
+        let pixel_rate = (1000.0 / self.get_shortest_pixel_ms()).ceil() as u32;
+        crate::MIN_SAMPLE_RATE.max(pixel_rate)
+    }
+
+    /// 本模式的默认采样率：常用采样率中不低于 `get_min_sample_rate()` 的最小值
+    ///
+    /// 多数模式为 `DEFAULT_SAMPLE_RATE`（6000Hz），Robot36为8000Hz。
+    pub fn get_default_sample_rate(&self) -> u32 {
+        let min_rate = self.get_min_sample_rate();
+        [crate::DEFAULT_SAMPLE_RATE, 8000, 11025, 16000, 22050, 44100, 48000]
+            .into_iter()
+            .find(|&rate| rate >= min_rate)
+            .unwrap_or(min_rate)
+    }
+    
+    /// 检查采样率是否在 `get_min_sample_rate()` 至 `MAX_SAMPLE_RATE` 之间
+    pub fn validate_sample_rate(&self, sample_rate: u32) -> Result<(), SstvError> {
+        let min_rate = self.get_min_sample_rate();
+        if !(min_rate..=crate::MAX_SAMPLE_RATE).contains(&sample_rate) {
+            return Err(SstvError::invalid_sample_rate(sample_rate, min_rate, crate::MAX_SAMPLE_RATE));
+        }
+        Ok(())
+    }
+    
+    /// 在指定采样率下，样本取整造成的音调边界时序抖动
+    pub fn get_timing_jitter(&self, sample_rate: u32) -> TimingJitter {
+        let peak_ms = 1000.0 / sample_rate as f64;
+        let pixel_ms = self.get_shortest_pixel_ms();
+        TimingJitter {
+            sample_rate,
+            peak_ms,
+            rms_ms: peak_ms / 12f64.sqrt(),
+            samples_per_pixel: pixel_ms / peak_ms,
+            pixel_fraction: peak_ms / pixel_ms,
+        }
+    }
+}
+
+/// 采样率造成的时序抖动
+///
+/// 每个音调的样本数取整，累积误差补偿使音调边界与理想时刻的偏差始终小于一个采样周期，
+/// 不会随传输累积；抖动只影响像素边界的位置，不影响扫描块周期。
+///
+/// ```rust
+/// use sstv_rust::SstvMode;
+///
+/// let jitter = SstvMode::Robot36.get_timing_jitter(8000);
+/// assert_eq!(jitter.peak_ms, 0.125);
+/// assert!(jitter.samples_per_pixel < 1.5); // 色度像素只有约1.1个样本
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingJitter {
+    /// 采样率（Hz）
+    pub sample_rate: u32,
+    /// 音调边界的最大偏差（毫秒），即一个采样周期
+    pub peak_ms: f64,
+    /// 偏差的均方根（毫秒），按均匀分布为采样周期的 1/√12
+    pub rms_ms: f64,
+    /// 最短像素内的样本数
+    pub samples_per_pixel: f64,
+    /// 最大偏差占最短像素时长的比例
+    pub pixel_fraction: f64,
+}
+
+/// 显示为模式名称（如 `PD120`）
+impl fmt::Display for SstvMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.get_mode_name())
+    }
+}
+
+/// 解析模式名称，不区分大小写并忽略空格、`-` 和 `_`
+///
+/// 接受 `PD120`、`pd-120`、`Robot 36`、`r36`、`Scottie DX`、`sdx`、`Martin M1`、`m1` 等写法。
+impl FromStr for SstvMode {
+    type Err = SstvError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let key: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match key.as_str() {
+            "scottiedx" | "sdx" => Ok(SstvMode::ScottieDx),
+            "robot36" | "r36" => Ok(SstvMode::Robot36),
+            "pd120" => Ok(SstvMode::Pd120),
+            "martinm1" | "martin1" | "m1" => Ok(SstvMode::MartinM1),
+            _ => Err(SstvError::unsupported_mode(name)),
+        }
+    }
+}
+
+/// 序列化为模式名称
+impl Serialize for SstvMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.get_mode_name())
+    }
+}
+
+/// 按 `FromStr` 的规则解析模式名称
+impl<'de> Deserialize<'de> for SstvMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
 }
 
 /// 图片保存格式配置
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageSaveConfig {
-    /// 图片格式
+    /// 图片格式（序列化为扩展名，如 `"png"`）
+    #[serde(with = "image_format_serde")]
     pub format: ImageFormat,
     /// JPEG质量 (1-100)
     pub jpeg_quality: Option<u8>,
@@ -109,18 +261,151 @@ impl ImageSaveConfig {
 }
 
 /// 处理信息元数据
-#[derive(Debug, Clone)]
+///
+/// 序列化格式与元数据文件（`save_processed_image` 旁边的JSON）中的字段一致，
+/// 可用 [`ProcessingMetadata::load_sidecar`] 读回。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProcessingMetadata {
+    #[serde(with = "dimensions_serde")]
     pub original_dimensions: (u32, u32),
+    #[serde(with = "dimensions_serde")]
     pub target_dimensions: (u32, u32),
     pub sstv_mode: SstvMode,
     pub scale_factor: f64,
+    #[serde(with = "black_bars_serde")]
     pub black_bars: (u32, u32, u32, u32), // left, top, right, bottom
     pub processing_timestamp: String,
+    // 以下字段是后来加入的，旧版本的元数据文件中没有，读取时取默认值（即旧版本的固定行为）
+    /// 使用的图像适配策略
+    #[serde(default)]
+    pub fit_mode: FitMode,
+    /// 使用的缩放滤波器
+    #[serde(default)]
+    pub resize_filter: ResizeFilter,
+    /// 使用的色彩调整参数
+    #[serde(default)]
+    pub color_adjustment: ColorAdjustment,
+    /// 裁剪区域（缩放后坐标：x, y, width, height），仅裁剪铺满模式有值
+    #[serde(default, with = "crop_region_serde")]
+    pub crop_region: Option<(u32, u32, u32, u32)>,
+}
+
+impl ProcessingMetadata {
+    /// 解析元数据文件的内容
+    pub fn from_sidecar_json(text: &str) -> Result<Self, SstvError> {
+        let sidecar: Sidecar<SidecarInfo> =
+            serde_json::from_str(text).map_err(|e| SstvError::InvalidFormat(format!("元数据文件: {}", e)))?;
+        Ok(sidecar.sstv_processing_info.metadata)
+    }
+    
+    /// 读取 `save_processed_image` 生成的元数据文件（与图像同名的 `.json`）
+    pub fn load_sidecar<P: AsRef<Path>>(path: P) -> Result<Self, SstvError> {
+        Self::from_sidecar_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// 元数据文件的外层结构
+#[derive(Serialize, Deserialize)]
+struct Sidecar<T> {
+    sstv_processing_info: T,
+}
+
+/// 元数据文件内容：处理元数据加上版本、采样率和时长
+#[derive(Serialize, Deserialize)]
+struct SidecarInfo {
+    #[serde(default)]
+    version: String,
+    #[serde(flatten)]
+    metadata: ProcessingMetadata,
+    sample_rate: u32,
+    duration_seconds: f64,
+}
+
+/// 尺寸序列化为 `{"width", "height"}`
+mod dimensions_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Dimensions {
+        width: u32,
+        height: u32,
+    }
+
+    pub fn serialize<S: Serializer>(&(width, height): &(u32, u32), serializer: S) -> Result<S::Ok, S::Error> {
+        Dimensions { width, height }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(u32, u32), D::Error> {
+        let Dimensions { width, height } = Dimensions::deserialize(deserializer)?;
+        Ok((width, height))
+    }
+}
+
+/// 留边宽度序列化为 `{"left", "top", "right", "bottom"}`
+mod black_bars_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct BlackBars {
+        left: u32,
+        top: u32,
+        right: u32,
+        bottom: u32,
+    }
+
+    pub fn serialize<S: Serializer>(&(left, top, right, bottom): &(u32, u32, u32, u32), serializer: S) -> Result<S::Ok, S::Error> {
+        BlackBars { left, top, right, bottom }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<(u32, u32, u32, u32), D::Error> {
+        let BlackBars { left, top, right, bottom } = BlackBars::deserialize(deserializer)?;
+        Ok((left, top, right, bottom))
+    }
+}
+
+/// 裁剪区域序列化为 `{"x", "y", "width", "height"}` 或 `null`
+mod crop_region_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct CropRegion {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    }
+
+    /// x, y, width, height
+    type Region = (u32, u32, u32, u32);
+
+    pub fn serialize<S: Serializer>(region: &Option<Region>, serializer: S) -> Result<S::Ok, S::Error> {
+        region.map(|(x, y, width, height)| CropRegion { x, y, width, height }).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Region>, D::Error> {
+        let region = Option::<CropRegion>::deserialize(deserializer)?;
+        Ok(region.map(|CropRegion { x, y, width, height }| (x, y, width, height)))
+    }
+}
+
+/// 图片格式序列化为扩展名
+mod image_format_serde {
+    use image::ImageFormat;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(format: &ImageFormat, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(format.extensions_str().first().copied().unwrap_or("png"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ImageFormat, D::Error> {
+        let extension = String::deserialize(deserializer)?;
+        ImageFormat::from_extension(&extension)
+            .ok_or_else(|| serde::de::Error::custom(format!("未知的图片格式: {}", extension)))
+    }
 }
 
 /// 内存使用统计
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MemoryUsage {
     /// 音频样本占用字节数
     pub audio_samples_bytes: usize,
@@ -145,7 +430,7 @@ impl MemoryUsage {
 }
 
 /// 内存使用统计（MB单位）
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MemoryUsageMB {
     pub audio_samples_mb: f64,
     pub processed_image_mb: f64,
@@ -158,10 +443,36 @@ pub struct SstvModulator {
     mode: SstvMode,
     sample_rate: u32,
     audio_processor: AudioProcessor,
-    // 相位连续性变量
-    older_data: f64,
-    older_cos: f64,
-    delta_length: f64,
+    // 信号生成器（相位连续性和误差补偿状态）
+    generator: SignalGenerator,
+    // 图像预处理选项
+    preprocess: PreprocessOptions,
+    // 导出文件格式、样本格式和量化抖动
+    audio_format: AudioFormat,
+    output_format: PcmFormat,
+    dither: Dither,
+    // 导出声道路由
+    channel_routing: ChannelRouting,
+    // 音调频率映射（偏移/镜像）
+    frequency_mapping: FrequencyMapping,
+    // 包络渐变和像素间频率平滑
+    tone_shaping: ToneShaping,
+    // 振荡器正弦值计算方式
+    sine_mode: SineMode,
+    // 是否并行生成扫描块
+    parallel_rendering: bool,
+    // 幅度、首尾静音、VIS码和结束音
+    framing: Framing,
+    // 逐扫描块的进度回调和取消令牌
+    progress: Option<Box<ProgressCallback>>,
+    cancellation: Option<CancellationToken>,
+    // 最近一次调制的时间轴，以及导出WAV时是否写入标记
+    timeline: Option<Timeline>,
+    cue_markers: bool,
+    // 导出WAV时内嵌的来源信息：是否开启、呼号和最近一次调制的元数据
+    embed_metadata: bool,
+    callsign: Option<String>,
+    wav_metadata: Option<WavMetadata>,
     // 存储处理后的图像和元数据
     processed_image: Option<RgbImage>,
     processing_metadata: Option<ProcessingMetadata>,
@@ -169,67 +480,464 @@ pub struct SstvModulator {
 
 impl SstvModulator {
     pub fn new(mode: SstvMode) -> Self {
+        let sample_rate = mode.get_default_sample_rate();  // 多数模式为6000Hz优化采样率
         Self {
             mode,
-            sample_rate: crate::DEFAULT_SAMPLE_RATE,  // 使用6000Hz优化采样率
-            audio_processor: AudioProcessor::new(crate::DEFAULT_SAMPLE_RATE),
-            older_data: 0.0,
-            older_cos: 1.0,
-            delta_length: 0.0,
+            sample_rate,
+            audio_processor: AudioProcessor::new(sample_rate),
+            generator: SignalGenerator::new(sample_rate, FrequencyMapping::default(), ToneShaping::default(), SineMode::default()),
+            preprocess: PreprocessOptions::default(),
+            audio_format: AudioFormat::default(),
+            output_format: PcmFormat::default(),
+            dither: Dither::default(),
+            channel_routing: ChannelRouting::default(),
+            frequency_mapping: FrequencyMapping::default(),
+            tone_shaping: ToneShaping::default(),
+            sine_mode: SineMode::default(),
+            parallel_rendering: true,
+            framing: Framing::default(),
+            progress: None,
+            cancellation: None,
+            timeline: None,
+            cue_markers: false,
+            embed_metadata: false,
+            callsign: None,
+            wav_metadata: None,
             processed_image: None,
             processing_metadata: None,
         }
     }
     
-    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+    /// 设置采样率，超出本模式 `get_min_sample_rate()` 至 `MAX_SAMPLE_RATE` 的范围时返回 `InvalidSampleRate`
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Result<Self, SstvError> {
+        self.mode.validate_sample_rate(sample_rate)?;
         self.sample_rate = sample_rate;
         self.audio_processor = AudioProcessor::new(sample_rate);
+        self.generator = self.new_generator();
+        Ok(self)
+    }
+    
+    /// 设置图像适配策略（默认黑边留白）
+    pub fn with_fit_mode(mut self, fit_mode: FitMode) -> Self {
+        self.preprocess.fit_mode = fit_mode;
+        self
+    }
+    
+    /// 设置缩放滤波器（默认Lanczos3）
+    pub fn with_resize_filter(mut self, filter: ResizeFilter) -> Self {
+        self.preprocess.resize_filter = filter;
+        self
+    }
+    
+    /// 设置色彩调整（默认不调整）
+    pub fn with_color_adjustment(mut self, adjustment: ColorAdjustment) -> Self {
+        self.preprocess.color_adjustment = adjustment;
+        self
+    }
+    
+    /// 添加文字叠加（呼号、台标等），在缩放和色彩调整之后绘制，可多次调用
+    pub fn with_text_overlay(mut self, overlay: TextOverlay) -> Self {
+        self.preprocess.overlays.push(overlay);
+        self
+    }
+    
+    /// 一次性设置全部预处理选项
+    pub fn with_preprocess_options(mut self, options: PreprocessOptions) -> Self {
+        self.preprocess = options;
+        self
+    }
+    
+    /// 设置 `batch_process` 和 `export_audio` 使用的文件格式（默认WAV）
+    pub fn with_audio_format(mut self, format: AudioFormat) -> Self {
+        self.audio_format = format;
+        self
+    }
+    
+    /// 设置导出样本格式（默认16位整数）
+    pub fn with_output_format(mut self, format: PcmFormat) -> Self {
+        self.output_format = format;
+        self
+    }
+    
+    /// 设置整数格式导出时的量化抖动（默认不加抖动）
+    pub fn with_dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+    
+    /// 设置导出声道路由（默认单声道）
+    pub fn with_channel_routing(mut self, routing: ChannelRouting) -> Self {
+        self.channel_routing = routing;
+        self
+    }
+    
+    /// 设置音调频率映射，在生成时平移或镜像全部音调（默认不变）
+    ///
+    /// 用于预先补偿电台的已知失谐或LSB造成的频谱反转，不会引入重采样失真。
+    pub fn with_frequency_mapping(mut self, mapping: FrequencyMapping) -> Self {
+        self.frequency_mapping = mapping;
+        self.generator.mapping = mapping;
+        self
+    }
+    
+    /// 设置包络渐变和像素间频率平滑（默认关闭）
+    pub fn with_tone_shaping(mut self, shaping: ToneShaping) -> Self {
+        self.tone_shaping = shaping;
+        self.generator.shaping = shaping;
+        self
+    }
+    
+    /// 获取当前包络渐变和频率平滑设置
+    pub fn get_tone_shaping(&self) -> ToneShaping {
+        self.tone_shaping
+    }
+    
+    /// 设置振荡器的正弦值计算方式（默认直接计算）
+    ///
+    /// `SineMode::LookupTable` 查表插值，速度更快且跨平台逐位一致，误差低于24位量化步长。
+    pub fn with_sine_mode(mut self, mode: SineMode) -> Self {
+        self.sine_mode = mode;
+        self.generator.nco = Nco::new(self.sample_rate).with_mode(mode);
+        self
+    }
+    
+    /// 获取振荡器的正弦值计算方式
+    pub fn get_sine_mode(&self) -> SineMode {
+        self.sine_mode
+    }
+    
+    /// 设置是否用多线程并行生成扫描块（默认开启）
+    ///
+    /// 并行生成前先按时序模型确定每个扫描块的起始相位和样本数，输出与串行生成逐样本一致。
+    /// rayon线程池只有一个线程时自动退回串行；`stream_image` 按需逐块生成，始终为串行。
+    pub fn with_parallel_rendering(mut self, enabled: bool) -> Self {
+        self.parallel_rendering = enabled;
+        self
+    }
+    
+    /// 是否并行生成扫描块
+    pub fn get_parallel_rendering(&self) -> bool {
+        self.parallel_rendering
+    }
+    
+    /// 设置输出幅度（满幅为1.0，默认1.0）
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.framing.amplitude = amplitude;
+        self.generator.framing = self.framing;
+        self
+    }
+    
+    /// 获取输出幅度
+    pub fn get_amplitude(&self) -> f32 {
+        self.framing.amplitude
+    }
+    
+    /// 设置传输开头和结尾的静音时长（毫秒，默认各200ms）
+    pub fn with_silence(mut self, leading_ms: f64, trailing_ms: f64) -> Self {
+        self.framing.leading_silence_ms = leading_ms;
+        self.framing.trailing_silence_ms = trailing_ms;
+        self.generator.framing = self.framing;
+        self
+    }
+    
+    /// 获取开头和结尾的静音时长（毫秒）
+    pub fn get_silence(&self) -> (f64, f64) {
+        (self.framing.leading_silence_ms, self.framing.trailing_silence_ms)
+    }
+    
+    /// 设置是否发送前导音和VIS码（默认发送）
+    ///
+    /// 关闭后接收端无法自动识别模式，需要手动选择；此时包络渐变的淡入不起作用。
+    pub fn with_vis_header(mut self, enabled: bool) -> Self {
+        self.framing.vis_header = enabled;
+        self.generator.framing = self.framing;
+        self
+    }
+    
+    /// 是否发送前导音和VIS码
+    pub fn get_vis_header(&self) -> bool {
+        self.framing.vis_header
+    }
+    
+    /// 设置是否在图像之后发送结束音（默认发送）
+    ///
+    /// 关闭后包络渐变的淡出不起作用。
+    pub fn with_end_tones(mut self, enabled: bool) -> Self {
+        self.framing.end_tones = enabled;
+        self.generator.framing = self.framing;
+        self
+    }
+    
+    /// 是否发送结束音
+    pub fn get_end_tones(&self) -> bool {
+        self.framing.end_tones
+    }
+    
+    /// 设置进度回调，每生成一个扫描块调用一次
+    ///
+    /// 回调在调用 `modulate_image`/`modulate_image_to` 的线程上执行；并行生成时按批回调，
+    /// 每批内仍逐块调用。
+    ///
+    /// ```rust,no_run
+    /// use sstv_rust::{SstvModulator, SstvMode};
+    ///
+    /// let image = sstv_rust::load_image("input.jpg").unwrap();
+    /// let mut modulator = SstvModulator::new(SstvMode::Pd120)
+    ///     .with_sample_rate(48000).unwrap()
+    ///     .with_progress(|progress| eprint!("\r{:5.1}% {:6.1}s", progress.percent(), progress.elapsed));
+    /// modulator.modulate_image(&image).unwrap();
+    /// ```
+    pub fn with_progress<F: FnMut(&Progress) + Send + 'static>(mut self, callback: F) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+    
+    /// 设置取消令牌，在扫描块之间检查；取消后调制返回 `SstvError::Cancelled`
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+    
+    /// 获取取消令牌
+    pub fn get_cancellation(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+    
+    /// 导出WAV时写入 `cue `/`LIST adtl` 标记（VIS码和每个扫描块的起点）
+    ///
+    /// 作用于 `export_wav`、`export_audio` 和 `encode_to_file` 的WAV输出。
+    pub fn with_cue_markers(mut self, enabled: bool) -> Self {
+        self.cue_markers = enabled;
+        self
+    }
+    
+    /// 导出WAV时是否写入标记
+    pub fn get_cue_markers(&self) -> bool {
+        self.cue_markers
+    }
+    
+    /// 导出WAV时内嵌来源信息（`LIST/INFO` 块和自定义 `sstv` 块）
+    ///
+    /// 包括模式、VIS码、采样率、呼号、生成时间和源图像的SHA-256，可用 `WavMetadata::read_wav` 读回。
+    /// 作用于 `export_wav`、`export_audio` 和 `encode_to_file` 的WAV输出。
+    pub fn with_wav_metadata(mut self, enabled: bool) -> Self {
+        self.embed_metadata = enabled;
         self
     }
     
+    /// 设置写入元数据的呼号
+    pub fn with_callsign<S: Into<String>>(mut self, callsign: S) -> Self {
+        self.callsign = Some(callsign.into());
+        self
+    }
+    
+    /// 获取呼号
+    pub fn get_callsign(&self) -> Option<&str> {
+        self.callsign.as_deref()
+    }
+    
+    /// 最近一次调制的来源信息（开启 `with_wav_metadata` 时生成）
+    pub fn get_wav_metadata(&self) -> Option<&WavMetadata> {
+        self.wav_metadata.as_ref()
+    }
+    
+    /// 最近一次调制的时间轴
+    ///
+    /// 由 `modulate_image`、`modulate_image_to`、`stream_image` 和 `encode_to_file` 生成，
+    /// 记录每段音调的起始样本、样本数和频率范围。
+    pub fn get_timeline(&self) -> Option<&Timeline> {
+        self.timeline.as_ref()
+    }
+    
+    /// 获取当前音调频率映射
+    pub fn get_frequency_mapping(&self) -> FrequencyMapping {
+        self.frequency_mapping
+    }
+    
+    /// 获取当前图像适配策略
+    pub fn get_fit_mode(&self) -> FitMode {
+        self.preprocess.fit_mode
+    }
+    
+    /// 获取当前预处理选项
+    pub fn get_preprocess_options(&self) -> &PreprocessOptions {
+        &self.preprocess
+    }
+    
     /// 主要的图像调制方法 - 包含智能图片预处理
+    ///
+    /// 内部以浮点精度生成样本，返回值为量化到16位（不加抖动）的样本。
     pub fn modulate_image(&mut self, image: &DynamicImage) -> Result<Vec<i16>, SstvError> {
-        // 智能图像预处理：保持宽高比，填充黑边
+        let rgb_image = self.prepare_image(image)?;
+        
+        // 重置音频处理器，并将其作为内存输出端使用
+        let mut buffer = std::mem::replace(&mut self.audio_processor, AudioProcessor::new(self.sample_rate));
+        buffer.clear();
+        self.render(&rgb_image, &mut buffer)?;
+        self.audio_processor = buffer;
+        
+        Ok(self.audio_processor.to_i16())
+    }
+    
+    /// 流式调制：把样本直接推送到输出端，不在内存中缓存整段音频
+    ///
+    /// 返回写入的样本数。调用方负责在全部写入后调用 `sink.finish()`。
+    ///
+    /// ```rust,no_run
+    /// use sstv_rust::{SstvModulator, SstvMode, SampleSink, WavStreamSink};
+    ///
+    /// let image = sstv_rust::load_image("input.jpg").unwrap();
+    /// let mut modulator = SstvModulator::new(SstvMode::Pd120).with_sample_rate(48000).unwrap();
+    ///
+    /// // 以恒定内存写入标准输出，可直接管道给 aplay
+    /// let mut sink = WavStreamSink::new(std::io::stdout(), 48000);
+    /// modulator.modulate_image_to(&image, &mut sink).unwrap();
+    /// sink.finish().unwrap();
+    /// ```
+    pub fn modulate_image_to<S: SampleSink + ?Sized>(
+        &mut self,
+        image: &DynamicImage,
+        sink: &mut S,
+    ) -> Result<usize, SstvError> {
+        let rgb_image = self.prepare_image(image)?;
+        
+        // 流式输出时不保留内存中的旧样本
+        self.audio_processor.clear();
+        self.render(&rgb_image, sink)
+    }
+    
+    /// 拉取式调制：返回按需逐行生成样本的流
+    ///
+    /// 流保存相位连续性和误差补偿状态，可反复调用 `fill_buffer` 每次取出任意数量的样本，
+    /// 输出与 `modulate_image` 逐样本一致。
+    ///
+    /// ```rust,no_run
+    /// use sstv_rust::{SstvModulator, SstvMode};
+    ///
+    /// let image = sstv_rust::load_image("input.jpg").unwrap();
+    /// let mut modulator = SstvModulator::new(SstvMode::Robot36).with_sample_rate(48000).unwrap();
+    /// let mut stream = modulator.stream_image(&image).unwrap();
+    ///
+    /// let mut buffer = [0i16; 512];
+    /// loop {
+    ///     let count = stream.fill_buffer(&mut buffer).unwrap();
+    ///     if count == 0 {
+    ///         break;
+    ///     }
+    ///     // 把 buffer[..count] 交给音频引擎
+    /// }
+    /// ```
+    pub fn stream_image(&mut self, image: &DynamicImage) -> Result<SampleStream, SstvError> {
+        let rgb_image = self.prepare_image(image)?;
+        
+        // 流式输出时不保留内存中的旧样本
+        self.audio_processor.clear();
+        let generator = self.new_generator();
+        self.timeline = Some(generator.record_timeline(self.mode, &rgb_image)?);
+        Ok(SampleStream::new(self.mode, rgb_image, generator))
+    }
+    
+    /// 预处理图像并存储处理后的图像和元数据
+    fn prepare_image(&mut self, image: &DynamicImage) -> Result<RgbImage, SstvError> {
+        // 采样率须满足本模式的最低要求，映射后的音调必须落在有效频率范围内
+        self.mode.validate_sample_rate(self.sample_rate)?;
+        self.frequency_mapping.validate(self.sample_rate)?;
+        self.tone_shaping.validate()?;
+        self.framing.validate()?;
+        
+        // 智能图像预处理：按适配策略缩放到目标分辨率
         let (rgb_image, metadata) = self.preprocess_image_with_aspect_ratio(image)?;
         
+        // 来源信息：源图像哈希按预处理前的原图计算
+        self.wav_metadata = self.embed_metadata.then(|| {
+            let metadata = WavMetadata::new(self.mode, self.sample_rate).with_source_image(image);
+            match &self.callsign {
+                Some(callsign) => metadata.with_callsign(callsign.clone()),
+                None => metadata,
+            }
+        });
+        
         // 存储处理后的图像和元数据
         self.processed_image = Some(rgb_image.clone());
         self.processing_metadata = Some(metadata);
         
-        // 重置音频处理器和相位连续性变量
-        self.audio_processor.clear();
-        self.older_data = 0.0;
-        self.older_cos = 1.0;
-        self.delta_length = 0.0;
+        Ok(rgb_image)
+    }
+    
+    /// 生成完整传输（静音、VIS码、图像数据、结束音）并写入输出端
+    fn render<S: SampleSink + ?Sized>(&mut self, rgb_image: &RgbImage, sink: &mut S) -> Result<usize, SstvError> {
+        // 各音调的样本数与图像内容无关，先试运行一遍记录时间轴
+        self.timeline = Some(self.generator.record_timeline(self.mode, rgb_image)?);
+        
+        let generator = &mut self.generator;
+        let cancellation = self.cancellation.as_ref();
+        let total_lines = self.mode.get_scan_line_count();
+        let mut report = |lines_done: u32, samples: usize| {
+            if let Some(callback) = self.progress.as_mut() {
+                callback(&Progress { lines_done, total_lines, elapsed: samples as f64 / self.sample_rate as f64 });
+            }
+        };
         
-        // 添加开始静音
-        self.write_tone(0.0, 200.0)?;
+        // 重置相位连续性变量
+        generator.reset();
         
-        // 生成VIS码
-        self.generate_vis_code()?;
+        generator.generate_header(sink, self.mode)?;
         
-        // 根据模式生成SSTV信号
-        match self.mode {
-            SstvMode::ScottieDx => self.generate_scottie_dx(&rgb_image)?,
-            SstvMode::Robot36 => self.generate_robot36(&rgb_image)?,
-            SstvMode::Pd120 => self.generate_pd120(&rgb_image)?,
-            SstvMode::MartinM1 => self.generate_martin_m1(&rgb_image)?,
+        if self.parallel_rendering && rayon::current_num_threads() > 1 {
+            // 先按时序模型确定每个扫描块的起始相位和样本数，再并行生成
+            let plan = generator.plan_lines(self.mode, rgb_image)?;
+            render_lines_parallel(&plan, self.mode, rgb_image, sink, cancellation, &mut report)?;
+        } else {
+            // 根据模式逐个扫描块生成SSTV信号
+            for line in 0..total_lines {
+                if let Some(token) = cancellation {
+                    token.check()?;
+                }
+                generator.generate_line(sink, self.mode, rgb_image, line)?;
+                report(line + 1, generator.sample_count);
+            }
         }
         
-        // 生成结束音
-        self.generate_end_tones()?;
+        generator.generate_trailer(sink)?;
         
-        // 添加结束静音
-        self.write_tone(0.0, 200.0)?;
-        
-        Ok(self.audio_processor.get_samples().to_vec())
+        Ok(generator.sample_count)
+    }
+    
+    /// 按当前模式、采样率和整形设置计算完整传输的样本数，不生成音频
+    ///
+    /// 每个音调的时长与图像内容无关，结果与实际调制得到的样本数完全一致，可用于预估时长和文件大小。
+    pub fn transmission_samples(&self) -> Result<usize, SstvError> {
+        self.mode.validate_sample_rate(self.sample_rate)?;
+        let (width, height) = self.mode.get_dimensions();
+        let blank = RgbImage::new(width, height);
+        let mut generator = self.new_generator();
+        generator.dry_run = true;
+        
+        let mut discard: Vec<f32> = Vec::new();
+        generator.generate_header(&mut discard, self.mode)?;
+        for line in 0..self.mode.get_scan_line_count() {
+            generator.generate_line(&mut discard, self.mode, &blank, line)?;
+        }
+        generator.generate_trailer(&mut discard)?;
+        Ok(generator.sample_count)
+    }
+    
+    /// 按当前采样率、频率映射、整形、振荡器和首尾设置创建信号生成器
+    fn new_generator(&self) -> SignalGenerator {
+        let mut generator = SignalGenerator::new(self.sample_rate, self.frequency_mapping, self.tone_shaping, self.sine_mode);
+        generator.framing = self.framing;
+        generator
     }
     
-    /// 智能图像预处理：保持宽高比并填充黑边（带元数据记录和内存优化）
+    /// 智能图像预处理：按适配策略缩放到目标尺寸（带元数据记录和内存优化）
     fn preprocess_image_with_aspect_ratio(&self, image: &DynamicImage) -> Result<(RgbImage, ProcessingMetadata), SstvError> {
         let (target_width, target_height) = self.mode.get_dimensions();
         let (src_width, src_height) = (image.width(), image.height());
         
+        if src_width == 0 || src_height == 0 {
+            return Err(SstvError::ImageProcessing("输入图像尺寸为0".to_string()));
+        }
+        
         // 检查原图大小，如果过大则提前警告
         let source_pixels = src_width as u64 * src_height as u64;
         let target_pixels = target_width as u64 * target_height as u64;
@@ -238,55 +946,25 @@ impl SstvModulator {
             eprintln!("警告：原图像过大 ({}x{})，建议预先缩小以节省内存", src_width, src_height);
         }
         
-        // 计算缩放比例，保持宽高比
-        let scale_x = target_width as f64 / src_width as f64;
-        let scale_y = target_height as f64 / src_height as f64;
-        let scale = scale_x.min(scale_y); // 使用较小的比例以确保图像完全适合
-        
-        // 计算缩放后的尺寸
-        let scaled_width = (src_width as f64 * scale) as u32;
-        let scaled_height = (src_height as f64 * scale) as u32;
-        
-        // 缩放图像
-        let scaled_image = image.resize(
-            scaled_width, 
-            scaled_height, 
-            image::imageops::FilterType::Lanczos3
-        );
-        
-        // 创建目标尺寸的黑色背景图像
-        let mut target_image = ImageBuffer::from_pixel(
-            target_width, 
-            target_height, 
-            Rgb([0, 0, 0]) // 黑色背景
+        // 按适配策略缩放、留边或裁剪，并进行色彩调整
+        let (target_image, geometry) = preprocess::preprocess_image(
+            image,
+            (target_width, target_height),
+            &self.preprocess,
         );
         
-        // 计算居中位置和黑边信息
-        let offset_x = (target_width - scaled_width) / 2;
-        let offset_y = (target_height - scaled_height) / 2;
-        
-        // 将缩放后的图像复制到目标图像的中心
-        let scaled_rgb = scaled_image.to_rgb8();
-        for y in 0..scaled_height {
-            for x in 0..scaled_width {
-                let pixel = scaled_rgb.get_pixel(x, y);
-                target_image.put_pixel(offset_x + x, offset_y + y, *pixel);
-            }
-        }
-        
         // 创建处理元数据
         let metadata = ProcessingMetadata {
             original_dimensions: (src_width, src_height),
             target_dimensions: (target_width, target_height),
             sstv_mode: self.mode,
-            scale_factor: scale,
-            black_bars: (
-                offset_x, 
-                offset_y, 
-                target_width - offset_x - scaled_width, 
-                target_height - offset_y - scaled_height
-            ),
+            scale_factor: geometry.scale_factor,
+            black_bars: geometry.black_bars,
             processing_timestamp: chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string(),
+            fit_mode: self.preprocess.fit_mode,
+            resize_filter: self.preprocess.resize_filter,
+            color_adjustment: self.preprocess.color_adjustment,
+            crop_region: geometry.crop_region,
         };
         
         Ok((target_image, metadata))
@@ -330,7 +1008,7 @@ impl SstvModulator {
                     ).map_err(|e| SstvError::ImageProcessing(format!("JPEG编码失败: {}", e)))?;
                 }
                 std::fs::write(path, buffer)
-                    .map_err(|e| SstvError::IoError(e))?;
+                    .map_err(SstvError::IoError)?;
             },
             ImageFormat::Bmp => {
                 image.save_with_format(path, ImageFormat::Bmp)
@@ -360,7 +1038,7 @@ impl SstvModulator {
         // 创建目录（如果不存在）
         if !base_dir.exists() {
             std::fs::create_dir_all(base_dir)
-                .map_err(|e| SstvError::IoError(e))?;
+                .map_err(SstvError::IoError)?;
         }
         
         // 生成文件名
@@ -403,34 +1081,18 @@ impl SstvModulator {
         let image_path = image_path.as_ref();
         let metadata_path = image_path.with_extension("json");
         
-        let metadata_json = serde_json::json!({
-            "sstv_processing_info": {
-                "version": crate::VERSION,
-                "sstv_mode": metadata.sstv_mode.get_mode_name(),
-                "original_dimensions": {
-                    "width": metadata.original_dimensions.0,
-                    "height": metadata.original_dimensions.1
-                },
-                "target_dimensions": {
-                    "width": metadata.target_dimensions.0,
-                    "height": metadata.target_dimensions.1
-                },
-                "scale_factor": metadata.scale_factor,
-                "black_bars": {
-                    "left": metadata.black_bars.0,
-                    "top": metadata.black_bars.1,
-                    "right": metadata.black_bars.2,
-                    "bottom": metadata.black_bars.3
-                },
-                "processing_timestamp": metadata.processing_timestamp,
-                "sample_rate": self.sample_rate,
-                "duration_seconds": metadata.sstv_mode.get_duration()
-            }
-        });
+        let sidecar = Sidecar {
+            sstv_processing_info: SidecarInfo {
+                version: crate::VERSION.to_string(),
+                metadata: metadata.clone(),
+                sample_rate: self.sample_rate,
+                duration_seconds: metadata.sstv_mode.get_duration(),
+            },
+        };
         
-        std::fs::write(metadata_path, serde_json::to_string_pretty(&metadata_json)
+        std::fs::write(metadata_path, serde_json::to_string_pretty(&sidecar)
             .map_err(|e| SstvError::ImageProcessing(format!("JSON序列化失败: {}", e)))?)
-            .map_err(|e| SstvError::IoError(e))?;
+            .map_err(SstvError::IoError)?;
         
         Ok(())
     }
@@ -453,7 +1115,7 @@ impl SstvModulator {
         // 创建输出目录
         if !output_dir.exists() {
             std::fs::create_dir_all(output_dir)
-                .map_err(|e| SstvError::IoError(e))?;
+                .map_err(SstvError::IoError)?;
         }
         
         // 调制图像
@@ -463,11 +1125,18 @@ impl SstvModulator {
         let mode_name = self.mode.get_mode_name();
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
         
-        let audio_filename = format!("{}_{}_{}_{}.wav", base_name, mode_name, timestamp, self.sample_rate);
+        let audio_filename = format!(
+            "{}_{}_{}_{}.{}",
+            base_name,
+            mode_name,
+            timestamp,
+            self.sample_rate,
+            self.audio_format.extension()
+        );
         let audio_path = output_dir.join(audio_filename);
         
-        // 保存音频
-        self.export_wav(&audio_path)?;
+        // 按设置的文件格式保存音频
+        self.export_audio(&audio_path, self.audio_format)?;
         
         // 保存图像（使用自动命名）
         let mut image_config = image_config.clone();
@@ -492,17 +1161,13 @@ impl SstvModulator {
         self.audio_processor.clear();
         self.processed_image = None;
         self.processing_metadata = None;
-        self.older_data = 0.0;
-        self.older_cos = 1.0;
-        self.delta_length = 0.0;
+        self.generator.reset();
     }
     
     /// 分阶段清理音频内存
     pub fn clear_audio_memory(&mut self) {
         self.audio_processor.clear();
-        self.older_data = 0.0;
-        self.older_cos = 1.0;
-        self.delta_length = 0.0;
+        self.generator.reset();
     }
     
     /// 分阶段清理图像内存
@@ -513,7 +1178,7 @@ impl SstvModulator {
     
     /// 获取当前内存使用统计
     pub fn get_memory_usage(&self) -> MemoryUsage {
-        let audio_samples_bytes = self.audio_processor.get_samples().len() * std::mem::size_of::<i16>();
+        let audio_samples_bytes = std::mem::size_of_val(self.audio_processor.get_samples());
         let image_bytes = self.processed_image.as_ref()
             .map(|img| img.width() * img.height() * 3) // RGB = 3 bytes per pixel
             .unwrap_or(0) as usize;
@@ -545,164 +1210,943 @@ impl SstvModulator {
         }
     }
     
-    fn generate_vis_code(&mut self) -> Result<(), SstvError> {
-        let vis_code = self.mode.get_vis_code();
-        
-        // 前导音序列
-        let preamble_tones = [
-            (1900.0, 100.0), (1500.0, 100.0), (1900.0, 100.0), (1500.0, 100.0),
-            (2300.0, 100.0), (1500.0, 100.0), (2300.0, 100.0), (1500.0, 100.0),
-            // VIS码引导音
-            (1900.0, 300.0), (1200.0, 10.0), (1900.0, 300.0), (1200.0, 30.0),
-        ];
-        
-        for (freq, duration) in &preamble_tones {
-            self.write_tone(*freq, *duration)?;
+    /// 按设置的样本格式（16/24/32位整数或32位浮点）和声道路由导出WAV文件
+    pub fn export_wav<P: AsRef<Path>>(&self, filename: P) -> Result<(), SstvError> {
+        let mut writer = WavWriter::with_routing(
+            &filename,
+            self.sample_rate,
+            self.output_format,
+            self.dither,
+            self.channel_routing,
+        )?;
+        writer.write_normalized(self.audio_processor.get_samples())?;
+        writer.finalize()?;
+        self.append_wav_chunks(filename)
+    }
+    
+    /// 按设置在WAV文件末尾追加时间轴标记和来源信息
+    fn append_wav_chunks<P: AsRef<Path>>(&self, filename: P) -> Result<(), SstvError> {
+        let mut chunks = Vec::new();
+        if let Some(timeline) = self.timeline.as_ref().filter(|_| self.cue_markers) {
+            chunks.extend(timeline.wav_cue_chunks());
         }
-        
-        // VIS码7位数据位（小端序，从第6位到第0位）
-        for i in (0..7).rev() {
-            let bit = vis_code.chars().nth(i).unwrap();
-            let frequency = if bit == '1' { 1100.0 } else { 1300.0 };
-            self.write_tone(frequency, 30.0)?;
+        if let Some(metadata) = self.wav_metadata.as_ref().filter(|_| self.embed_metadata) {
+            chunks.extend(metadata.wav_chunks());
         }
-        
-        // 偶校验位
-        let ones_count = vis_code.chars().filter(|&c| c == '1').count();
-        let parity_freq = if ones_count % 2 == 0 { 1300.0 } else { 1100.0 };
-        self.write_tone(parity_freq, 30.0)?;
-        
-        // 结束位
-        self.write_tone(1200.0, 30.0)?;
-        
+        if chunks.is_empty() {
+            return Ok(());
+        }
+        audio::append_wav_chunks(filename.as_ref(), &chunks)
+    }
+    
+    /// 对已调制的样本应用SSTV发射带通滤波（1000-2500Hz），滤除带外成分
+    ///
+    /// 滤波经过延迟补偿，同步脉冲的时间位置不变。需在 `modulate_image` 之后、导出之前调用。
+    pub fn apply_transmit_filter(&mut self) -> Result<(), SstvError> {
+        let filter = audio::filters::FirFilter::sstv_bandpass(self.sample_rate)?;
+        filter.apply_aligned(self.audio_processor.get_samples_mut());
         Ok(())
     }
     
-    fn generate_scottie_dx(&mut self, image: &RgbImage) -> Result<(), SstvError> {
-        let (width, height) = image.dimensions();
-        
-        // 起始同步脉冲，仅第一行（严格按照PDF中的C代码）
-        self.write_tone_with_phase(1200.0, 9.0, 0.0)?;
-        
-        // 图像数据部分
-        for row in 0..height {
-            // 分离脉冲（使用相位连续性）
-            self.write_tone_with_continuous_phase(1500.0, 1.5)?;
-            
-            // 绿色扫描
-            for col in 0..width {
-                let pixel = image.get_pixel(col, row);
-                let green_freq = 1500.0 + (pixel[1] as f64) * COLOR_FREQ_MULT;
-                self.write_tone_with_continuous_phase(green_freq, 1.08)?;
+    /// 按设置的样本格式和声道路由导出FLAC文件（仅支持16/24位整数）
+    ///
+    /// 未启用 `flac` 特性时返回 `InvalidFormat`。
+    #[cfg(feature = "flac")]
+    pub fn export_flac<P: AsRef<Path>>(&self, filename: P) -> Result<(), SstvError> {
+        let mut writer = FlacWriter::create(
+            filename,
+            self.sample_rate,
+            self.output_format,
+            self.dither,
+            self.channel_routing,
+        )?;
+        writer.write_samples(self.audio_processor.get_samples())?;
+        writer.finish()
+    }
+    
+    /// 按设置的样本格式和声道路由导出FLAC文件（仅支持16/24位整数）
+    ///
+    /// 未启用 `flac` 特性时返回 `InvalidFormat`。
+    #[cfg(not(feature = "flac"))]
+    pub fn export_flac<P: AsRef<Path>>(&self, _filename: P) -> Result<(), SstvError> {
+        AudioFormat::Flac.ensure_available()
+    }
+    
+    /// 按设置的样本格式和声道路由导出无文件头的小端PCM（如s16le、f32le）
+    pub fn export_raw<P: AsRef<Path>>(&self, filename: P) -> Result<(), SstvError> {
+        let file = std::fs::File::create(filename)?;
+        let mut sink = RawPcmSink::with_routing(
+            file,
+            self.sample_rate,
+            self.output_format,
+            self.dither,
+            self.channel_routing,
+        );
+        sink.write_samples(self.audio_processor.get_samples())?;
+        sink.finish()
+    }
+    
+    /// 把已调制的音频转换为SDR复基带I/Q文件，返回复数样本数
+    pub fn export_iq<P: AsRef<Path>>(&self, filename: P, config: &IqConfig) -> Result<usize, SstvError> {
+        let modulator = IqModulator::new(*config, self.sample_rate)?;
+        let file = std::fs::File::create(filename)?;
+        modulator.write_to(self.audio_processor.get_samples(), file)
+    }
+    
+    /// 以指定文件格式导出
+    pub fn export_audio<P: AsRef<Path>>(&self, filename: P, format: AudioFormat) -> Result<(), SstvError> {
+        match format {
+            AudioFormat::Wav => self.export_wav(filename),
+            AudioFormat::Flac => self.export_flac(filename),
+            AudioFormat::Raw => self.export_raw(filename),
+        }
+    }
+    
+    /// 调制图像并按设置的文件格式直接写入文件，返回单声道样本数
+    ///
+    /// 样本边生成边写出，不在内存中保留整段音频，适合批量处理和长时间模式。
+    pub fn encode_to_file<P: AsRef<Path>>(&mut self, image: &DynamicImage, filename: P) -> Result<usize, SstvError> {
+        let (rate, format, dither, routing) = (self.sample_rate, self.output_format, self.dither, self.channel_routing);
+        match self.audio_format {
+            AudioFormat::Wav => {
+                let mut sink = WavWriter::with_routing(&filename, rate, format, dither, routing)?;
+                let count = self.modulate_image_to(image, &mut sink)?;
+                sink.finish()?;
+                self.append_wav_chunks(filename)?;
+                Ok(count)
             }
-            
-            // 分离脉冲（使用相位连续性）
-            self.write_tone_with_continuous_phase(1500.0, 1.5)?;
-            
-            // 蓝色扫描
-            for col in 0..width {
-                let pixel = image.get_pixel(col, row);
-                let blue_freq = 1500.0 + (pixel[2] as f64) * COLOR_FREQ_MULT;
-                self.write_tone_with_continuous_phase(blue_freq, 1.08)?;
+            #[cfg(feature = "flac")]
+            AudioFormat::Flac => {
+                let mut sink = FlacWriter::create(filename, rate, format, dither, routing)?;
+                let count = self.modulate_image_to(image, &mut sink)?;
+                sink.finish()?;
+                Ok(count)
             }
-            
-            // 同步脉冲与同步沿（使用相位连续性）
-            self.write_tone_with_continuous_phase(1200.0, 9.0)?;
-            self.write_tone_with_continuous_phase(1500.0, 1.5)?;
-            
-            // 红色扫描
-            for col in 0..width {
-                let pixel = image.get_pixel(col, row);
-                let red_freq = 1500.0 + (pixel[0] as f64) * COLOR_FREQ_MULT;
-                self.write_tone_with_continuous_phase(red_freq, 1.08)?;
+            #[cfg(not(feature = "flac"))]
+            AudioFormat::Flac => AudioFormat::Flac.ensure_available().map(|_| 0),
+            AudioFormat::Raw => {
+                let file = std::fs::File::create(filename)?;
+                let mut sink = RawPcmSink::with_routing(file, rate, format, dither, routing);
+                let count = self.modulate_image_to(image, &mut sink)?;
+                sink.finish()?;
+                Ok(count)
+            }
+        }
+    }
+    
+    /// 获取归一化浮点样本（-1.0 ~ 1.0）
+    ///
+    /// 旧版本返回 `&[i16]`；需要与之相同的16位整数样本时使用 [`SstvModulator::get_samples_i16`]。
+    pub fn get_samples(&self) -> &[f32] {
+        self.audio_processor.get_samples()
+    }
+    
+    /// 获取量化为16位整数的样本（不加抖动）
+    pub fn get_samples_i16(&self) -> Vec<i16> {
+        self.audio_processor.to_i16()
+    }
+    
+    /// 获取导出文件格式
+    pub fn get_audio_format(&self) -> AudioFormat {
+        self.audio_format
+    }
+    
+    /// 获取导出样本格式
+    pub fn get_output_format(&self) -> PcmFormat {
+        self.output_format
+    }
+    
+    /// 获取导出声道路由
+    pub fn get_channel_routing(&self) -> &ChannelRouting {
+        &self.channel_routing
+    }
+    
+    /// 获取量化抖动方式
+    pub fn get_dither(&self) -> Dither {
+        self.dither
+    }
+    
+    pub fn get_mode(&self) -> SstvMode {
+        self.mode
+    }
+    
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    
+    /// 按配置创建调制器，先校验全部参数
+    pub fn from_config(config: &EncoderConfig) -> Result<Self, SstvError> {
+        config.validate()?;
+        let mut modulator = SstvModulator::new(config.mode)
+            .with_sample_rate(config.sample_rate)?
+            .with_preprocess_options(config.preprocess.clone())
+            .with_frequency_mapping(config.frequency_mapping)
+            .with_tone_shaping(config.tone_shaping)
+            .with_sine_mode(config.sine_mode)
+            .with_parallel_rendering(config.parallel_rendering)
+            .with_audio_format(config.audio_format)
+            .with_output_format(config.output_format)
+            .with_dither(config.dither)
+            .with_channel_routing(config.channel_routing)
+            .with_cue_markers(config.cue_markers)
+            .with_wav_metadata(config.wav_metadata);
+        modulator.framing = config.framing();
+        modulator.generator.framing = modulator.framing;
+        modulator.callsign = config.callsign.clone();
+        Ok(modulator)
+    }
+    
+    /// 导出当前设置（不含进度回调和取消令牌）
+    pub fn get_config(&self) -> EncoderConfig {
+        EncoderConfig {
+            mode: self.mode,
+            sample_rate: self.sample_rate,
+            amplitude: self.framing.amplitude,
+            leading_silence_ms: self.framing.leading_silence_ms,
+            trailing_silence_ms: self.framing.trailing_silence_ms,
+            vis_header: self.framing.vis_header,
+            end_tones: self.framing.end_tones,
+            preprocess: self.preprocess.clone(),
+            frequency_mapping: self.frequency_mapping,
+            tone_shaping: self.tone_shaping,
+            sine_mode: self.sine_mode,
+            parallel_rendering: self.parallel_rendering,
+            audio_format: self.audio_format,
+            output_format: self.output_format,
+            dither: self.dither,
+            channel_routing: self.channel_routing,
+            cue_markers: self.cue_markers,
+            wav_metadata: self.embed_metadata,
+            callsign: self.callsign.clone(),
+        }
+    }
+}
+
+/// 音调频率映射
+///
+/// 在生成时对每个音调先按中心频率镜像（可选），再加上固定偏移。0Hz（静音）不受影响。
+///
+/// ```rust
+/// use sstv_rust::FrequencyMapping;
+///
+/// // 电台偏高50Hz：预先把所有音调降低50Hz
+/// let shift = FrequencyMapping::offset(-50.0);
+/// assert_eq!(shift.apply(1900.0), 1850.0);
+///
+/// // LSB频谱反转：以1900Hz为中心镜像，黑电平1500Hz与白电平2300Hz互换
+/// let invert = FrequencyMapping::mirror(1900.0);
+/// assert_eq!(invert.apply(1500.0), 2300.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrequencyMapping {
+    offset: f64,
+    mirror_centre: Option<f64>,
+}
+
+impl FrequencyMapping {
+    /// SSTV音调的频率范围（VIS码1100Hz至白电平2300Hz）
+    const TONE_RANGE: (f64, f64) = (1100.0, 2300.0);
+
+    /// 不做映射
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    /// 所有音调平移 `offset` Hz
+    pub fn offset(offset: f64) -> Self {
+        Self {
+            offset,
+            mirror_centre: None,
+        }
+    }
+
+    /// 以 `centre` Hz为中心镜像所有音调
+    pub fn mirror(centre: f64) -> Self {
+        Self {
+            offset: 0.0,
+            mirror_centre: Some(centre),
+        }
+    }
+
+    /// 在当前映射之后再叠加平移
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset += offset;
+        self
+    }
+
+    /// 频率偏移（Hz）
+    pub fn get_offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// 镜像中心（Hz）
+    pub fn get_mirror_centre(&self) -> Option<f64> {
+        self.mirror_centre
+    }
+
+    /// 是否为恒等映射
+    pub fn is_identity(&self) -> bool {
+        self.offset == 0.0 && self.mirror_centre.is_none()
+    }
+
+    /// 映射单个频率
+    pub fn apply(&self, frequency: f64) -> f64 {
+        if frequency == 0.0 {
+            return 0.0;
+        }
+        let mirrored = match self.mirror_centre {
+            Some(centre) => 2.0 * centre - frequency,
+            None => frequency,
+        };
+        mirrored + self.offset
+    }
+
+    /// 检查映射后的SSTV音调是否都在 (0, 奈奎斯特频率) 内
+    pub fn validate(&self, sample_rate: u32) -> Result<(), SstvError> {
+        let nyquist = sample_rate as f64 / 2.0;
+        let (low, high) = Self::TONE_RANGE;
+        for frequency in [self.apply(low), self.apply(high)] {
+            if !frequency.is_finite() || frequency <= 0.0 || frequency >= nyquist {
+                return Err(SstvError::modulation_error(format!(
+                    "频率映射后的音调 {:.1}Hz 超出有效范围 (0, {:.1})Hz",
+                    frequency, nyquist
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 音调整形：传输首尾的包络渐变和像素间的频率平滑
+///
+/// 两项默认关闭，输出与旧版本一致。开启后每个音调的样本数和同步脉冲的位置保持不变：
+/// 包络渐变只作用于VIS码开头和结束音末尾，频率平滑只作用于相邻像素之间。
+///
+/// ```rust
+/// use sstv_rust::{SstvModulator, SstvMode, ToneShaping};
+///
+/// let modulator = SstvModulator::new(SstvMode::Robot36)
+///     .with_sample_rate(8000).unwrap()
+///     .with_tone_shaping(ToneShaping::new().with_ramps(5.0).with_frequency_smoothing(0.1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToneShaping {
+    /// 传输开始和结束处升余弦包络渐变的时长（毫秒），0表示关闭
+    pub ramp_ms: f64,
+    /// 像素之间瞬时频率升余弦过渡的时长（毫秒），0表示关闭
+    pub frequency_smoothing_ms: f64,
+}
+
+impl ToneShaping {
+    /// 包络渐变的最大时长，不超过第一个100ms前导音
+    pub const MAX_RAMP_MS: f64 = 100.0;
+    /// 频率平滑的最大时长，与最长的像素时长（Scottie DX约1.08ms）相当
+    pub const MAX_SMOOTHING_MS: f64 = 1.0;
+
+    /// 不做整形
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置包络渐变时长（毫秒）
+    pub fn with_ramps(mut self, ramp_ms: f64) -> Self {
+        self.ramp_ms = ramp_ms;
+        self
+    }
+
+    /// 设置像素间频率平滑时长（毫秒）
+    pub fn with_frequency_smoothing(mut self, smoothing_ms: f64) -> Self {
+        self.frequency_smoothing_ms = smoothing_ms;
+        self
+    }
+
+    /// 检查参数范围
+    pub fn validate(&self) -> Result<(), SstvError> {
+        if !(0.0..=Self::MAX_RAMP_MS).contains(&self.ramp_ms) {
+            return Err(SstvError::modulation_error(format!(
+                "包络渐变时长 {}ms 超出范围 0-{}ms",
+                self.ramp_ms,
+                Self::MAX_RAMP_MS
+            )));
+        }
+        if !(0.0..=Self::MAX_SMOOTHING_MS).contains(&self.frequency_smoothing_ms) {
+            return Err(SstvError::modulation_error(format!(
+                "频率平滑时长 {}ms 超出范围 0-{}ms",
+                self.frequency_smoothing_ms,
+                Self::MAX_SMOOTHING_MS
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 传输的幅度和首尾组成：开头静音、前导音和VIS码、结束音、结尾静音
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Framing {
+    pub(crate) amplitude: f32,
+    pub(crate) leading_silence_ms: f64,
+    pub(crate) trailing_silence_ms: f64,
+    pub(crate) vis_header: bool,
+    pub(crate) end_tones: bool,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            amplitude: 1.0,
+            leading_silence_ms: 200.0,
+            trailing_silence_ms: 200.0,
+            vis_header: true,
+            end_tones: true,
+        }
+    }
+}
+
+impl Framing {
+    /// 首尾静音的最大时长
+    pub(crate) const MAX_SILENCE_MS: f64 = 10_000.0;
+
+    /// 检查幅度在 (0, 1] 内、静音时长在 0-10s 内
+    pub(crate) fn validate(&self) -> Result<(), SstvError> {
+        if !(self.amplitude > 0.0 && self.amplitude <= 1.0) {
+            return Err(SstvError::InvalidAudioParameter {
+                parameter: "amplitude".to_string(),
+                value: self.amplitude.to_string(),
+            });
+        }
+        for (parameter, value) in [
+            ("leading_silence_ms", self.leading_silence_ms),
+            ("trailing_silence_ms", self.trailing_silence_ms),
+        ] {
+            if !(0.0..=Self::MAX_SILENCE_MS).contains(&value) {
+                return Err(SstvError::InvalidAudioParameter {
+                    parameter: parameter.to_string(),
+                    value: value.to_string(),
+                });
             }
         }
-        
         Ok(())
     }
+}
+
+/// 拉取式样本流
+///
+/// 由 [`SstvModulator::stream_image`] 创建，按需逐个扫描块生成样本，
+/// 内存占用只有一个扫描块的样本。
+///
+/// `fill_buffer` 和 `fill_buffer_f32` 直接返回生成错误。作为迭代器使用时无法返回错误，
+/// 出错后迭代结束，错误保存在 [`SampleStream::error`] 中；迭代结束后应检查它，
+/// 或用 [`SampleStream::is_finished`] 确认传输完整，否则得到的是被截断的传输。
+pub struct SampleStream {
+    mode: SstvMode,
+    image: RgbImage,
+    generator: SignalGenerator,
+    stage: StreamStage,
+    buffer: Vec<f32>,
+    position: usize,
+    // 迭代器遇到的生成错误
+    error: Option<SstvError>,
+}
+
+/// 样本流的生成阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamStage {
+    Header,
+    Line(u32),
+    Trailer,
+    Finished,
+}
+
+impl SampleStream {
+    fn new(mode: SstvMode, image: RgbImage, generator: SignalGenerator) -> Self {
+        Self {
+            mode,
+            image,
+            generator,
+            stage: StreamStage::Header,
+            buffer: Vec::new(),
+            position: 0,
+            error: None,
+        }
+    }
     
-    fn generate_robot36(&mut self, image: &RgbImage) -> Result<(), SstvError> {
-        let (width, height) = image.dimensions();
-        
-        for row in 0..height {
-            // 同步脉冲
-            self.write_tone(1200.0, 9.0)?;
-            // Porch脉冲
-            self.write_tone(1500.0, 3.0)?;
+    /// 填充16位整数输出缓冲区，返回写入的样本数（返回0表示传输结束）
+    pub fn fill_buffer(&mut self, out: &mut [i16]) -> Result<usize, SstvError> {
+        self.fill_with(out, audio::quantize_i16)
+    }
+    
+    /// 填充浮点输出缓冲区（-1.0 ~ 1.0），返回写入的样本数（返回0表示传输结束）
+    pub fn fill_buffer_f32(&mut self, out: &mut [f32]) -> Result<usize, SstvError> {
+        self.fill_with(out, |sample| sample)
+    }
+    
+    fn fill_with<T, F: Fn(f32) -> T>(&mut self, out: &mut [T], convert: F) -> Result<usize, SstvError> {
+        let mut written = 0;
+        while written < out.len() {
+            if self.position >= self.buffer.len() && !self.generate_next()? {
+                break;
+            }
             
-            if row % 2 == 0 {
-                // 偶数行亮度扫描
-                for col in 0..width {
-                    let y_value = self.get_y_value(image, col, row);
-                    let y_freq = 1500.0 + y_value * COLOR_FREQ_MULT;
-                    self.write_tone(y_freq, 0.275)?;
+            let available = &self.buffer[self.position..];
+            let count = available.len().min(out.len() - written);
+            for (slot, &sample) in out[written..written + count].iter_mut().zip(available) {
+                *slot = convert(sample);
+            }
+            self.position += count;
+            written += count;
+        }
+        Ok(written)
+    }
+    
+    /// 生成下一阶段的样本，返回是否还有数据
+    fn generate_next(&mut self) -> Result<bool, SstvError> {
+        self.buffer.clear();
+        self.position = 0;
+        
+        let line_count = self.mode.get_scan_line_count();
+        let next_stage = match self.stage {
+            StreamStage::Header => {
+                self.generator.generate_header(&mut self.buffer, self.mode)?;
+                if line_count > 0 { StreamStage::Line(0) } else { StreamStage::Trailer }
+            }
+            StreamStage::Line(line) => {
+                self.generator.generate_line(&mut self.buffer, self.mode, &self.image, line)?;
+                if line + 1 < line_count { StreamStage::Line(line + 1) } else { StreamStage::Trailer }
+            }
+            StreamStage::Trailer => {
+                self.generator.generate_trailer(&mut self.buffer)?;
+                StreamStage::Finished
+            }
+            StreamStage::Finished => return Ok(false),
+        };
+        self.stage = next_stage;
+        Ok(true)
+    }
+    
+    /// 是否已输出全部样本
+    pub fn is_finished(&self) -> bool {
+        self.stage == StreamStage::Finished && self.position >= self.buffer.len()
+    }
+    
+    /// 迭代时遇到的生成错误；有错误时迭代器提前结束，传输不完整
+    pub fn error(&self) -> Option<&SstvError> {
+        self.error.as_ref()
+    }
+    
+    /// 已生成的样本数
+    pub fn samples_generated(&self) -> usize {
+        self.generator.sample_count
+    }
+    
+    /// 获取采样率
+    pub fn sample_rate(&self) -> u32 {
+        self.generator.sample_rate
+    }
+    
+    /// 获取SSTV模式
+    pub fn mode(&self) -> SstvMode {
+        self.mode
+    }
+}
+
+impl Iterator for SampleStream {
+    type Item = i16;
+    
+    /// 生成出错时记录错误并结束迭代，见 [`SampleStream::error`]
+    fn next(&mut self) -> Option<i16> {
+        while self.position >= self.buffer.len() {
+            if self.error.is_some() {
+                return None;
+            }
+            match self.generate_next() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
                 }
-                
-                // 偶数分离脉冲
-                self.write_tone(1500.0, 4.5)?;
-                // Porch脉冲
-                self.write_tone(1900.0, 1.5)?;
-                
-                // 两行RY均值扫描
-                for col in 0..width {
-                    let ry1 = self.get_ry_value(image, col, row);
-                    let ry2 = if row + 1 < height {
-                        self.get_ry_value(image, col, row + 1)
-                    } else {
-                        ry1
-                    };
-                    let ry_avg = (ry1 + ry2) / 2.0;
-                    let ry_freq = 1500.0 + ry_avg * COLOR_FREQ_MULT;
-                    self.write_tone(ry_freq, 0.1375)?;
+            }
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(audio::quantize_i16(sample))
+    }
+}
+
+/// SSTV信号生成器
+///
+/// 负责把VIS码、扫描行和结束音转换为音调样本，并推送到任意输出端，
+/// 同时维护相位连续性和采样累积误差补偿状态。
+#[derive(Debug, Clone)]
+struct SignalGenerator {
+    sample_rate: u32,
+    // 音调频率映射
+    mapping: FrequencyMapping,
+    // 包络渐变和频率平滑
+    shaping: ToneShaping,
+    // 上一个像素音调结束时的瞬时频率（仅在连续像素之间平滑）
+    pixel_frequency: Option<f64>,
+    // 相位累加器振荡器（相位连续性）
+    nco: Nco,
+    // 样本数累积误差补偿
+    delta_length: f64,
+    // 已生成的样本数
+    sample_count: usize,
+    // 试运行：只推进相位、误差补偿和样本计数，不输出样本
+    dry_run: bool,
+    // 记录时间轴（仅在生成时间轴的试运行中启用）
+    recorder: Option<TimelineRecorder>,
+    // 幅度和首尾组成
+    framing: Framing,
+}
+
+impl SignalGenerator {
+    fn new(sample_rate: u32, mapping: FrequencyMapping, shaping: ToneShaping, sine_mode: SineMode) -> Self {
+        Self {
+            sample_rate,
+            mapping,
+            shaping,
+            pixel_frequency: None,
+            nco: Nco::new(sample_rate).with_mode(sine_mode),
+            delta_length: 0.0,
+            sample_count: 0,
+            dry_run: false,
+            recorder: None,
+            framing: Framing::default(),
+        }
+    }
+    
+    /// 重置相位连续性和误差补偿状态
+    fn reset(&mut self) {
+        self.nco.reset();
+        self.delta_length = 0.0;
+        self.sample_count = 0;
+        self.pixel_frequency = None;
+    }
+    
+    /// 生成传输头：开始静音和VIS码
+    fn generate_header<S: SampleSink + ?Sized>(&mut self, sink: &mut S, mode: SstvMode) -> Result<(), SstvError> {
+        // 添加开始静音
+        if self.framing.leading_silence_ms > 0.0 {
+            self.mark(SegmentKind::Silence, None, None, None);
+            self.write_tone(sink, 0.0, self.framing.leading_silence_ms)?;
+        }
+        
+        // 生成VIS码
+        if !self.framing.vis_header {
+            return Ok(());
+        }
+        if self.shaping.ramp_ms == 0.0 {
+            return self.generate_vis_code(sink, mode);
+        }
+        
+        // 开启包络渐变时，VIS码开头从静音淡入
+        let mut vis = Vec::new();
+        self.generate_vis_code(&mut vis, mode)?;
+        let ramp = self.ramp_samples().min(vis.len());
+        for (i, sample) in vis.iter_mut().take(ramp).enumerate() {
+            *sample *= raised_cosine(i as f64 / ramp as f64) as f32;
+        }
+        sink.write_samples(&vis)
+    }
+    
+    /// 生成单个扫描块
+    fn generate_line<S: SampleSink + ?Sized>(
+        &mut self,
+        sink: &mut S,
+        mode: SstvMode,
+        image: &RgbImage,
+        line: u32,
+    ) -> Result<(), SstvError> {
+        match mode {
+            SstvMode::ScottieDx => self.generate_scottie_dx_line(sink, image, line),
+            SstvMode::Robot36 => self.generate_robot36_line(sink, image, line),
+            SstvMode::Pd120 => self.generate_pd120_line(sink, image, line),
+            SstvMode::MartinM1 => self.generate_martin_m1_line(sink, image, line),
+        }
+    }
+    
+    /// 按时序模型试运行全部扫描块，记录每块开始时的生成器状态和样本数
+    ///
+    /// 试运行不计算正弦值，只推进相位累加器和误差补偿；结束后生成器处于最后一块之后的状态，
+    /// 与逐块实际生成完全一致。
+    fn plan_lines(&mut self, mode: SstvMode, image: &RgbImage) -> Result<Vec<LinePlan>, SstvError> {
+        let line_count = mode.get_scan_line_count();
+        let mut plan = Vec::with_capacity(line_count as usize);
+        let mut discard: Vec<f32> = Vec::new();
+        for line in 0..line_count {
+            let start = self.clone();
+            self.dry_run = true;
+            let result = self.generate_line(&mut discard, mode, image, line);
+            self.dry_run = false;
+            result?;
+            let len = self.sample_count - start.sample_count;
+            plan.push(LinePlan { line, start, len });
+        }
+        Ok(plan)
+    }
+    
+    /// 试运行整个传输并记录时间轴；各音调的样本数与实际生成完全一致
+    fn record_timeline(&self, mode: SstvMode, image: &RgbImage) -> Result<Timeline, SstvError> {
+        let mut generator = SignalGenerator::new(self.sample_rate, self.mapping, self.shaping, self.nco.mode());
+        generator.framing = self.framing;
+        generator.dry_run = true;
+        generator.recorder = Some(TimelineRecorder::default());
+        let mut discard: Vec<f32> = Vec::new();
+        generator.generate_header(&mut discard, mode)?;
+        for line in 0..mode.get_scan_line_count() {
+            generator.generate_line(&mut discard, mode, image, line)?;
+        }
+        generator.generate_trailer(&mut discard)?;
+        let recorder = generator.recorder.take().unwrap_or_default();
+        Ok(recorder.finish(self.sample_rate))
+    }
+    
+    /// 记录时间轴时从当前位置开始新的一段
+    fn mark(&mut self, kind: SegmentKind, line: Option<u32>, row: Option<u32>, channel: Option<ColorChannel>) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.begin(kind, line, row, channel, self.sample_count);
+        }
+    }
+    
+    /// 标记扫描块内的一段
+    fn mark_line(&mut self, kind: SegmentKind, line: u32, row: u32) {
+        self.mark(kind, Some(line), Some(row), None);
+    }
+    
+    /// 标记颜色扫描
+    fn mark_scan(&mut self, line: u32, row: u32, channel: ColorChannel) {
+        self.mark(SegmentKind::Scan, Some(line), Some(row), Some(channel));
+    }
+    
+    /// 生成传输尾：结束音和结束静音
+    fn generate_trailer<S: SampleSink + ?Sized>(&mut self, sink: &mut S) -> Result<(), SstvError> {
+        let trailing_silence_ms = self.framing.trailing_silence_ms;
+        if self.shaping.ramp_ms == 0.0 {
+            // 生成结束音
+            if self.framing.end_tones {
+                self.generate_end_tones(sink)?;
+            }
+            
+            // 添加结束静音
+            if trailing_silence_ms > 0.0 {
+                self.mark(SegmentKind::Silence, None, None, None);
+                self.write_tone(sink, 0.0, trailing_silence_ms)?;
+            }
+            return Ok(());
+        }
+        
+        // 开启包络渐变时，结束音末尾淡出到静音，结束静音严格为0
+        let mut tail = Vec::new();
+        if self.framing.end_tones {
+            self.generate_end_tones(&mut tail)?;
+        }
+        let ramp = self.ramp_samples().min(tail.len());
+        let start = tail.len() - ramp;
+        for (i, sample) in tail[start..].iter_mut().enumerate() {
+            *sample *= raised_cosine(1.0 - (i + 1) as f64 / ramp as f64) as f32;
+        }
+        sink.write_samples(&tail)?;
+        
+        if trailing_silence_ms == 0.0 {
+            return Ok(());
+        }
+        let mut silence = Vec::new();
+        self.mark(SegmentKind::Silence, None, None, None);
+        self.write_tone(&mut silence, 0.0, trailing_silence_ms)?;
+        silence.iter_mut().for_each(|s| *s = 0.0);
+        sink.write_samples(&silence)
+    }
+    
+    /// 包络渐变的样本数
+    fn ramp_samples(&self) -> usize {
+        (self.shaping.ramp_ms * self.sample_rate as f64 / 1000.0).round() as usize
+    }
+    
+    /// 写入像素音调：开启频率平滑时，瞬时频率从上一个像素平滑过渡到当前像素
+    ///
+    /// 只在相邻像素之间平滑，同步脉冲和分隔音的边沿不受影响；每个音调的样本数不变。
+    fn write_pixel_tone<S: SampleSink + ?Sized>(&mut self, sink: &mut S, frequency: f64, duration_ms: f64) -> Result<(), SstvError> {
+        let previous = self.pixel_frequency.take();
+        let smoothing = (self.shaping.frequency_smoothing_ms * self.sample_rate as f64 / 1000.0).round() as usize;
+        let previous = match previous {
+            Some(previous) if smoothing > 0 => previous,
+            _ => {
+                self.write_tone(sink, frequency, duration_ms)?;
+                if smoothing > 0 {
+                    self.pixel_frequency = Some(self.mapping.apply(frequency));
                 }
+                return Ok(());
+            }
+        };
+        
+        let target = self.mapping.apply(frequency);
+        let num_samples = self.advance_sample_count(duration_ms) as usize;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.tone(target, self.sample_count);
+        }
+        
+        // 过渡段逐样本更新频率控制字，其余部分按目标频率整段生成
+        let ramp = smoothing.min(num_samples);
+        let mut current = previous;
+        for i in 0..ramp {
+            current = previous + (target - previous) * raised_cosine((i + 1) as f64 / smoothing as f64);
+            let word = self.nco.frequency_word(current);
+            if self.dry_run {
+                self.nco.advance(word, 1);
             } else {
-                // 奇数行亮度扫描
-                for col in 0..width {
-                    let y_value = self.get_y_value(image, col, row);
-                    let y_freq = 1500.0 + y_value * COLOR_FREQ_MULT;
-                    self.write_tone(y_freq, 0.275)?;
-                }
-                
-                // 奇数分离脉冲
-                self.write_tone(2300.0, 4.5)?;
-                // Porch脉冲
-                self.write_tone(1900.0, 1.5)?;
-                
-                // 两行BY均值扫描
-                for col in 0..width {
-                    let by1 = self.get_by_value(image, col, row);
-                    let by2 = if row + 1 < height {
-                        self.get_by_value(image, col, row + 1)
-                    } else {
-                        by1
-                    };
-                    let by_avg = (by1 + by2) / 2.0;
-                    let by_freq = 1500.0 + by_avg * COLOR_FREQ_MULT;
-                    self.write_tone(by_freq, 0.1375)?;
-                }
+                sink.write_sample(self.nco.next_sample(word) * self.framing.amplitude)?;
             }
         }
+        if num_samples > ramp {
+            current = target;
+            self.write_samples_at(sink, target, num_samples - ramp)?;
+        }
+        
+        self.pixel_frequency = Some(current);
+        Ok(())
+    }
+    
+    /// 按时长推进样本计数（含累积误差补偿），返回本音调的样本数
+    fn advance_sample_count(&mut self, duration_ms: f64) -> u32 {
+        let mut num_samples = ((self.sample_rate as f64) * duration_ms / 1000.0) as u32;
+        self.delta_length += (self.sample_rate as f64) * duration_ms / 1000.0 - (num_samples as f64);
+        if self.delta_length >= 1.0 {
+            num_samples += self.delta_length as u32;
+            self.delta_length -= self.delta_length.floor();
+        }
+        self.sample_count += num_samples as usize;
+        num_samples
+    }
+
+    
+    fn generate_vis_code<S: SampleSink + ?Sized>(&mut self, sink: &mut S, mode: SstvMode) -> Result<(), SstvError> {
+        let vis_code = mode.get_vis_code();
+        
+        // 前导音序列
+        let preamble_tones = [
+            (1900.0, 100.0), (1500.0, 100.0), (1900.0, 100.0), (1500.0, 100.0),
+            (2300.0, 100.0), (1500.0, 100.0), (2300.0, 100.0), (1500.0, 100.0),
+        ];
+        
+        self.mark(SegmentKind::Preamble, None, None, None);
+        for (freq, duration) in &preamble_tones {
+            self.write_tone(sink, *freq, *duration)?;
+        }
+        
+        // VIS码引导音
+        let leader_tones = [
+            (SegmentKind::VisLeader, 1900.0, 300.0),
+            (SegmentKind::VisBreak, 1200.0, 10.0),
+            (SegmentKind::VisLeader, 1900.0, 300.0),
+            (SegmentKind::VisStartBit, 1200.0, 30.0),
+        ];
+        for (kind, freq, duration) in leader_tones {
+            self.mark(kind, None, None, None);
+            self.write_tone(sink, freq, duration)?;
+        }
+        
+        // VIS码7位数据位（小端序，从第6位到第0位）
+        for i in (0..7).rev() {
+            let bit = vis_code.chars().nth(i).unwrap();
+            let frequency = if bit == '1' { 1100.0 } else { 1300.0 };
+            self.mark(SegmentKind::VisBit(6 - i as u8), None, None, None);
+            self.write_tone(sink, frequency, 30.0)?;
+        }
+        
+        // 偶校验位
+        let ones_count = vis_code.chars().filter(|&c| c == '1').count();
+        let parity_freq = if ones_count % 2 == 0 { 1300.0 } else { 1100.0 };
+        self.mark(SegmentKind::VisParity, None, None, None);
+        self.write_tone(sink, parity_freq, 30.0)?;
+        
+        // 结束位
+        self.mark(SegmentKind::VisStopBit, None, None, None);
+        self.write_tone(sink, 1200.0, 30.0)?;
         
         Ok(())
     }
     
-    fn generate_pd120(&mut self, image: &RgbImage) -> Result<(), SstvError> {
+    fn generate_scottie_dx_line<S: SampleSink + ?Sized>(&mut self, sink: &mut S, image: &RgbImage, row: u32) -> Result<(), SstvError> {
+        let width = image.width();
+        
+        // 起始同步脉冲，仅第一行（C参考实现在此把相位归零，这里保持相位连续以避免咔嗒声）
+        if row == 0 {
+            self.mark_line(SegmentKind::Sync, row, row);
+            self.write_tone(sink, 1200.0, 9.0)?;
+        }
+        
+        // 分离脉冲（使用相位连续性）
+        self.mark_line(SegmentKind::Separator, row, row);
+        self.write_tone(sink, 1500.0, 1.5)?;
+        
+        // 绿色扫描
+        self.mark_scan(row, row, ColorChannel::Green);
+        for col in 0..width {
+            let pixel = image.get_pixel(col, row);
+            let green_freq = 1500.0 + (pixel[1] as f64) * COLOR_FREQ_MULT;
+            self.write_pixel_tone(sink, green_freq, 1.08)?;
+        }
+        
+        // 分离脉冲（使用相位连续性）
+        self.mark_line(SegmentKind::Separator, row, row);
+        self.write_tone(sink, 1500.0, 1.5)?;
+        
+        // 蓝色扫描
+        self.mark_scan(row, row, ColorChannel::Blue);
+        for col in 0..width {
+            let pixel = image.get_pixel(col, row);
+            let blue_freq = 1500.0 + (pixel[2] as f64) * COLOR_FREQ_MULT;
+            self.write_pixel_tone(sink, blue_freq, 1.08)?;
+        }
+        
+        // 同步脉冲与同步沿（使用相位连续性）
+        self.mark_line(SegmentKind::Sync, row, row);
+        self.write_tone(sink, 1200.0, 9.0)?;
+        self.mark_line(SegmentKind::Porch, row, row);
+        self.write_tone(sink, 1500.0, 1.5)?;
+        
+        // 红色扫描
+        self.mark_scan(row, row, ColorChannel::Red);
+        for col in 0..width {
+            let pixel = image.get_pixel(col, row);
+            let red_freq = 1500.0 + (pixel[0] as f64) * COLOR_FREQ_MULT;
+            self.write_pixel_tone(sink, red_freq, 1.08)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn generate_robot36_line<S: SampleSink + ?Sized>(&mut self, sink: &mut S, image: &RgbImage, row: u32) -> Result<(), SstvError> {
         let (width, height) = image.dimensions();
         
-        for row in (0..height).step_by(2) {
-            // 长同步脉冲
-            self.write_tone(1200.0, 20.0)?;
-            // Porch脉冲
-            self.write_tone(1500.0, 2.08)?;
-            
+        // 同步脉冲
+        self.mark_line(SegmentKind::Sync, row, row);
+        self.write_tone(sink, 1200.0, 9.0)?;
+        // Porch脉冲
+        self.mark_line(SegmentKind::Porch, row, row);
+        self.write_tone(sink, 1500.0, 3.0)?;
+        
+        if row.is_multiple_of(2) {
             // 偶数行亮度扫描
+            self.mark_scan(row, row, ColorChannel::Luma);
             for col in 0..width {
                 let y_value = self.get_y_value(image, col, row);
                 let y_freq = 1500.0 + y_value * COLOR_FREQ_MULT;
-                self.write_tone(y_freq, 0.19)?;
+                self.write_pixel_tone(sink, y_freq, 0.275)?;
             }
             
+            // 偶数分离脉冲
+            self.mark_line(SegmentKind::Separator, row, row);
+            self.write_tone(sink, 1500.0, 4.5)?;
+            // Porch脉冲
+            self.mark_line(SegmentKind::Porch, row, row);
+            self.write_tone(sink, 1900.0, 1.5)?;
+            
             // 两行RY均值扫描
+            self.mark_scan(row, row, ColorChannel::RedDifference);
             for col in 0..width {
                 let ry1 = self.get_ry_value(image, col, row);
                 let ry2 = if row + 1 < height {
@@ -712,10 +2156,26 @@ impl SstvModulator {
                 };
                 let ry_avg = (ry1 + ry2) / 2.0;
                 let ry_freq = 1500.0 + ry_avg * COLOR_FREQ_MULT;
-                self.write_tone(ry_freq, 0.19)?;
+                self.write_pixel_tone(sink, ry_freq, 0.1375)?;
+            }
+        } else {
+            // 奇数行亮度扫描
+            self.mark_scan(row, row, ColorChannel::Luma);
+            for col in 0..width {
+                let y_value = self.get_y_value(image, col, row);
+                let y_freq = 1500.0 + y_value * COLOR_FREQ_MULT;
+                self.write_pixel_tone(sink, y_freq, 0.275)?;
             }
             
+            // 奇数分离脉冲
+            self.mark_line(SegmentKind::Separator, row, row);
+            self.write_tone(sink, 2300.0, 4.5)?;
+            // Porch脉冲
+            self.mark_line(SegmentKind::Porch, row, row);
+            self.write_tone(sink, 1900.0, 1.5)?;
+            
             // 两行BY均值扫描
+            self.mark_scan(row, row, ColorChannel::BlueDifference);
             for col in 0..width {
                 let by1 = self.get_by_value(image, col, row);
                 let by2 = if row + 1 < height {
@@ -725,56 +2185,111 @@ impl SstvModulator {
                 };
                 let by_avg = (by1 + by2) / 2.0;
                 let by_freq = 1500.0 + by_avg * COLOR_FREQ_MULT;
-                self.write_tone(by_freq, 0.19)?;
-            }
-            
-            // 奇数行亮度扫描
-            if row + 1 < height {
-                for col in 0..width {
-                    let y_value = self.get_y_value(image, col, row + 1);
-                    let y_freq = 1500.0 + y_value * COLOR_FREQ_MULT;
-                    self.write_tone(y_freq, 0.19)?;
-                }
+                self.write_pixel_tone(sink, by_freq, 0.1375)?;
             }
         }
         
         Ok(())
     }
     
-    fn generate_martin_m1(&mut self, image: &RgbImage) -> Result<(), SstvError> {
+    fn generate_pd120_line<S: SampleSink + ?Sized>(&mut self, sink: &mut S, image: &RgbImage, line: u32) -> Result<(), SstvError> {
         let (width, height) = image.dimensions();
+        // 每个扫描块包含两行图像
+        let row = line * 2;
+        
+        // 长同步脉冲
+        self.mark_line(SegmentKind::Sync, line, row);
+        self.write_tone(sink, 1200.0, 20.0)?;
+        // Porch脉冲
+        self.mark_line(SegmentKind::Porch, line, row);
+        self.write_tone(sink, 1500.0, 2.08)?;
+        
+        // 偶数行亮度扫描
+        self.mark_scan(line, row, ColorChannel::Luma);
+        for col in 0..width {
+            let y_value = self.get_y_value(image, col, row);
+            let y_freq = 1500.0 + y_value * COLOR_FREQ_MULT;
+            self.write_pixel_tone(sink, y_freq, 0.19)?;
+        }
         
-        for row in 0..height {
-            // Martin M1标准时序参数（基于参考实现）
-            // 总时长：114.7秒，分辨率：320x256
-            // 扫描顺序：GBR（绿色-蓝色-红色）
-            // 每像素时间：457.6微秒
-            // 同步脉冲：1200Hz 4.862ms
-            // 颜色分隔符：1500Hz 0.572ms
-            
-            // 同步脉冲 + 颜色分隔符
-            self.write_tone(1200.0, 4.862)?;  // 同步脉冲
-            self.write_tone(1500.0, 0.572)?;  // 颜色分隔符
-            
-            // GBR扫描顺序：绿色-蓝色-红色（与参考代码一致）
-            for color_index in [1, 2, 0] {  // GBR顺序：绿色(1)-蓝色(2)-红色(0)
-                // 扫描当前颜色通道的所有像素
-                for col in 0..width {
-                    let pixel = image.get_pixel(col, row);
-                    let color_value = pixel[color_index] as f64;
-                    let freq = 1500.0 + color_value * COLOR_FREQ_MULT;
-                    self.write_tone(freq, 0.4576)?;  // 457.6微秒
-                }
-                
-                // 颜色通道之间的分隔符
-                self.write_tone(1500.0, 0.572)?;  // 颜色分隔符
+        // 两行RY均值扫描
+        self.mark_scan(line, row, ColorChannel::RedDifference);
+        for col in 0..width {
+            let ry1 = self.get_ry_value(image, col, row);
+            let ry2 = if row + 1 < height {
+                self.get_ry_value(image, col, row + 1)
+            } else {
+                ry1
+            };
+            let ry_avg = (ry1 + ry2) / 2.0;
+            let ry_freq = 1500.0 + ry_avg * COLOR_FREQ_MULT;
+            self.write_pixel_tone(sink, ry_freq, 0.19)?;
+        }
+        
+        // 两行BY均值扫描
+        self.mark_scan(line, row, ColorChannel::BlueDifference);
+        for col in 0..width {
+            let by1 = self.get_by_value(image, col, row);
+            let by2 = if row + 1 < height {
+                self.get_by_value(image, col, row + 1)
+            } else {
+                by1
+            };
+            let by_avg = (by1 + by2) / 2.0;
+            let by_freq = 1500.0 + by_avg * COLOR_FREQ_MULT;
+            self.write_pixel_tone(sink, by_freq, 0.19)?;
+        }
+        
+        // 奇数行亮度扫描
+        if row + 1 < height {
+            self.mark_scan(line, row + 1, ColorChannel::Luma);
+            for col in 0..width {
+                let y_value = self.get_y_value(image, col, row + 1);
+                let y_freq = 1500.0 + y_value * COLOR_FREQ_MULT;
+                self.write_pixel_tone(sink, y_freq, 0.19)?;
             }
         }
         
         Ok(())
     }
     
-    fn generate_end_tones(&mut self) -> Result<(), SstvError> {
+    fn generate_martin_m1_line<S: SampleSink + ?Sized>(&mut self, sink: &mut S, image: &RgbImage, row: u32) -> Result<(), SstvError> {
+        let width = image.width();
+        
+        // Martin M1标准时序参数（基于参考实现）
+        // 总时长：114.7秒，分辨率：320x256
+        // 扫描顺序：GBR（绿色-蓝色-红色）
+        // 每像素时间：457.6微秒
+        // 同步脉冲：1200Hz 4.862ms
+        // 颜色分隔符：1500Hz 0.572ms
+        
+        // 同步脉冲 + 颜色分隔符
+        self.mark_line(SegmentKind::Sync, row, row);
+        self.write_tone(sink, 1200.0, 4.862)?;  // 同步脉冲
+        self.mark_line(SegmentKind::Separator, row, row);
+        self.write_tone(sink, 1500.0, 0.572)?;  // 颜色分隔符
+        
+        // GBR扫描顺序：绿色-蓝色-红色（与参考代码一致）
+        for (color_index, channel) in [(1, ColorChannel::Green), (2, ColorChannel::Blue), (0, ColorChannel::Red)] {  // GBR顺序：绿色(1)-蓝色(2)-红色(0)
+            // 扫描当前颜色通道的所有像素
+            self.mark_scan(row, row, channel);
+            for col in 0..width {
+                let pixel = image.get_pixel(col, row);
+                let color_value = pixel[color_index] as f64;
+                let freq = 1500.0 + color_value * COLOR_FREQ_MULT;
+                self.write_pixel_tone(sink, freq, 0.4576)?;  // 457.6微秒
+            }
+            
+            // 颜色通道之间的分隔符
+            self.mark_line(SegmentKind::Separator, row, row);
+            self.write_tone(sink, 1500.0, 0.572)?;  // 颜色分隔符
+        }
+        
+        Ok(())
+    }
+    
+    fn generate_end_tones<S: SampleSink + ?Sized>(&mut self, sink: &mut S) -> Result<(), SstvError> {
+        self.mark(SegmentKind::EndTone, None, None, None);
         let end_tones = [
             (1500.0, 500.0),
             (1900.0, 100.0),
@@ -784,7 +2299,7 @@ impl SstvModulator {
         ];
         
         for (freq, duration) in &end_tones {
-            self.write_tone(*freq, *duration)?;
+            self.write_tone(sink, *freq, *duration)?;
         }
         
         Ok(())
@@ -818,97 +2333,137 @@ impl SstvModulator {
         128.0 + (0.003906 * ((-37.945 * r) + (-74.494 * g) + (112.439 * b)))
     }
     
-    // 写入音调，严格按照PDF文章中的C代码实现相位连续性算法
-    fn write_tone(&mut self, frequency: f64, duration_ms: f64) -> Result<(), SstvError> {
-        // 计算样本数（与C代码完全一致）
-        let mut num_samples = ((self.sample_rate as f64) * duration_ms / 1000.0) as u32;
-        
-        // 累积误差补偿（与C代码完全一致）
-        self.delta_length += (self.sample_rate as f64) * duration_ms / 1000.0 - (num_samples as f64);
-        if self.delta_length >= 1.0 {
-            num_samples += self.delta_length as u32;
-            self.delta_length -= self.delta_length.floor();
-        }
-        
-        // 计算相位连续性的初始相位（严格按照PDF中的C代码）
-        let sign_older_cos = if self.older_cos >= 0.0 { 1.0_f64 } else { -1.0_f64 };
-        let abs_sign_diff = (sign_older_cos - 1.0_f64).abs() / 2.0_f64;
-        let phi = sign_older_cos * self.older_data.asin() + abs_sign_diff * PI;
-        
-        // 生成音频样本（修正相位计算）
-        for i in 0..num_samples {
-            let phase = 2.0 * PI * frequency * (i as f64) / (self.sample_rate as f64) + phi;
-            let sample_value = phase.sin();
-            let sample = (32767.0 * sample_value) as i16;
-            self.audio_processor.add_sample(sample);
+    // 写入音调：样本数按C参考实现做累积误差补偿，相位由振荡器累加器延续，音调边界严格连续
+    fn write_tone<S: SampleSink + ?Sized>(&mut self, sink: &mut S, frequency: f64, duration_ms: f64) -> Result<(), SstvError> {
+        let frequency = self.mapping.apply(frequency);
+        self.pixel_frequency = None;
+        
+        let num_samples = self.advance_sample_count(duration_ms) as usize;
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.tone(frequency, self.sample_count);
         }
-        
-        // 更新相位连续性变量（修正相位计算）
-        let final_phase = 2.0 * PI * frequency * (num_samples as f64) / (self.sample_rate as f64) + phi;
-        self.older_data = final_phase.sin();
-        self.older_cos = final_phase.cos();
-        
-        Ok(())
+        self.write_samples_at(sink, frequency, num_samples)
     }
     
-    // 带指定相位的音调写入函数
-    fn write_tone_with_phase(&mut self, frequency: f64, duration_ms: f64, phi: f64) -> Result<(), SstvError> {
-        // 计算样本数
-        let mut num_samples = ((self.sample_rate as f64) * duration_ms / 1000.0) as u32;
-        
-        // 累积误差补偿
-        self.delta_length += (self.sample_rate as f64) * duration_ms / 1000.0 - (num_samples as f64);
-        if self.delta_length >= 1.0 {
-            num_samples += self.delta_length as u32;
-            self.delta_length -= self.delta_length.floor();
+    /// 以固定频率生成指定数量的样本，分块写入输出端
+    fn write_samples_at<S: SampleSink + ?Sized>(&mut self, sink: &mut S, frequency: f64, num_samples: usize) -> Result<(), SstvError> {
+        let word = self.nco.frequency_word(frequency);
+        if self.dry_run {
+            self.nco.advance(word, num_samples);
+            return Ok(());
         }
         
-        // 生成音频样本（修正相位计算）
-        for i in 0..num_samples {
-            let phase = 2.0 * PI * frequency * (i as f64) / (self.sample_rate as f64) + phi;
-            let sample_value = phase.sin();
-            let sample = (32767.0 * sample_value) as i16;
-            self.audio_processor.add_sample(sample);
+        let mut block = [0.0f32; TONE_BLOCK];
+        let mut remaining = num_samples;
+        while remaining > 0 {
+            let count = remaining.min(TONE_BLOCK);
+            self.nco.fill(word, &mut block[..count]);
+            if self.framing.amplitude != 1.0 {
+                block[..count].iter_mut().for_each(|sample| *sample *= self.framing.amplitude);
+            }
+            sink.write_samples(&block[..count])?;
+            remaining -= count;
         }
-        
-        // 更新相位连续性变量（修正相位计算）
-        let final_phase = 2.0 * PI * frequency * (num_samples as f64) / (self.sample_rate as f64) + phi;
-        self.older_data = final_phase.sin();
-        self.older_cos = final_phase.cos();
-        
         Ok(())
     }
-    
-    // 使用相位连续性的音调写入函数（严格按照PDF中的公式）
-    fn write_tone_with_continuous_phase(&mut self, frequency: f64, duration_ms: f64) -> Result<(), SstvError> {
-        // 计算相位连续性的相位（严格按照PDF中的公式）
-        let sign_older_cos = if self.older_cos >= 0.0 { 1.0_f64 } else { -1.0_f64 };
-        let abs_sign_diff = (sign_older_cos - 1.0_f64).abs() / 2.0_f64;
-        let phi = sign_older_cos * self.older_data.asin() + abs_sign_diff * PI;
-        
-        self.write_tone_with_phase(frequency, duration_ms, phi)
-    }
-    
-    pub fn export_wav<P: AsRef<Path>>(&self, filename: P) -> Result<(), SstvError> {
-        let mut writer = WavWriter::new(filename, self.sample_rate)?;
-        writer.write_samples(self.audio_processor.get_samples())?;
-        writer.finalize()?;
+}
+
+/// 单个扫描块的生成计划：开始时的生成器状态和样本数
+struct LinePlan {
+    line: u32,
+    start: SignalGenerator,
+    len: usize,
+}
+
+/// 写入预分配切片的输出端，供并行生成扫描块使用
+struct SliceSink<'a> {
+    buffer: &'a mut [f32],
+    position: usize,
+}
+
+impl SampleSink for SliceSink<'_> {
+    fn write_sample(&mut self, sample: f32) -> Result<(), SstvError> {
+        self.buffer[self.position] = sample;
+        self.position += 1;
         Ok(())
     }
     
-    pub fn get_samples(&self) -> &[i16] {
-        self.audio_processor.get_samples()
-    }
-    
-    pub fn get_mode(&self) -> SstvMode {
-        self.mode
+    fn write_samples(&mut self, samples: &[f32]) -> Result<(), SstvError> {
+        let end = self.position + samples.len();
+        self.buffer[self.position..end].copy_from_slice(samples);
+        self.position = end;
+        Ok(())
     }
-    
-    pub fn get_sample_rate(&self) -> u32 {
-        self.sample_rate
+}
+
+/// 按计划并行生成扫描块，按顺序写入输出端
+///
+/// 每批扫描块写入一块预分配的缓冲区（约 `PARALLEL_BATCH_SAMPLES` 个样本），各块从计划中的
+/// 状态独立生成，输出与串行生成逐样本一致，内存占用与图像大小无关。
+fn render_lines_parallel<S: SampleSink + ?Sized>(
+    plan: &[LinePlan],
+    mode: SstvMode,
+    image: &RgbImage,
+    sink: &mut S,
+    cancellation: Option<&CancellationToken>,
+    report: &mut dyn FnMut(u32, usize),
+) -> Result<(), SstvError> {
+    let mut buffer = Vec::new();
+    let mut first = 0;
+    while first < plan.len() {
+        // 凑够一批样本（至少一个扫描块）
+        let mut last = first;
+        let mut total = 0;
+        while last < plan.len() && (last == first || total + plan[last].len <= PARALLEL_BATCH_SAMPLES) {
+            total += plan[last].len;
+            last += 1;
+        }
+        let batch = &plan[first..last];
+        
+        buffer.clear();
+        buffer.resize(total, 0.0f32);
+        let mut slices = Vec::with_capacity(batch.len());
+        let mut rest = buffer.as_mut_slice();
+        for entry in batch {
+            let (head, tail) = rest.split_at_mut(entry.len);
+            slices.push(head);
+            rest = tail;
+        }
+        
+        batch
+            .par_iter()
+            .zip(slices.into_par_iter())
+            .try_for_each(|(entry, slice)| {
+                if let Some(token) = cancellation {
+                    token.check()?;
+                }
+                let mut generator = entry.start.clone();
+                let mut out = SliceSink { buffer: slice, position: 0 };
+                generator.generate_line(&mut out, mode, image, entry.line)?;
+                debug_assert_eq!(out.position, entry.len);
+                Ok::<(), SstvError>(())
+            })?;
+        
+        sink.write_samples(&buffer)?;
+        for entry in batch {
+            report(entry.line + 1, entry.start.sample_count + entry.len);
+        }
+        first = last;
     }
+    Ok(())
+}
+
+// 并行生成时每批的样本数（约4MB）
+const PARALLEL_BATCH_SAMPLES: usize = 1 << 20;
+
+// 升余弦渐变：x从0到1时增益从0平滑升到1
+fn raised_cosine(x: f64) -> f64 {
+    0.5 - 0.5 * (PI * x.clamp(0.0, 1.0)).cos()
 }
 
+// 音调分块生成的样本数
+const TONE_BLOCK: usize = 256;
+
 // 颜色频率乘数常量（与C实现完全一致）
 const COLOR_FREQ_MULT: f64 = 3.1372549;
 
@@ -917,4 +2472,672 @@ impl Drop for SstvModulator {
         // 确保在对象销毁时清理所有内存
         self.clear_memory();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_image() -> DynamicImage {
+        let image = RgbImage::from_fn(64, 48, |x, y| {
+            image::Rgb([(x * 4) as u8, (y * 5) as u8, ((x + y) * 2) as u8])
+        });
+        DynamicImage::ImageRgb8(image)
+    }
+
+    const ALL_MODES: [SstvMode; 4] = [SstvMode::ScottieDx, SstvMode::Robot36, SstvMode::Pd120, SstvMode::MartinM1];
+
+    #[test]
+    fn test_stream_matches_modulate_image() {
+        let image = test_image();
+        for mode in ALL_MODES {
+            let mut modulator = SstvModulator::new(mode).with_sample_rate(8000).unwrap();
+            let expected = modulator.modulate_image(&image).unwrap();
+
+            let mut stream = modulator.stream_image(&image).unwrap();
+            let mut pulled = Vec::with_capacity(expected.len());
+            let mut buffer = [0i16; 511];
+            loop {
+                let count = stream.fill_buffer(&mut buffer).unwrap();
+                if count == 0 {
+                    break;
+                }
+                pulled.extend_from_slice(&buffer[..count]);
+            }
+            assert!(stream.is_finished());
+            assert_eq!(pulled, expected, "{:?}", mode);
+
+            let mut stream = modulator.stream_image(&image).unwrap();
+            let iterated: Vec<i16> = stream.by_ref().collect();
+            assert_eq!(iterated, expected, "{:?}", mode);
+            assert!(stream.is_finished() && stream.error().is_none());
+        }
+    }
+
+    #[test]
+    fn test_modulate_image_to_sink() {
+        let image = test_image();
+        let mut modulator = SstvModulator::new(SstvMode::Robot36).with_sample_rate(8000).unwrap();
+        let expected = modulator.modulate_image(&image).unwrap();
+
+        let mut sink: Vec<i16> = Vec::new();
+        let count = modulator.modulate_image_to(&image, &mut sink).unwrap();
+        assert_eq!(count, expected.len());
+        assert_eq!(sink, expected);
+    }
+
+    #[test]
+    fn test_frequency_mapping_tones() {
+        // 用正向过零次数估计稳态音调的频率
+        fn measure(samples: &[f32], sample_rate: f64) -> f64 {
+            let crossings = samples.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+            crossings as f64 * sample_rate / samples.len() as f64
+        }
+
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 48, image::Rgb([0, 0, 0])));
+        let mut plain = SstvModulator::new(SstvMode::MartinM1).with_sample_rate(48000).unwrap();
+        plain.modulate_image(&image).unwrap();
+
+        // 前导音第一段是100ms的1900Hz
+        let start = (0.2 * 48000.0) as usize;
+        let leader = start + 400..start + 4400;
+        assert!((measure(&plain.get_samples()[leader.clone()], 48000.0) - 1900.0).abs() < 15.0);
+
+        let mut shifted = SstvModulator::new(SstvMode::MartinM1)
+            .with_frequency_mapping(FrequencyMapping::offset(-120.0))
+            .with_sample_rate(48000).unwrap();
+        shifted.modulate_image(&image).unwrap();
+        assert_eq!(shifted.get_samples().len(), plain.get_samples().len());
+        assert!((measure(&shifted.get_samples()[leader.clone()], 48000.0) - 1780.0).abs() < 15.0);
+
+        // 以1800Hz镜像：1900Hz -> 1700Hz，开头静音保持为0
+        let mut inverted = SstvModulator::new(SstvMode::MartinM1)
+            .with_sample_rate(48000).unwrap()
+            .with_frequency_mapping(FrequencyMapping::mirror(1800.0));
+        inverted.modulate_image(&image).unwrap();
+        assert!((measure(&inverted.get_samples()[leader], 48000.0) - 1700.0).abs() < 15.0);
+        assert!(inverted.get_samples()[..start].iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_frequency_mapping_validation() {
+        let image = test_image();
+        let mut modulator = SstvModulator::new(SstvMode::Pd120)
+            .with_sample_rate(6000).unwrap()
+            .with_frequency_mapping(FrequencyMapping::offset(800.0));
+        assert!(modulator.modulate_image(&image).is_err());
+        assert!(FrequencyMapping::mirror(500.0).validate(48000).is_err());
+        assert!(FrequencyMapping::mirror(1900.0).with_offset(100.0).validate(8000).is_ok());
+    }
+
+    #[test]
+    fn test_tone_shaping_keeps_timing() {
+        let image = test_image();
+        let shaping = ToneShaping::new().with_ramps(5.0).with_frequency_smoothing(0.2);
+        for mode in ALL_MODES {
+            let mut plain = SstvModulator::new(mode).with_sample_rate(8000).unwrap();
+            let mut shaped = SstvModulator::new(mode).with_sample_rate(8000).unwrap().with_tone_shaping(shaping);
+            let plain_len = plain.modulate_image(&image).unwrap().len();
+            let expected = shaped.modulate_image(&image).unwrap();
+            assert_eq!(expected.len(), plain_len, "{:?}", mode);
+            assert_eq!(shaped.transmission_samples().unwrap(), plain_len, "{:?}", mode);
+            assert_eq!(plain.transmission_samples().unwrap(), plain_len, "{:?}", mode);
+
+            // 流式输出与一次性输出一致
+            let streamed: Vec<i16> = shaped.stream_image(&image).unwrap().collect();
+            assert!(streamed == expected, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_no_phase_discontinuity_at_tone_boundaries() {
+        // 相位连续时相邻样本之差不超过最高音调的单步增量 2·sin(π·f/fs)
+        let image = test_image();
+        let sample_rate = 22050;
+        let bound = 2.0 * (PI * 2300.0 / sample_rate as f64).sin() as f32 + 1e-5;
+        for sine_mode in [SineMode::Exact, SineMode::LookupTable] {
+            for mode in ALL_MODES {
+                let mut modulator = SstvModulator::new(mode)
+                    .with_sample_rate(sample_rate).unwrap()
+                    .with_sine_mode(sine_mode);
+                modulator.modulate_image(&image).unwrap();
+                let samples = modulator.get_samples();
+                let worst = samples
+                    .windows(2)
+                    .enumerate()
+                    .map(|(i, w)| (i, (w[1] - w[0]).abs()))
+                    .fold((0, 0.0f32), |a, b| if b.1 > a.1 { b } else { a });
+                assert!(worst.1 <= bound, "{:?}/{:?}: jump {} at sample {}", mode, sine_mode, worst.1, worst.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sine_modes_agree() {
+        let image = test_image();
+        let mut exact = SstvModulator::new(SstvMode::Pd120).with_sample_rate(11025).unwrap();
+        let mut table = SstvModulator::new(SstvMode::Pd120)
+            .with_sample_rate(11025).unwrap()
+            .with_sine_mode(SineMode::LookupTable);
+        assert_eq!(table.get_sine_mode(), SineMode::LookupTable);
+        exact.modulate_image(&image).unwrap();
+        let first = table.modulate_image(&image).unwrap();
+        assert_eq!(exact.get_samples().len(), table.get_samples().len());
+        let max_error = exact
+            .get_samples()
+            .iter()
+            .zip(table.get_samples())
+            .fold(0.0f32, |m, (a, b)| m.max((a - b).abs()));
+        assert!(max_error < 1e-6, "max error {}", max_error);
+
+        // 查表模式重复生成逐位一致，改变采样率后仍保留设置
+        assert_eq!(table.modulate_image(&image).unwrap(), first);
+        let table = table.with_sample_rate(8000).unwrap();
+        assert_eq!(table.get_sine_mode(), SineMode::LookupTable);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        // 宽图像使每批只含少量扫描块，覆盖分批和批内并行
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(640, 480, |x, y| {
+            image::Rgb([(x ^ y) as u8, (x * 3) as u8, (y * 7) as u8])
+        }));
+        let shaping = ToneShaping::new().with_ramps(5.0).with_frequency_smoothing(0.3);
+        for mode in ALL_MODES {
+            for (shaping, sine_mode) in [(ToneShaping::default(), SineMode::Exact), (shaping, SineMode::LookupTable)] {
+                let mut serial = SstvModulator::new(mode)
+                    .with_sample_rate(8000).unwrap()
+                    .with_tone_shaping(shaping)
+                    .with_sine_mode(sine_mode)
+                    .with_parallel_rendering(false);
+                let mut parallel = SstvModulator::new(mode)
+                    .with_sample_rate(8000).unwrap()
+                    .with_tone_shaping(shaping)
+                    .with_sine_mode(sine_mode);
+                assert!(parallel.get_parallel_rendering());
+                serial.modulate_image(&image).unwrap();
+                // 固定多线程线程池，单核机器上也走并行路径
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+                pool.install(|| parallel.modulate_image(&image)).unwrap();
+                assert!(serial.get_samples() == parallel.get_samples(), "{:?}/{:?}", mode, sine_mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_progress_and_cancellation() {
+        use std::sync::{Arc, Mutex};
+        let image = test_image();
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        for parallel in [false, true] {
+            // 每个扫描块回调一次，最后一次的时长等于图像结束时的样本数
+            let reports = Arc::new(Mutex::new(Vec::new()));
+            let sink = reports.clone();
+            let mut modulator = SstvModulator::new(SstvMode::Robot36)
+                .with_sample_rate(8000).unwrap()
+                .with_parallel_rendering(parallel)
+                .with_progress(move |progress| sink.lock().unwrap().push(*progress));
+            pool.install(|| modulator.modulate_image(&image)).unwrap();
+            let reports = reports.lock().unwrap();
+            assert_eq!(reports.len(), 240);
+            assert!(reports.iter().enumerate().all(|(i, p)| p.lines_done == i as u32 + 1 && p.total_lines == 240));
+            assert!(reports.windows(2).all(|pair| pair[1].elapsed > pair[0].elapsed));
+            assert_eq!(reports[239].percent(), 100.0);
+            // 图像数据在第1.91秒开始，每块150ms
+            assert!((reports[0].elapsed - 2.06).abs() < 0.01, "{}", reports[0].elapsed);
+            assert!((reports[239].elapsed - 37.91).abs() < 0.01, "{}", reports[239].elapsed);
+
+            // 在回调中取消，过半后停止；48kHz下并行生成分为两批，第二批开始前检测到取消
+            let token = CancellationToken::new();
+            let trigger = token.clone();
+            let mut modulator = SstvModulator::new(SstvMode::Robot36)
+                .with_sample_rate(48000).unwrap()
+                .with_parallel_rendering(parallel)
+                .with_cancellation(token)
+                .with_progress(move |progress| {
+                    if progress.percent() >= 50.0 {
+                        trigger.cancel();
+                    }
+                });
+            let result = pool.install(|| modulator.modulate_image(&image));
+            assert!(matches!(result, Err(SstvError::Cancelled)), "{:?}", result.err());
+            assert!(modulator.get_cancellation().unwrap().is_cancelled());
+        }
+
+        // 已取消的令牌在第一个扫描块之前就中止
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut modulator = SstvModulator::new(SstvMode::Robot36).with_sample_rate(8000).unwrap().with_cancellation(token);
+        let mut sink: Vec<f32> = Vec::new();
+        assert!(matches!(modulator.modulate_image_to(&image, &mut sink), Err(SstvError::Cancelled)));
+        assert!(sink.len() < 2 * 8000);
+    }
+
+    #[test]
+    fn test_envelope_ramps() {
+        let image = test_image();
+        let mut modulator = SstvModulator::new(SstvMode::Robot36)
+            .with_sample_rate(8000).unwrap()
+            .with_tone_shaping(ToneShaping::new().with_ramps(10.0));
+        modulator.modulate_image(&image).unwrap();
+        let samples = modulator.get_samples();
+
+        // 开始静音200ms后淡入：前几个样本接近0，10ms后恢复满幅
+        let start = 1600;
+        assert!(samples[start..start + 4].iter().all(|s| s.abs() < 0.01));
+        let settled = samples[start + 80..start + 200].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(settled > 0.95);
+
+        // 结束音淡出到0，之后的200ms静音严格为0
+        let silence_start = samples.len() - 1600;
+        assert!(samples[silence_start..].iter().all(|&s| s == 0.0));
+        assert!(samples[silence_start - 4..silence_start].iter().all(|s| s.abs() < 0.01));
+    }
+
+    #[test]
+    fn test_frequency_smoothing_reduces_splatter() {
+        // 黑白交替的像素产生最大的频率跳变
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(64, 48, |x, _| {
+            if x % 2 == 0 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) }
+        }));
+        let out_of_band = |shaping: ToneShaping| {
+            let mut modulator = SstvModulator::new(SstvMode::MartinM1)
+                .with_sample_rate(22050).unwrap()
+                .with_tone_shaping(shaping);
+            modulator.modulate_image(&image).unwrap();
+            let mut samples = modulator.get_samples().to_vec();
+            audio::filters::FirFilter::highpass(3500.0, 500.0, 22050).unwrap().apply_aligned(&mut samples);
+            audio::utils::calculate_rms(&samples)
+        };
+
+        let plain = out_of_band(ToneShaping::new());
+        let smoothed = out_of_band(ToneShaping::new().with_frequency_smoothing(0.5));
+        assert!(smoothed < plain * 0.5, "{} vs {}", smoothed, plain);
+    }
+
+    #[test]
+    fn test_tone_shaping_validation() {
+        let image = test_image();
+        let mut modulator = SstvModulator::new(SstvMode::Robot36).with_tone_shaping(ToneShaping::new().with_ramps(500.0));
+        assert!(modulator.modulate_image(&image).is_err());
+        assert!(ToneShaping::new().with_frequency_smoothing(-1.0).validate().is_err());
+        assert!(ToneShaping::new().with_ramps(20.0).with_frequency_smoothing(0.3).validate().is_ok());
+    }
+
+    #[test]
+    fn test_timeline_matches_samples() {
+        let image = test_image();
+        let shaping = ToneShaping::new().with_ramps(5.0).with_frequency_smoothing(0.3);
+        for mode in ALL_MODES {
+            let mut modulator = SstvModulator::new(mode)
+                .with_sample_rate(11025).unwrap()
+                .with_tone_shaping(shaping)
+                .with_frequency_mapping(FrequencyMapping::offset(50.0));
+            modulator.modulate_image(&image).unwrap();
+            let timeline = modulator.get_timeline().unwrap();
+            let segments = timeline.segments();
+
+            // 各段首尾相接，覆盖全部样本
+            assert_eq!(segments[0].start, 0);
+            assert!(segments.windows(2).all(|pair| pair[0].end() == pair[1].start));
+            assert_eq!(timeline.total_samples(), modulator.get_samples().len());
+            assert_eq!(timeline.line_starts().len(), mode.get_scan_line_count() as usize);
+
+            // 开头200ms静音和800ms前导音之后是VIS码
+            assert_eq!(timeline.vis_start(), Some(11025));
+            let code = segments
+                .iter()
+                .filter_map(|s| match s.kind {
+                    SegmentKind::VisBit(bit) => Some(((s.min_frequency == 1150.0) as u8) << bit),
+                    _ => None,
+                })
+                .sum::<u8>();
+            assert_eq!(code, u8::from_str_radix(mode.get_vis_code(), 2).unwrap());
+
+            let syncs: Vec<_> = segments.iter().filter(|s| s.kind == SegmentKind::Sync).collect();
+            assert!(syncs.iter().all(|s| s.min_frequency == 1250.0 && s.max_frequency == 1250.0));
+            let scans = segments.iter().filter(|s| s.kind == SegmentKind::Scan);
+            assert!(scans.clone().all(|s| s.min_frequency >= 1550.0 && s.max_frequency <= 2350.0 && s.channel.is_some()));
+            let expected_scans = match mode {
+                SstvMode::Robot36 => 2 * 240,
+                SstvMode::Pd120 => 4 * 248,
+                _ => 3 * 256,
+            };
+            assert_eq!(scans.count(), expected_scans);
+
+            // 同步脉冲处的样本确实是同步频率：相邻过零点间距约为半个1250Hz周期
+            let sync = syncs[syncs.len() / 2];
+            let samples = &modulator.get_samples()[sync.start..sync.end()];
+            let crossings = samples.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+            let expected = 2.0 * 1250.0 * sync.length as f64 / 11025.0;
+            assert!((crossings as f64 - expected).abs() <= 2.0, "{:?} {} {}", mode, crossings, expected);
+        }
+    }
+
+    #[test]
+    fn test_wav_cue_markers() {
+        let image = test_image();
+        let path = std::env::temp_dir().join(format!("sstv_cues_{}.wav", std::process::id()));
+        let mut modulator = SstvModulator::new(SstvMode::Robot36)
+            .with_sample_rate(8000).unwrap()
+            .with_output_format(PcmFormat::Int24)
+            .with_cue_markers(true);
+        let count = modulator.encode_to_file(&image, &path).unwrap();
+
+        // 文件仍是合法WAV，RIFF长度与文件大小一致
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.len() as usize, count);
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize, bytes.len() - 8);
+
+        let cue = bytes.windows(4).position(|w| w == b"cue ").unwrap();
+        let points = u32::from_le_bytes(bytes[cue + 8..cue + 12].try_into().unwrap());
+        assert_eq!(points, 241);
+        let timeline = modulator.get_timeline().unwrap();
+        let offset = |point: usize| {
+            let at = cue + 12 + point * 24 + 20;
+            u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+        };
+        assert_eq!(offset(0), timeline.vis_start().unwrap());
+        assert_eq!(offset(240), timeline.line_starts()[239]);
+        assert!(bytes.windows(8).any(|w| w == b"Line 239"));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_wav_metadata_round_trip() {
+        let image = test_image();
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("sstv_metadata_{}.wav", std::process::id()));
+        let plain_path = dir.join(format!("sstv_metadata_plain_{}.wav", std::process::id()));
+        let mut modulator = SstvModulator::new(SstvMode::MartinM1)
+            .with_sample_rate(11025).unwrap()
+            .with_wav_metadata(true)
+            .with_callsign("VK7KSM")
+            .with_cue_markers(true);
+        let count = modulator.encode_to_file(&image, &path).unwrap();
+
+        let metadata = WavMetadata::read_wav(&path).unwrap().unwrap();
+        assert_eq!(Some(&metadata), modulator.get_wav_metadata());
+        assert_eq!(metadata.mode, SstvMode::MartinM1);
+        assert_eq!(metadata.vis_code, 44);
+        assert_eq!(metadata.sample_rate, 11025);
+        assert_eq!(metadata.callsign.as_deref(), Some("VK7KSM"));
+        assert_eq!(metadata.image_sha256, Some(crate::metadata::image_sha256(&image)));
+        assert!((chrono::Utc::now() - metadata.timestamp).num_seconds() < 60);
+
+        let info = crate::metadata::read_wav_info(&path).unwrap();
+        assert!(info.contains(&("IART".to_string(), "VK7KSM".to_string())));
+        assert!(info.iter().any(|(id, text)| id == "ICMT" && text.starts_with("mode=MartinM1 vis=44 rate=11025 sha256=")));
+
+        // 追加的块不影响读取音频
+        assert_eq!(hound::WavReader::open(&path).unwrap().len() as usize, count);
+        let mut reader = crate::AudioReader::wav(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut samples = Vec::new();
+        while reader.read_channel(0, 65536, &mut samples).unwrap() > 0 {}
+        assert_eq!(samples.len(), count);
+
+        // 未开启时不写入
+        let mut plain = SstvModulator::new(SstvMode::MartinM1).with_sample_rate(11025).unwrap().with_callsign("VK7KSM");
+        plain.modulate_image(&image).unwrap();
+        assert!(plain.get_wav_metadata().is_none());
+        plain.export_wav(&plain_path).unwrap();
+        assert!(WavMetadata::read_wav(&plain_path).unwrap().is_none());
+
+        std::fs::remove_file(path).ok();
+        std::fs::remove_file(plain_path).ok();
+    }
+
+    #[test]
+    fn test_sample_rate_limits() {
+        for mode in ALL_MODES {
+            let min_rate = mode.get_min_sample_rate();
+            assert!(min_rate >= crate::MIN_SAMPLE_RATE);
+            assert!(mode.get_shortest_pixel_ms() * min_rate as f64 / 1000.0 >= 1.0);
+            assert!(mode.get_timing_jitter(min_rate).samples_per_pixel >= 1.0);
+            let default_rate = mode.get_default_sample_rate();
+            assert!(default_rate >= min_rate && mode.validate_sample_rate(default_rate).is_ok());
+            assert_eq!(SstvModulator::new(mode).get_sample_rate(), default_rate);
+            assert!(mode.validate_sample_rate(min_rate).is_ok());
+            assert!(mode.validate_sample_rate(crate::MAX_SAMPLE_RATE).is_ok());
+            for rate in [0, 4601, min_rate - 1, crate::MAX_SAMPLE_RATE + 1] {
+                match mode.validate_sample_rate(rate) {
+                    Err(SstvError::InvalidSampleRate { sample_rate, min_rate: min, max_rate }) => {
+                        assert_eq!((sample_rate, min, max_rate), (rate, min_rate, crate::MAX_SAMPLE_RATE));
+                    }
+                    other => panic!("{} {}Hz: {:?}", mode, rate, other),
+                }
+            }
+        }
+        assert_eq!(SstvMode::Robot36.get_min_sample_rate(), 7273);
+        assert_eq!(SstvMode::Robot36.get_default_sample_rate(), 8000);
+        assert_eq!(SstvMode::Pd120.get_min_sample_rate(), crate::MIN_SAMPLE_RATE);
+        assert_eq!(SstvMode::Pd120.get_default_sample_rate(), crate::DEFAULT_SAMPLE_RATE);
+        
+        // 设置采样率时立即校验
+        for rate in [0, 4000, crate::MIN_SAMPLE_RATE - 1] {
+            assert!(matches!(
+                SstvModulator::new(SstvMode::Robot36).with_sample_rate(rate),
+                Err(SstvError::InvalidSampleRate { .. })
+            ));
+        }
+        assert!(SstvModulator::new(SstvMode::Pd120).with_sample_rate(crate::DEFAULT_SAMPLE_RATE).is_ok());
+    }
+    
+    #[test]
+    fn test_timing_jitter_bounds_line_starts() {
+        let rate = 11025;
+        let jitter = SstvMode::Robot36.get_timing_jitter(rate);
+        assert!((jitter.peak_ms - 1000.0 / 11025.0).abs() < 1e-12);
+        assert!((jitter.samples_per_pixel * jitter.pixel_fraction - 1.0).abs() < 1e-12);
+        assert!(jitter.rms_ms < jitter.peak_ms);
+        
+        // 各扫描块起点与理想时刻（VIS码结束于1910ms，周期150ms）的偏差不超过最大抖动
+        let mut modulator = SstvModulator::new(SstvMode::Robot36).with_sample_rate(rate).unwrap();
+        modulator.modulate_image_to(&test_image(), &mut Vec::<f32>::new()).unwrap();
+        let starts = modulator.get_timeline().unwrap().line_starts();
+        assert_eq!(starts.len(), 240);
+        for (line, &start) in starts.iter().enumerate() {
+            let ideal = (1910.0 + line as f64 * 150.0) * rate as f64 / 1000.0;
+            let deviation_ms = (start as f64 - ideal).abs() * 1000.0 / rate as f64;
+            assert!(deviation_ms <= jitter.peak_ms, "line {}: {}ms", line, deviation_ms);
+        }
+    }
+    
+    #[test]
+    fn test_framing_options() {
+        let image = test_image();
+        let rate = 8000;
+        let mut plain = SstvModulator::new(SstvMode::Robot36).with_sample_rate(rate).unwrap().with_parallel_rendering(false);
+        let reference = plain.modulate_image(&image).unwrap();
+        let reference_timeline = plain.get_timeline().unwrap().clone();
+        let vis_start = reference_timeline.vis_start().unwrap();
+        let body = reference_timeline.line_starts()[0];
+        
+        // 半幅输出与满幅成比例，样本数不变
+        let mut quiet = SstvModulator::new(SstvMode::Robot36).with_sample_rate(rate).unwrap().with_amplitude(0.5);
+        quiet.modulate_image(&image).unwrap();
+        assert_eq!(quiet.get_samples().len(), reference.len());
+        for (a, b) in quiet.get_samples().iter().zip(plain.get_samples()).step_by(97) {
+            assert!((a * 2.0 - b).abs() < 1e-6);
+        }
+        
+        // 去掉VIS码、结束音和首尾静音后只剩扫描块
+        let mut bare = SstvModulator::new(SstvMode::Robot36)
+            .with_sample_rate(rate).unwrap()
+            .with_vis_header(false)
+            .with_end_tones(false)
+            .with_silence(0.0, 0.0);
+        let count = bare.modulate_image_to(&image, &mut Vec::<f32>::new()).unwrap();
+        let timeline = bare.get_timeline().unwrap();
+        assert!(timeline.vis_start().is_none());
+        assert_eq!(timeline.line_starts()[0], 0);
+        assert!(timeline.segments().iter().all(|segment| segment.kind != SegmentKind::EndTone && segment.kind != SegmentKind::Silence));
+        assert_eq!(count, bare.transmission_samples().unwrap());
+        
+        // 开头静音延长1秒，VIS码随之后移
+        let mut padded = SstvModulator::new(SstvMode::Robot36).with_sample_rate(rate).unwrap().with_silence(1200.0, 0.0);
+        padded.modulate_image(&image).unwrap();
+        assert_eq!(padded.get_timeline().unwrap().vis_start().unwrap(), vis_start + rate as usize);
+        assert_eq!(padded.get_timeline().unwrap().line_starts()[0], body + rate as usize);
+        assert_eq!(padded.get_samples().len(), padded.transmission_samples().unwrap());
+        
+        // 无效参数在调制时报错
+        let mut invalid = SstvModulator::new(SstvMode::Robot36).with_sample_rate(rate).unwrap().with_amplitude(2.0);
+        assert!(matches!(invalid.modulate_image(&image), Err(SstvError::InvalidAudioParameter { .. })));
+    }
+    
+    #[test]
+    fn test_modulator_config_round_trip() {
+        let config = EncoderConfig::new(SstvMode::MartinM1)
+            .with_sample_rate(11025)
+            .with_amplitude(0.7)
+            .with_silence(50.0, 0.0)
+            .with_end_tones(false)
+            .with_output_format(PcmFormat::Int24)
+            .with_dither(Dither::Tpdf)
+            .with_channel_routing(ChannelRouting::stereo_right(crate::audio::AuxChannel::PilotTone { frequency: 1000.0, amplitude: 0.5 }))
+            .with_tone_shaping(ToneShaping::new().with_ramps(5.0))
+            .with_frequency_mapping(FrequencyMapping::offset(-20.0))
+            .with_wav_metadata(true)
+            .with_callsign("VK7KSM");
+        let modulator = config.build().unwrap();
+        assert_eq!(modulator.get_config(), config);
+        assert_eq!(modulator.get_silence(), (50.0, 0.0));
+        assert!(!modulator.get_end_tones());
+        assert_eq!(SstvModulator::new(SstvMode::Pd120).get_config(), EncoderConfig::new(SstvMode::Pd120));
+        
+        let path = std::env::temp_dir().join(format!("sstv_preset_{}.json", std::process::id()));
+        config.save(&path).unwrap();
+        assert_eq!(EncoderConfig::load(&path).unwrap(), config);
+        std::fs::write(&path, r#"{ "mode": "PD120", "amplitude": 0 }"#).unwrap();
+        assert!(matches!(EncoderConfig::load(&path), Err(SstvError::InvalidAudioParameter { .. })));
+        std::fs::remove_file(path).ok();
+    }
+    
+    #[test]
+    fn test_mode_names_parse() {
+        for mode in ALL_MODES {
+            assert_eq!(mode.to_string().parse::<SstvMode>().unwrap(), mode);
+            assert_eq!(serde_json::to_value(mode).unwrap(), mode.get_mode_name());
+        }
+        for (name, mode) in [
+            ("PD120", SstvMode::Pd120),
+            ("pd-120", SstvMode::Pd120),
+            ("Robot 36", SstvMode::Robot36),
+            ("robot_36", SstvMode::Robot36),
+            ("Scottie DX", SstvMode::ScottieDx),
+            ("Martin M1", SstvMode::MartinM1),
+            ("m1", SstvMode::MartinM1),
+        ] {
+            assert_eq!(name.parse::<SstvMode>().unwrap(), mode, "{}", name);
+        }
+        assert!(matches!("PD90".parse::<SstvMode>(), Err(SstvError::UnsupportedMode { .. })));
+        assert!(serde_json::from_str::<SstvMode>("\"Robot 36\"").is_ok());
+        assert!(serde_json::from_str::<SstvMode>("\"Robot 72\"").is_err());
+    }
+
+    #[test]
+    fn test_text_overlay_in_processed_image() {
+        let overlay = TextOverlay::new("VK7KSM")
+            .with_position(crate::preprocess::OverlayPosition::BottomRight)
+            .with_background(Some([0, 255, 0]));
+        let mut modulator = SstvModulator::new(SstvMode::Robot36).with_text_overlay(overlay.clone());
+        modulator.modulate_image(&test_image()).unwrap();
+        let image = modulator.get_processed_image().unwrap();
+        let (width, height) = overlay.dimensions();
+        assert_eq!(*image.get_pixel(320 - 4 - width, 240 - 4 - height), image::Rgb([0, 255, 0]));
+
+        // 文字叠加随配置导出，从配置创建的调制器保持不变
+        let config = modulator.get_config();
+        assert_eq!(config.preprocess.overlays, vec![overlay]);
+        assert_eq!(SstvModulator::from_config(&config).unwrap().get_preprocess_options(), &config.preprocess);
+    }
+
+    #[test]
+    fn test_processing_metadata_sidecar_round_trip() {
+        let path = std::env::temp_dir().join(format!("sstv_sidecar_{}.png", std::process::id()));
+        let mut modulator = SstvModulator::new(SstvMode::Pd120)
+            .with_sample_rate(8000).unwrap()
+            .with_fit_mode(FitMode::CropToFill(crate::preprocess::CropAnchor::Top));
+        modulator.modulate_image(&test_image()).unwrap();
+        modulator.save_processed_image(&path).unwrap();
+
+        let sidecar_path = path.with_extension("json");
+        let loaded = ProcessingMetadata::load_sidecar(&sidecar_path).unwrap();
+        assert_eq!(Some(&loaded), modulator.get_processing_metadata());
+        assert!(loaded.crop_region.is_some());
+
+        // 保持原有的字段格式
+        let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&sidecar_path).unwrap()).unwrap();
+        let info = &value["sstv_processing_info"];
+        assert_eq!(info["sstv_mode"], "PD120");
+        assert_eq!(info["target_dimensions"], serde_json::json!({ "width": 640, "height": 496 }));
+        assert_eq!(info["fit_mode"], serde_json::json!({ "strategy": "crop_to_fill", "anchor": "top" }));
+        assert_eq!(info["sample_rate"], 8000);
+        assert_eq!(info["version"], crate::VERSION);
+        assert!(ProcessingMetadata::from_sidecar_json("{}").is_err());
+
+        let config = ImageSaveConfig { format: ImageFormat::Jpeg, ..ImageSaveConfig::default() };
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["format"], "jpg");
+        assert_eq!(serde_json::from_value::<ImageSaveConfig>(value).unwrap().format, ImageFormat::Jpeg);
+
+        std::fs::remove_file(path).ok();
+        std::fs::remove_file(sidecar_path).ok();
+    }
+
+    #[test]
+    fn test_load_baseline_sidecar() {
+        // 旧版本 save_metadata_file 写出的文件没有适配策略、滤波器、色彩和裁剪字段
+        let text = r#"{
+  "sstv_processing_info": {
+    "black_bars": { "bottom": 16, "left": 0, "right": 0, "top": 16 },
+    "duration_seconds": 36.0,
+    "original_dimensions": { "height": 600, "width": 800 },
+    "processing_timestamp": "2024-05-01 12:00:00 UTC",
+    "sample_rate": 6000,
+    "scale_factor": 0.4,
+    "sstv_mode": "Robot36",
+    "target_dimensions": { "height": 240, "width": 320 },
+    "version": "0.1.0"
+  }
+}"#;
+        let metadata = ProcessingMetadata::from_sidecar_json(text).unwrap();
+        assert_eq!(metadata.sstv_mode, SstvMode::Robot36);
+        assert_eq!(metadata.original_dimensions, (800, 600));
+        assert_eq!(metadata.black_bars, (0, 16, 0, 16));
+        assert_eq!(metadata.fit_mode, FitMode::default());
+        assert_eq!(metadata.resize_filter, ResizeFilter::default());
+        assert_eq!(metadata.color_adjustment, ColorAdjustment::default());
+        assert_eq!(metadata.crop_region, None);
+    }
+
+    #[test]
+    fn test_export_audio_formats() {
+        let image = test_image();
+        let mut modulator = SstvModulator::new(SstvMode::Robot36).with_sample_rate(8000).unwrap();
+        let expected: Vec<i32> = modulator.modulate_image(&image).unwrap().into_iter().map(i32::from).collect();
+
+        let dir = std::env::temp_dir();
+        let flac_path = dir.join(format!("sstv_export_{}.flac", std::process::id()));
+        let raw_path = dir.join(format!("sstv_export_{}.raw", std::process::id()));
+
+        #[cfg(feature = "flac")]
+        {
+            modulator.export_audio(&flac_path, AudioFormat::Flac).unwrap();
+            let mut reader = claxon::FlacReader::open(&flac_path).unwrap();
+            let decoded: Vec<i32> = reader.samples().map(|s| s.unwrap()).collect();
+            assert_eq!(decoded, expected);
+        }
+        #[cfg(not(feature = "flac"))]
+        assert!(matches!(modulator.export_audio(&flac_path, AudioFormat::Flac), Err(SstvError::InvalidFormat(_))));
+
+        modulator.export_audio(&raw_path, AudioFormat::Raw).unwrap();
+        let raw = std::fs::read(&raw_path).unwrap();
+        let decoded: Vec<i32> = raw.chunks(2).map(|c| i16::from_le_bytes([c[0], c[1]]) as i32).collect();
+        assert_eq!(decoded, expected);
+
+        std::fs::remove_file(flac_path).ok();
+        std::fs::remove_file(raw_path).ok();
+    }
+}
@@ -0,0 +1,401 @@
+//! 传输时间轴模块
+//!
+//! 调制时记录每一段音调的样本位置：静音、前导音、VIS码各位、同步脉冲、分隔音和每个颜色扫描，
+//! 用于对齐界面光标、调试接收端，或导出为JSON和WAV标记（`cue ` 与 `LIST adtl` 块）。
+//! 各段首尾相接，覆盖整个传输；样本位置以单声道样本（多声道导出时即帧）计。
+
+use crate::error::{Result, SstvError};
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// 时间轴段的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// 开头或结尾的静音
+    Silence,
+    /// VIS码之前的校准前导音（1900/1500/2300Hz交替）
+    Preamble,
+    /// VIS引导音（1900Hz 300ms）
+    VisLeader,
+    /// VIS引导音之间的间隔（1200Hz 10ms）
+    VisBreak,
+    /// VIS起始位
+    VisStartBit,
+    /// VIS数据位，参数为位序号（0为最低位）
+    VisBit(u8),
+    /// VIS偶校验位
+    VisParity,
+    /// VIS结束位
+    VisStopBit,
+    /// 同步脉冲
+    Sync,
+    /// 同步脉冲后或颜色扫描前的Porch音
+    Porch,
+    /// 颜色扫描之间的分隔音
+    Separator,
+    /// 颜色扫描
+    Scan,
+    /// 图像之后的结束音
+    EndTone,
+}
+
+impl SegmentKind {
+    /// JSON中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            SegmentKind::Silence => "silence",
+            SegmentKind::Preamble => "preamble",
+            SegmentKind::VisLeader => "vis_leader",
+            SegmentKind::VisBreak => "vis_break",
+            SegmentKind::VisStartBit => "vis_start_bit",
+            SegmentKind::VisBit(_) => "vis_bit",
+            SegmentKind::VisParity => "vis_parity",
+            SegmentKind::VisStopBit => "vis_stop_bit",
+            SegmentKind::Sync => "sync",
+            SegmentKind::Porch => "porch",
+            SegmentKind::Separator => "separator",
+            SegmentKind::Scan => "scan",
+            SegmentKind::EndTone => "end_tone",
+        }
+    }
+
+    /// 是否属于VIS码（引导音到结束位）
+    pub fn is_vis(&self) -> bool {
+        matches!(
+            self,
+            SegmentKind::VisLeader
+                | SegmentKind::VisBreak
+                | SegmentKind::VisStartBit
+                | SegmentKind::VisBit(_)
+                | SegmentKind::VisParity
+                | SegmentKind::VisStopBit
+        )
+    }
+}
+
+/// 颜色扫描的分量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+    /// 亮度Y
+    Luma,
+    /// 色差R-Y
+    RedDifference,
+    /// 色差B-Y
+    BlueDifference,
+}
+
+impl ColorChannel {
+    /// JSON中使用的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorChannel::Red => "red",
+            ColorChannel::Green => "green",
+            ColorChannel::Blue => "blue",
+            ColorChannel::Luma => "y",
+            ColorChannel::RedDifference => "r-y",
+            ColorChannel::BlueDifference => "b-y",
+        }
+    }
+}
+
+/// 时间轴上的一段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelineSegment {
+    /// 类型
+    pub kind: SegmentKind,
+    /// 扫描块序号（传输头和结束音为 `None`）
+    pub line: Option<u32>,
+    /// 图像行；色差分量为两行平均时取第一行
+    pub row: Option<u32>,
+    /// 颜色扫描的分量
+    pub channel: Option<ColorChannel>,
+    /// 起始样本序号
+    pub start: usize,
+    /// 样本数
+    pub length: usize,
+    /// 段内实际发出的最低频率（Hz，已应用频率映射）
+    pub min_frequency: f64,
+    /// 段内实际发出的最高频率（Hz）
+    pub max_frequency: f64,
+}
+
+impl TimelineSegment {
+    /// 结束样本序号（不含）
+    pub fn end(&self) -> usize {
+        self.start + self.length
+    }
+
+    fn to_json(self, sample_rate: u32) -> Value {
+        let mut value = json!({
+            "kind": self.kind.name(),
+            "start_sample": self.start,
+            "length": self.length,
+            "start_time": self.start as f64 / sample_rate as f64,
+            "duration": self.length as f64 / sample_rate as f64,
+            "min_frequency": self.min_frequency,
+            "max_frequency": self.max_frequency,
+        });
+        if let SegmentKind::VisBit(bit) = self.kind {
+            value["bit"] = bit.into();
+        }
+        if let Some(line) = self.line {
+            value["line"] = line.into();
+        }
+        if let Some(row) = self.row {
+            value["row"] = row.into();
+        }
+        if let Some(channel) = self.channel {
+            value["channel"] = channel.name().into();
+        }
+        value
+    }
+}
+
+/// 一次完整传输的时间轴
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    sample_rate: u32,
+    segments: Vec<TimelineSegment>,
+}
+
+impl Timeline {
+    pub(crate) fn new(sample_rate: u32, segments: Vec<TimelineSegment>) -> Self {
+        Self { sample_rate, segments }
+    }
+
+    /// 采样率
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// 全部段，按时间顺序
+    pub fn segments(&self) -> &[TimelineSegment] {
+        &self.segments
+    }
+
+    /// 传输总样本数
+    pub fn total_samples(&self) -> usize {
+        self.segments.last().map_or(0, |segment| segment.end())
+    }
+
+    /// 包含指定样本的段
+    pub fn segment_at(&self, sample: usize) -> Option<&TimelineSegment> {
+        let index = self.segments.partition_point(|segment| segment.end() <= sample);
+        self.segments.get(index).filter(|segment| segment.start <= sample)
+    }
+
+    /// VIS码起点（第一段引导音）的样本序号
+    pub fn vis_start(&self) -> Option<usize> {
+        self.segments.iter().find(|segment| segment.kind.is_vis()).map(|segment| segment.start)
+    }
+
+    /// 各扫描块的起始样本序号
+    pub fn line_starts(&self) -> Vec<usize> {
+        let mut starts = Vec::new();
+        for segment in &self.segments {
+            if let Some(line) = segment.line
+                && line as usize == starts.len()
+            {
+                starts.push(segment.start);
+            }
+        }
+        starts
+    }
+
+    /// 导出为JSON
+    pub fn to_json(&self) -> Value {
+        json!({
+            "sample_rate": self.sample_rate,
+            "total_samples": self.total_samples(),
+            "vis_start": self.vis_start(),
+            "line_starts": self.line_starts(),
+            "segments": self.segments.iter().map(|segment| segment.to_json(self.sample_rate)).collect::<Vec<_>>(),
+        })
+    }
+
+    /// 保存为JSON文件
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = serde_json::to_string_pretty(&self.to_json()).map_err(|e| SstvError::InvalidFormat(e.to_string()))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// WAV标记：VIS码起点和每个扫描块的起点，返回（名称, 起始样本, 样本数）
+    pub fn markers(&self) -> Vec<(String, usize, usize)> {
+        let mut markers = Vec::new();
+        if let Some(start) = self.vis_start() {
+            let end = self.segments.iter().filter(|segment| segment.kind.is_vis()).map(|s| s.end()).max().unwrap_or(start);
+            markers.push(("VIS".to_string(), start, end - start));
+        }
+        let starts = self.line_starts();
+        let image_end = self
+            .segments
+            .iter()
+            .rev()
+            .find(|segment| segment.line.is_some())
+            .map_or(0, |segment| segment.end());
+        for (line, &start) in starts.iter().enumerate() {
+            let end = starts.get(line + 1).copied().unwrap_or(image_end);
+            markers.push((format!("Line {}", line), start, end - start));
+        }
+        markers
+    }
+
+    /// WAV的 `cue ` 块和 `LIST adtl` 块（标签 `labl` 与区域长度 `ltxt`）
+    pub(crate) fn wav_cue_chunks(&self) -> Vec<([u8; 4], Vec<u8>)> {
+        let markers = self.markers();
+        let mut cue = Vec::with_capacity(4 + markers.len() * 24);
+        cue.extend((markers.len() as u32).to_le_bytes());
+        let mut adtl = b"adtl".to_vec();
+        for (index, (name, start, length)) in markers.iter().enumerate() {
+            let id = index as u32 + 1;
+            cue.extend(id.to_le_bytes());
+            cue.extend((*start as u32).to_le_bytes());
+            cue.extend(b"data");
+            cue.extend(0u32.to_le_bytes());
+            cue.extend(0u32.to_le_bytes());
+            cue.extend((*start as u32).to_le_bytes());
+
+            let mut label = id.to_le_bytes().to_vec();
+            label.extend(name.as_bytes());
+            label.push(0);
+            push_subchunk(&mut adtl, b"labl", &label);
+
+            let mut text = id.to_le_bytes().to_vec();
+            text.extend((*length as u32).to_le_bytes());
+            text.extend(b"rgn ");
+            // 国家、语言、方言、代码页
+            text.extend([0u8; 8]);
+            push_subchunk(&mut adtl, b"ltxt", &text);
+        }
+        vec![(*b"cue ", cue), (*b"LIST", adtl)]
+    }
+
+    /// 在已有WAV文件末尾追加 `cue ` 和 `LIST adtl` 标记块
+    pub fn write_wav_cues<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        crate::audio::append_wav_chunks(path.as_ref(), &self.wav_cue_chunks())
+    }
+}
+
+/// 追加子块，奇数长度补齐一个字节
+fn push_subchunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    out.extend(id);
+    out.extend((data.len() as u32).to_le_bytes());
+    out.extend(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/// 调制时逐段记录时间轴
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TimelineRecorder {
+    segments: Vec<TimelineSegment>,
+}
+
+impl TimelineRecorder {
+    /// 从 `start` 开始新的一段
+    pub(crate) fn begin(
+        &mut self,
+        kind: SegmentKind,
+        line: Option<u32>,
+        row: Option<u32>,
+        channel: Option<ColorChannel>,
+        start: usize,
+    ) {
+        self.segments.push(TimelineSegment {
+            kind,
+            line,
+            row,
+            channel,
+            start,
+            length: 0,
+            min_frequency: f64::INFINITY,
+            max_frequency: f64::NEG_INFINITY,
+        });
+    }
+
+    /// 当前段写入了一个音调，`end` 为写入后的样本计数
+    pub(crate) fn tone(&mut self, frequency: f64, end: usize) {
+        if let Some(segment) = self.segments.last_mut() {
+            segment.length = end - segment.start;
+            segment.min_frequency = segment.min_frequency.min(frequency);
+            segment.max_frequency = segment.max_frequency.max(frequency);
+        }
+    }
+
+    pub(crate) fn finish(self, sample_rate: u32) -> Timeline {
+        Timeline::new(sample_rate, self.segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_timeline() -> Timeline {
+        let mut recorder = TimelineRecorder::default();
+        recorder.begin(SegmentKind::Silence, None, None, None, 0);
+        recorder.tone(0.0, 100);
+        recorder.begin(SegmentKind::VisLeader, None, None, None, 100);
+        recorder.tone(1900.0, 400);
+        recorder.begin(SegmentKind::VisStopBit, None, None, None, 400);
+        recorder.tone(1200.0, 430);
+        for line in 0..2 {
+            let start = 430 + line as usize * 100;
+            recorder.begin(SegmentKind::Sync, Some(line), Some(line), None, start);
+            recorder.tone(1200.0, start + 10);
+            recorder.begin(SegmentKind::Scan, Some(line), Some(line), Some(ColorChannel::Luma), start + 10);
+            recorder.tone(1700.0, start + 60);
+            recorder.tone(2100.0, start + 100);
+        }
+        recorder.begin(SegmentKind::EndTone, None, None, None, 630);
+        recorder.tone(1500.0, 700);
+        recorder.finish(1000)
+    }
+
+    #[test]
+    fn test_timeline_queries() {
+        let timeline = sample_timeline();
+        assert_eq!(timeline.total_samples(), 700);
+        assert_eq!(timeline.vis_start(), Some(100));
+        assert_eq!(timeline.line_starts(), vec![430, 530]);
+        assert_eq!(timeline.segment_at(0).unwrap().kind, SegmentKind::Silence);
+        assert_eq!(timeline.segment_at(439).unwrap().kind, SegmentKind::Sync);
+        let scan = timeline.segment_at(440).unwrap();
+        assert_eq!((scan.kind, scan.channel, scan.length), (SegmentKind::Scan, Some(ColorChannel::Luma), 90));
+        assert_eq!((scan.min_frequency, scan.max_frequency), (1700.0, 2100.0));
+        assert!(timeline.segment_at(700).is_none());
+
+        let markers = timeline.markers();
+        assert_eq!(markers[0], ("VIS".to_string(), 100, 330));
+        assert_eq!(markers[2], ("Line 1".to_string(), 530, 100));
+
+        let json = timeline.to_json();
+        assert_eq!(json["segments"].as_array().unwrap().len(), 8);
+        assert_eq!(json["segments"][4]["channel"], "y");
+        assert_eq!(json["line_starts"][1], 530);
+    }
+
+    #[test]
+    fn test_wav_cue_chunks() {
+        let chunks = sample_timeline().wav_cue_chunks();
+        let (id, cue) = &chunks[0];
+        assert_eq!(id, b"cue ");
+        assert_eq!(cue.len(), 4 + 3 * 24);
+        assert_eq!(u32::from_le_bytes(cue[0..4].try_into().unwrap()), 3);
+        // 第二个标记（Line 0）的采样位置
+        assert_eq!(u32::from_le_bytes(cue[4 + 24 + 20..4 + 24 + 24].try_into().unwrap()), 430);
+        let (id, list) = &chunks[1];
+        assert_eq!(id, b"LIST");
+        assert_eq!(&list[..4], b"adtl");
+        assert_eq!(&list[4..8], b"labl");
+        // 标签 "VIS\0" 长度为8
+        assert_eq!(u32::from_le_bytes(list[8..12].try_into().unwrap()), 8);
+        assert_eq!(&list[16..20], b"VIS\0");
+        assert_eq!(list.len() % 2, 0);
+    }
+}